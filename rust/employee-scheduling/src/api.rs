@@ -1,38 +1,262 @@
 //! REST API handlers for Employee Scheduling.
 
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
     routing::{delete, get, post, put},
     Json, Router,
 };
-use chrono::{NaiveDate, NaiveDateTime};
-use parking_lot::RwLock;
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+use parking_lot::{Mutex, RwLock};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
 use crate::demo_data::{self, DemoData};
 use crate::domain::{Employee, EmployeeSchedule, Shift};
+use crate::job_store::{self, JobRepository, SolverStatus, StoredJob};
+use crate::metrics::{self, JobSample, SolveProgress};
+use solverforge::prelude::HardSoftDecimalScore;
+
+/// Minimum time between saving intermediate solutions.
+const SAVE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Environment variable limiting the number of schedules solved at once.
+pub const MAX_CONCURRENT_ENV: &str = "SOLVER_MAX_CONCURRENT";
+
+/// Environment variable capping the solving time of every schedule, in seconds.
+pub const MAX_WALL_TIME_ENV: &str = "SOLVER_MAX_WALL_TIME_SECS";
+
+/// Environment variable setting how long finished jobs are kept, in seconds.
+pub const JOB_TTL_ENV: &str = "SOLVER_JOB_TTL_SECS";
+
+/// Default cap on the solving time of a schedule: 30 minutes.
+const DEFAULT_MAX_WALL_TIME_SECS: u64 = 30 * 60;
+
+/// Default time finished jobs are kept: 24 hours.
+const DEFAULT_JOB_TTL_SECS: u64 = 24 * 3600;
+
+/// Time between sweeps for expired jobs.
+const EVICTION_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Service-wide limits on solving.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolverLimits {
+    /// Schedules solved at the same time; further jobs wait in a FIFO queue.
+    pub max_concurrent: usize,
+    /// Upper bound on the solving time of every schedule, on top of the
+    /// termination section of `solver.toml`.
+    pub max_wall_time: Duration,
+    /// Finished jobs are evicted this long after they stopped solving.
+    pub job_ttl: Duration,
+}
+
+impl SolverLimits {
+    /// Reads the limits from [`MAX_CONCURRENT_ENV`], [`MAX_WALL_TIME_ENV`]
+    /// and [`JOB_TTL_ENV`], defaulting to one job per available core,
+    /// 30 minutes per job and a day.
+    pub fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).ok().and_then(|v| v.parse::<u64>().ok());
+        Self {
+            max_concurrent: var(MAX_CONCURRENT_ENV)
+                .map(|n| n.max(1) as usize)
+                .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get())),
+            max_wall_time: Duration::from_secs(var(MAX_WALL_TIME_ENV).unwrap_or(DEFAULT_MAX_WALL_TIME_SECS)),
+            job_ttl: Duration::from_secs(var(JOB_TTL_ENV).unwrap_or(DEFAULT_JOB_TTL_SECS)),
+        }
+    }
+}
 
 /// Job tracking for active solves.
 struct SolveJob {
+    problem: ScheduleDto,
     solution: EmployeeSchedule,
-    solver_status: String,
+    solver_status: SolverStatus,
+    /// Solving was interrupted by a restart; the job can be resumed.
+    interrupted: bool,
+    /// When the job last stopped solving (or was loaded stopped).
+    finished_at: Option<Instant>,
+    /// When the job last started solving.
+    started_at: Option<Instant>,
+    /// Best solutions received since the job last started solving.
+    best_solutions: u64,
+    /// Local search progress since the job last started solving.
+    progress: Arc<SolveProgress>,
+    /// Best scores of the current (or last) solve, oldest first.
+    history: Vec<ScorePointDto>,
+    /// Stops the running solve when set.
+    terminate: Option<Arc<AtomicBool>>,
+}
+
+impl SolveJob {
+    /// Asks the running solve, if any, to stop.
+    fn stop(&self) {
+        if let Some(terminate) = &self.terminate {
+            terminate.store(true, Ordering::Relaxed);
+        }
+    }
+
+    fn to_stored(&self, id: &str) -> StoredJob {
+        StoredJob {
+            id: id.to_string(),
+            solver_status: self.solver_status,
+            interrupted: self.interrupted,
+            problem: self.problem.clone(),
+            solution: ScheduleDto::from_schedule(&self.solution, None),
+            updated_at: Utc::now(),
+        }
+    }
 }
 
 /// Application state shared across handlers.
+///
+/// At most [`SolverLimits::max_concurrent`] schedules solve at once; the
+/// others wait as `SOLVING_SCHEDULED` in a FIFO queue.
 pub struct AppState {
     jobs: RwLock<HashMap<String, SolveJob>>,
+    repository: Arc<dyn JobRepository>,
+    limits: SolverLimits,
+    /// Jobs waiting for a solver and the number of running solves.
+    queue: Mutex<QueueState>,
+    /// Held while a job file is written or deleted, so a save finishing
+    /// after a delete cannot bring the job back.
+    store_lock: Mutex<()>,
 }
 
 impl AppState {
+    /// Creates the state with the job repository and limits selected by
+    /// the environment (see [`job_store::from_env`] and
+    /// [`SolverLimits::from_env`]), reloading stored jobs.
     pub fn new() -> Self {
+        Self::with_repository(job_store::from_env(), SolverLimits::from_env())
+    }
+
+    /// Creates the state backed by `repository`, reloading the jobs stored
+    /// in it. Jobs that were solving when the service stopped keep their
+    /// best solution and are marked as interrupted until resumed.
+    pub fn with_repository(repository: Arc<dyn JobRepository>, limits: SolverLimits) -> Self {
+        let stored = repository.load_all().unwrap_or_else(|e| {
+            eprintln!("[job store] Failed to load stored jobs: {}", e);
+            Vec::new()
+        });
+
+        let mut jobs = HashMap::new();
+        for stored in stored {
+            let was_solving = stored.solver_status != SolverStatus::NotSolving;
+            let mut job = SolveJob {
+                solution: stored.solution.to_domain(),
+                problem: stored.problem,
+                solver_status: SolverStatus::NotSolving,
+                interrupted: stored.interrupted || was_solving,
+                finished_at: Some(Instant::now()),
+                started_at: None,
+                best_solutions: 0,
+                progress: Arc::default(),
+                history: Vec::new(),
+                terminate: None,
+            };
+            job.solution.score = Some(calculate_score(&job.solution));
+            if was_solving {
+                save(repository.as_ref(), &job.to_stored(&stored.id));
+            }
+            jobs.insert(stored.id, job);
+        }
+
         Self {
-            jobs: RwLock::new(HashMap::new()),
+            jobs: RwLock::new(jobs),
+            repository,
+            limits,
+            queue: Mutex::new(QueueState::default()),
+            store_lock: Mutex::new(()),
+        }
+    }
+
+    /// Removes jobs that finished longer than [`SolverLimits::job_ttl`] ago.
+    ///
+    /// Called whenever jobs are created or listed, and every
+    /// [`EVICTION_INTERVAL`].
+    fn evict_expired(&self) {
+        let expired: Vec<(String, SolveJob)> = {
+            let mut jobs = self.jobs.write();
+            let ids: Vec<String> = jobs
+                .iter()
+                .filter(|(_, job)| {
+                    job.solver_status == SolverStatus::NotSolving
+                        && job.finished_at.is_some_and(|t| t.elapsed() >= self.limits.job_ttl)
+                })
+                .map(|(id, _)| id.clone())
+                .collect();
+            ids.iter().filter_map(|id| jobs.remove_entry(id)).collect()
+        };
+        for (id, job) in expired {
+            job.stop();
+            self.remove_stored(&id);
+        }
+    }
+
+    /// Deletes the stored copy of a job already removed from `jobs`.
+    fn remove_stored(&self, id: &str) {
+        let _guard = self.store_lock.lock();
+        if let Err(e) = self.repository.remove(id) {
+            eprintln!("[job store] Failed to delete stored job {}: {}", id, e);
         }
     }
+
+    /// 1-based position of a job in the solve queue, if it is waiting.
+    fn queue_position(&self, id: &str) -> Option<usize> {
+        self.queue.lock().waiting.iter().position(|queued| queued.id == id).map(|p| p + 1)
+    }
+
+    /// Samples every job for the metrics endpoint.
+    fn job_samples(&self) -> Vec<JobSample> {
+        self.jobs
+            .read()
+            .iter()
+            .map(|(id, job)| JobSample {
+                id: id.clone(),
+                solver_status: job.solver_status,
+                score: job.solution.score.map(|s| format!("{}", s)),
+                best_solutions: job.best_solutions,
+                steps_accepted: job.progress.steps_accepted(),
+                moves_per_second: job.progress.moves_per_second(),
+                solving_time: job.started_at.map_or(Duration::ZERO, |start| {
+                    job.finished_at.unwrap_or_else(Instant::now).saturating_duration_since(start)
+                }),
+            })
+            .collect()
+    }
+}
+
+/// Scores a schedule with the fluent constraints.
+fn calculate_score(schedule: &EmployeeSchedule) -> HardSoftDecimalScore {
+    use crate::constraints::create_fluent_constraints;
+    use solverforge::TypedScoreDirector;
+
+    TypedScoreDirector::new(schedule.clone(), create_fluent_constraints()).calculate_score()
+}
+
+/// Saves a job, logging failures.
+fn save(repository: &dyn JobRepository, job: &StoredJob) {
+    if let Err(e) = repository.save(job) {
+        eprintln!("[job store] Failed to save job {}: {}", job.id, e);
+    }
+}
+
+/// Saves a job on the blocking pool, away from the jobs lock, unless it
+/// was deleted in the meantime.
+async fn persist(state: &Arc<AppState>, job: StoredJob) {
+    let state = state.clone();
+    let _ = tokio::task::spawn_blocking(move || {
+        let _guard = state.store_lock.lock();
+        if state.jobs.read().contains_key(&job.id) {
+            save(state.repository.as_ref(), &job);
+        }
+    })
+    .await;
 }
 
 impl Default for AppState {
@@ -41,6 +265,46 @@ impl Default for AppState {
     }
 }
 
+/// A job waiting for a solver.
+struct QueuedSolve {
+    id: String,
+    schedule: EmployeeSchedule,
+}
+
+/// FIFO queue feeding at most [`SolverLimits::max_concurrent`] solves.
+///
+/// Lock order: the queue before the jobs, never the other way around.
+#[derive(Default)]
+struct QueueState {
+    waiting: VecDeque<QueuedSolve>,
+    running: usize,
+}
+
+/// A running solve; frees its slot and starts the next job when dropped.
+struct SolverSlot(Arc<AppState>);
+
+impl Drop for SolverSlot {
+    fn drop(&mut self) {
+        self.0.queue.lock().running -= 1;
+        dispatch(&self.0);
+    }
+}
+
+/// Evicts expired jobs every [`EVICTION_INTERVAL`] while the state is alive.
+fn spawn_eviction(state: &Arc<AppState>) {
+    let state: Weak<AppState> = Arc::downgrade(state);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(EVICTION_INTERVAL);
+        loop {
+            interval.tick().await;
+            let Some(state) = state.upgrade() else {
+                break;
+            };
+            state.evict_expired();
+        }
+    });
+}
+
 // ============================================================================
 // DTOs
 // ============================================================================
@@ -114,7 +378,7 @@ pub struct ShiftDto {
 }
 
 /// Full schedule DTO for request/response.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ScheduleDto {
     pub employees: Vec<EmployeeDto>,
@@ -122,11 +386,11 @@ pub struct ScheduleDto {
     #[serde(default)]
     pub score: Option<String>,
     #[serde(default, skip_deserializing)]
-    pub solver_status: Option<String>,
+    pub solver_status: Option<SolverStatus>,
 }
 
 impl ScheduleDto {
-    pub fn from_schedule(schedule: &EmployeeSchedule, solver_status: Option<String>) -> Self {
+    pub fn from_schedule(schedule: &EmployeeSchedule, solver_status: Option<SolverStatus>) -> Self {
         let employees: Vec<EmployeeDto> = schedule.employees.iter().map(EmployeeDto::from).collect();
 
         let shifts: Vec<ShiftDto> = schedule
@@ -186,12 +450,14 @@ impl ScheduleDto {
 // Router and Handlers
 // ============================================================================
 
-/// Creates the API router.
+/// Creates the API router, evicting expired jobs in the background.
 pub fn router(state: Arc<AppState>) -> Router {
+    spawn_eviction(&state);
     Router::new()
         // Health & Info
         .route("/health", get(health))
         .route("/info", get(info))
+        .route("/metrics", get(get_metrics))
         // Demo data
         .route("/demo-data", get(list_demo_data))
         .route("/demo-data/{id}", get(get_demo_data))
@@ -202,6 +468,10 @@ pub fn router(state: Arc<AppState>) -> Router {
         .route("/schedules/{id}", get(get_schedule))
         .route("/schedules/{id}/status", get(get_schedule_status))
         .route("/schedules/{id}", delete(stop_solving))
+        .route("/schedules/{id}/resume", post(resume_solving))
+        .route("/schedules/{id}/history", get(get_score_history))
+        // Request counts and latencies for every route above
+        .route_layer(axum::middleware::from_fn(metrics::track_http))
         .with_state(state)
 }
 
@@ -236,6 +506,11 @@ async fn info() -> Json<InfoResponse> {
     })
 }
 
+/// GET /metrics - Prometheus metrics.
+async fn get_metrics(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, metrics::CONTENT_TYPE)], metrics::render(&state.job_samples()))
+}
+
 /// GET /demo-data - List available demo data sets.
 async fn list_demo_data() -> Json<Vec<&'static str>> {
     Json(demo_data::list_demo_data())
@@ -260,46 +535,140 @@ async fn create_schedule(
 ) -> String {
     let id = Uuid::new_v4().to_string();
     let schedule = dto.to_domain();
+    state.evict_expired();
 
     // Store initial state
-    {
+    let stored = {
         let mut jobs = state.jobs.write();
-        jobs.insert(id.clone(), SolveJob {
+        let job = SolveJob {
+            problem: ScheduleDto { solver_status: None, ..dto },
             solution: schedule.clone(),
-            solver_status: "SOLVING".to_string(),
-        });
-    }
+            solver_status: SolverStatus::SolvingScheduled,
+            interrupted: false,
+            finished_at: None,
+            started_at: None,
+            best_solutions: 0,
+            progress: Arc::default(),
+            history: Vec::new(),
+            terminate: None,
+        };
+        let stored = job.to_stored(&id);
+        jobs.insert(id.clone(), job);
+        stored
+    };
+    persist(&state, stored).await;
+
+    start_solving(state, id.clone(), schedule);
+    id
+}
 
-    // Start solving in background via library API
-    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
-    let job_id = id.clone();
-    let state_clone = state.clone();
+/// Queues a schedule for solving in the background once a solver slot is
+/// free.
+fn start_solving(state: Arc<AppState>, job_id: String, schedule: EmployeeSchedule) {
+    state.queue.lock().waiting.push_back(QueuedSolve { id: job_id, schedule });
+    dispatch(&state);
+}
 
-    tokio::spawn(async move {
-        while let Some((solution, _score)) = rx.recv().await {
-            let mut jobs = state_clone.jobs.write();
-            if let Some(job) = jobs.get_mut(&job_id) {
-                job.solution = solution;
-            }
-        }
-        // Channel closed - solver finished
-        let mut jobs = state_clone.jobs.write();
-        if let Some(job) = jobs.get_mut(&job_id) {
-            job.solver_status = "NOT_SOLVING".to_string();
-        }
-    });
+/// Starts waiting jobs, oldest first, while solver slots are free.
+fn dispatch(state: &Arc<AppState>) {
+    let mut queue = state.queue.lock();
+    while queue.running < state.limits.max_concurrent.max(1) {
+        let Some(solve) = queue.waiting.pop_front() else {
+            break;
+        };
+        let terminate = Arc::new(AtomicBool::new(false));
+        let progress = Arc::new(SolveProgress::default());
+        let stored = {
+            // Removed while waiting: move on to the next job
+            let mut jobs = state.jobs.write();
+            let Some(job) = jobs.get_mut(&solve.id) else {
+                continue;
+            };
+            job.solver_status = SolverStatus::Solving;
+            job.started_at = Some(Instant::now());
+            job.best_solutions = 0;
+            job.progress = progress.clone();
+            job.history.clear();
+            job.terminate = Some(terminate.clone());
+            job.to_stored(&solve.id)
+        };
+        queue.running += 1;
+        let slot = SolverSlot(state.clone());
+        tokio::spawn(run_solve(slot, solve, terminate, progress, stored));
+    }
+}
+
+/// Solves a dispatched schedule, saving the best solution at most once per
+/// [`SAVE_INTERVAL`] and when solving ends. Solving stops after
+/// [`SolverLimits::max_wall_time`] or when `terminate` is set; its local
+/// search progress is recorded in `progress`.
+async fn run_solve(
+    slot: SolverSlot,
+    solve: QueuedSolve,
+    terminate: Arc<AtomicBool>,
+    progress: Arc<SolveProgress>,
+    stored: StoredJob,
+) {
+    let state = slot.0.clone();
+    let QueuedSolve { id: job_id, schedule } = solve;
+    persist(&state, stored).await;
+    metrics::global().record_solve_started();
+
+    let timer = {
+        let terminate = terminate.clone();
+        let max_wall_time = state.limits.max_wall_time;
+        tokio::spawn(async move {
+            tokio::time::sleep(max_wall_time).await;
+            terminate.store(true, Ordering::Relaxed);
+        })
+    };
 
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
     // Solvable trait auto-implemented by #[planning_solution] macro
     use solverforge::Solvable;
     rayon::spawn(move || {
-        schedule.solve(None, tx);
+        progress.observe(|| schedule.solve(Some(&terminate), tx));
     });
 
-    id
+    let mut last_saved = Instant::now();
+    while let Some((solution, _score)) = rx.recv().await {
+        metrics::global().record_best_solution();
+        let stored = {
+            let mut jobs = state.jobs.write();
+            let Some(job) = jobs.get_mut(&job_id) else {
+                continue;
+            };
+            job.solution = solution;
+            job.best_solutions += 1;
+            if let Some(score) = job.solution.score {
+                let elapsed = job.started_at.map_or(Duration::ZERO, |start| start.elapsed());
+                job.history.push(ScorePointDto::new(elapsed, job.progress.steps_accepted(), format!("{}", score)));
+            }
+            (last_saved.elapsed() >= SAVE_INTERVAL).then(|| job.to_stored(&job_id))
+        };
+        if let Some(stored) = stored {
+            persist(&state, stored).await;
+            last_saved = Instant::now();
+        }
+    }
+    // Channel closed - solver finished, its slot is free again
+    timer.abort();
+    metrics::global().record_solve_completed();
+    let stored = state.jobs.write().get_mut(&job_id).map(|job| {
+        job.solver_status = SolverStatus::NotSolving;
+        job.finished_at = Some(Instant::now());
+        job.terminate = None;
+        job.to_stored(&job_id)
+    });
+    drop(slot);
+    if let Some(stored) = stored {
+        persist(&state, stored).await;
+    }
 }
 
 /// GET /schedules - List all schedule IDs.
 async fn list_schedules(State(state): State<Arc<AppState>>) -> Json<Vec<String>> {
+    state.evict_expired();
     Json(state.jobs.read().keys().cloned().collect())
 }
 
@@ -310,7 +679,7 @@ async fn get_schedule(
 ) -> Result<Json<ScheduleDto>, StatusCode> {
     match state.jobs.read().get(&id) {
         Some(job) => {
-            Ok(Json(ScheduleDto::from_schedule(&job.solution, Some(job.solver_status.clone()))))
+            Ok(Json(ScheduleDto::from_schedule(&job.solution, Some(job.solver_status))))
         }
         None => Err(StatusCode::NOT_FOUND),
     }
@@ -321,6 +690,11 @@ async fn get_schedule(
 #[serde(rename_all = "camelCase")]
 pub struct StatusResponse {
     pub score: Option<String>,
+    pub solver_status: SolverStatus,
+    pub interrupted: bool,
+    /// 1-based position in the solve queue while `SOLVING_SCHEDULED`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub queue_position: Option<usize>,
 }
 
 /// GET /schedules/{id}/status - Get a schedule's status.
@@ -328,10 +702,15 @@ async fn get_schedule_status(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
 ) -> Result<Json<StatusResponse>, StatusCode> {
+    // Before the jobs lock: the queue is always locked first
+    let queue_position = state.queue_position(&id);
     match state.jobs.read().get(&id) {
         Some(job) => {
             Ok(Json(StatusResponse {
                 score: job.solution.score.map(|s| format!("{}", s)),
+                solver_status: job.solver_status,
+                interrupted: job.interrupted,
+                queue_position,
             }))
         }
         None => Err(StatusCode::NOT_FOUND),
@@ -343,13 +722,122 @@ async fn stop_solving(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
 ) -> StatusCode {
-    if state.jobs.write().remove(&id).is_some() {
+    state.queue.lock().waiting.retain(|queued| queued.id != id);
+    let removed = state.jobs.write().remove(&id);
+    if let Some(job) = removed {
+        job.stop();
+        state.remove_stored(&id);
         StatusCode::NO_CONTENT
     } else {
         StatusCode::NOT_FOUND
     }
 }
 
+/// POST /schedules/{id}/resume - Continue solving a stopped schedule from
+/// its best solution, e.g. one interrupted by a restart.
+async fn resume_solving(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> StatusCode {
+    let (schedule, stored) = {
+        let mut jobs = state.jobs.write();
+        let Some(job) = jobs.get_mut(&id) else {
+            return StatusCode::NOT_FOUND;
+        };
+        if job.solver_status != SolverStatus::NotSolving {
+            return StatusCode::CONFLICT;
+        }
+        job.solver_status = SolverStatus::SolvingScheduled;
+        job.interrupted = false;
+        job.finished_at = None;
+        (job.solution.clone(), job.to_stored(&id))
+    };
+    persist(&state, stored).await;
+
+    start_solving(state, id, schedule);
+    StatusCode::ACCEPTED
+}
+
+/// A new best score published by the solver.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScorePointDto {
+    /// Milliseconds since solving started.
+    pub elapsed_ms: u64,
+    /// Local search steps accepted when the solution was received (0 for
+    /// the construction heuristic).
+    pub step: u64,
+    /// The new best score.
+    pub score: String,
+    /// Hard part of the score.
+    pub hard_score: Option<f64>,
+    /// Soft part of the score.
+    pub soft_score: Option<f64>,
+}
+
+impl ScorePointDto {
+    fn new(elapsed: Duration, step: u64, score: String) -> Self {
+        let parts = metrics::score_parts(&score);
+        Self {
+            elapsed_ms: elapsed.as_millis() as u64,
+            step,
+            hard_score: parts.map(|(hard, _)| hard),
+            soft_score: parts.map(|(_, soft)| soft),
+            score,
+        }
+    }
+}
+
+/// Query parameters for the best-score timeline.
+#[derive(Debug, Deserialize)]
+pub struct HistoryQuery {
+    /// Output format: `json` (default) or `csv`.
+    #[serde(default)]
+    pub format: HistoryFormat,
+}
+
+/// Best-score timeline output format.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+/// GET /schedules/{id}/history - Best scores of the current (or last)
+/// solve over time, as JSON or CSV (`?format=csv`).
+async fn get_score_history(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Response, StatusCode> {
+    let jobs = state.jobs.read();
+    let job = jobs.get(&id).ok_or(StatusCode::NOT_FOUND)?;
+    Ok(match query.format {
+        HistoryFormat::Json => Json(job.history.clone()).into_response(),
+        HistoryFormat::Csv => {
+            ([(header::CONTENT_TYPE, "text/csv; charset=utf-8")], history_csv(&job.history)).into_response()
+        }
+    })
+}
+
+/// Renders a best-score timeline as CSV with a header row.
+fn history_csv(history: &[ScorePointDto]) -> String {
+    let mut csv = String::from("elapsedMs,step,hardScore,softScore\n");
+    for point in history {
+        let part = |value: Option<f64>| value.map(|v| v.to_string()).unwrap_or_default();
+        csv.push_str(&format!(
+            "{},{},{},{}\n",
+            point.elapsed_ms,
+            point.step,
+            part(point.hard_score),
+            part(point.soft_score)
+        ));
+    }
+    csv
+}
+
 /// Constraint analysis result.
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -423,3 +911,86 @@ async fn analyze_schedule(
         constraints: constraints_dto,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::job_store::MemoryJobRepository;
+
+    fn limits(max_concurrent: usize) -> SolverLimits {
+        SolverLimits {
+            max_concurrent,
+            max_wall_time: Duration::from_secs(60),
+            job_ttl: Duration::from_secs(60),
+        }
+    }
+
+    fn status(state: &AppState, id: &str) -> Option<SolverStatus> {
+        state.jobs.read().get(id).map(|job| job.solver_status)
+    }
+
+    #[tokio::test]
+    async fn test_delete_frees_the_solver_slot() {
+        let state = Arc::new(AppState::with_repository(Arc::new(MemoryJobRepository::default()), limits(1)));
+        let dto = ScheduleDto::from_schedule(&demo_data::generate(DemoData::Small), None);
+
+        let first = create_schedule(State(state.clone()), Json(dto.clone())).await;
+        let second = create_schedule(State(state.clone()), Json(dto)).await;
+        assert_eq!(status(&state, &first), Some(SolverStatus::Solving));
+        assert_eq!(status(&state, &second), Some(SolverStatus::SolvingScheduled));
+        assert_eq!(state.queue_position(&second), Some(1));
+
+        // Deleting the running job stops its solver and starts the next one
+        assert_eq!(stop_solving(State(state.clone()), Path(first)).await, StatusCode::NO_CONTENT);
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while status(&state, &second) != Some(SolverStatus::Solving) {
+            assert!(Instant::now() < deadline, "queued job never started");
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(state.queue_position(&second), None);
+        stop_solving(State(state.clone()), Path(second)).await;
+    }
+
+    #[tokio::test]
+    async fn test_wall_time_stops_solving() {
+        let limits = SolverLimits { max_wall_time: Duration::ZERO, ..limits(1) };
+        let state = Arc::new(AppState::with_repository(Arc::new(MemoryJobRepository::default()), limits));
+        let dto = ScheduleDto::from_schedule(&demo_data::generate(DemoData::Small), None);
+
+        let id = create_schedule(State(state.clone()), Json(dto)).await;
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while status(&state, &id) != Some(SolverStatus::NotSolving) {
+            assert!(Instant::now() < deadline, "solve ran past the wall time");
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_save_after_delete_is_dropped() {
+        let repository = Arc::new(MemoryJobRepository::default());
+        let state = Arc::new(AppState::with_repository(repository.clone(), limits(1)));
+
+        let schedule = demo_data::generate(DemoData::Small);
+        let job = SolveJob {
+            problem: ScheduleDto::from_schedule(&schedule, None),
+            solution: schedule,
+            solver_status: SolverStatus::Solving,
+            interrupted: false,
+            finished_at: None,
+            started_at: Some(Instant::now()),
+            best_solutions: 0,
+            progress: Arc::default(),
+            history: Vec::new(),
+            terminate: None,
+        };
+        let stored = job.to_stored("job-1");
+        state.jobs.write().insert("job-1".to_string(), job);
+        persist(&state, stored.clone()).await;
+        assert_eq!(repository.load_all().unwrap().len(), 1);
+
+        // A save that was in flight when the job was deleted
+        assert_eq!(stop_solving(State(state.clone()), Path("job-1".to_string())).await, StatusCode::NO_CONTENT);
+        persist(&state, stored).await;
+        assert!(repository.load_all().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,224 @@
+//! Durable storage for schedule jobs.
+//!
+//! A [`JobRepository`] keeps each job's submitted problem, best solution
+//! (with its score) and solver status, so schedules survive a restart.
+//! [`FileJobRepository`] writes one JSON file per job to `.jobs/`
+//! (overridable with `JOBS_DIR`), replacing it atomically;
+//! [`MemoryJobRepository`] keeps nothing across restarts.
+
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::api::ScheduleDto;
+
+/// Default job directory.
+pub const JOBS_DIR: &str = ".jobs";
+
+/// Environment variable overriding the job directory.
+pub const JOBS_DIR_ENV: &str = "JOBS_DIR";
+
+/// Environment variable selecting the backend: `file` (default) or `memory`.
+pub const JOB_STORE_ENV: &str = "JOB_STORE";
+
+/// Status of a schedule job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SolverStatus {
+    /// Not currently solving.
+    NotSolving,
+    /// Waiting in the queue for a free solver.
+    SolvingScheduled,
+    /// Actively solving.
+    Solving,
+}
+
+impl SolverStatus {
+    /// Every status, in metrics output order.
+    pub const ALL: [SolverStatus; 3] = [SolverStatus::NotSolving, SolverStatus::SolvingScheduled, SolverStatus::Solving];
+
+    /// Returns the status as a SCREAMING_SNAKE_CASE string for API responses.
+    ///
+    /// ```
+    /// use employee_scheduling::job_store::SolverStatus;
+    ///
+    /// assert_eq!(SolverStatus::NotSolving.as_str(), "NOT_SOLVING");
+    /// assert_eq!(SolverStatus::SolvingScheduled.as_str(), "SOLVING_SCHEDULED");
+    /// assert_eq!(SolverStatus::Solving.as_str(), "SOLVING");
+    /// ```
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SolverStatus::NotSolving => "NOT_SOLVING",
+            SolverStatus::SolvingScheduled => "SOLVING_SCHEDULED",
+            SolverStatus::Solving => "SOLVING",
+        }
+    }
+}
+
+/// A job as stored in a repository.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoredJob {
+    /// Job identifier.
+    pub id: String,
+    /// Solver status when last saved.
+    pub solver_status: SolverStatus,
+    /// The service stopped while the job was solving; it can be resumed.
+    #[serde(default)]
+    pub interrupted: bool,
+    /// The schedule as submitted.
+    pub problem: ScheduleDto,
+    /// Best solution so far, including its score.
+    pub solution: ScheduleDto,
+    /// Time of the last save.
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Storage for schedule jobs.
+pub trait JobRepository: Send + Sync {
+    /// Stores a job, replacing any previous version.
+    fn save(&self, job: &StoredJob) -> io::Result<()>;
+
+    /// Deletes a job.
+    fn remove(&self, id: &str) -> io::Result<()>;
+
+    /// Loads every stored job.
+    fn load_all(&self) -> io::Result<Vec<StoredJob>>;
+}
+
+/// Creates the repository selected by [`JOB_STORE_ENV`] and [`JOBS_DIR_ENV`].
+pub fn from_env() -> Arc<dyn JobRepository> {
+    if std::env::var(JOB_STORE_ENV).as_deref() == Ok("memory") {
+        return Arc::new(MemoryJobRepository::default());
+    }
+    let dir = std::env::var_os(JOBS_DIR_ENV)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(JOBS_DIR));
+    Arc::new(FileJobRepository::new(dir))
+}
+
+/// Keeps jobs in memory only.
+#[derive(Default)]
+pub struct MemoryJobRepository {
+    jobs: Mutex<HashMap<String, StoredJob>>,
+}
+
+impl JobRepository for MemoryJobRepository {
+    fn save(&self, job: &StoredJob) -> io::Result<()> {
+        self.jobs.lock().insert(job.id.clone(), job.clone());
+        Ok(())
+    }
+
+    fn remove(&self, id: &str) -> io::Result<()> {
+        self.jobs.lock().remove(id);
+        Ok(())
+    }
+
+    fn load_all(&self) -> io::Result<Vec<StoredJob>> {
+        Ok(self.jobs.lock().values().cloned().collect())
+    }
+}
+
+/// Stores jobs as `{id}.json` files in a directory.
+pub struct FileJobRepository {
+    dir: PathBuf,
+}
+
+impl FileJobRepository {
+    /// Creates a repository storing jobs under `dir`.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// File of one job. Ids are generated UUIDs; anything that could escape
+    /// the job directory is rejected.
+    fn path(&self, id: &str) -> io::Result<PathBuf> {
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("invalid job id {:?}", id)));
+        }
+        Ok(self.dir.join(format!("{}.json", id)))
+    }
+}
+
+impl JobRepository for FileJobRepository {
+    fn save(&self, job: &StoredJob) -> io::Result<()> {
+        let path = self.path(&job.id)?;
+        fs::create_dir_all(&self.dir)?;
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec(job)?)?;
+        fs::rename(&tmp, &path)
+    }
+
+    fn remove(&self, id: &str) -> io::Result<()> {
+        match fs::remove_file(self.path(id)?) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        }
+    }
+
+    fn load_all(&self) -> io::Result<Vec<StoredJob>> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        let mut jobs = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                match read_job(&path) {
+                    Ok(job) => jobs.push(job),
+                    Err(e) => eprintln!("[job store] Skipping unreadable job {:?}: {}", path, e),
+                }
+            }
+        }
+        Ok(jobs)
+    }
+}
+
+fn read_job(path: &Path) -> io::Result<StoredJob> {
+    Ok(serde_json::from_slice(&fs::read(path)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::demo_data::{generate, DemoData};
+
+    #[test]
+    fn test_file_round_trip() {
+        let dir = std::env::temp_dir().join(format!("employee-jobs-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let repo = FileJobRepository::new(&dir);
+
+        let schedule = ScheduleDto::from_schedule(&generate(DemoData::Small), None);
+        let job = StoredJob {
+            id: "job-1".to_string(),
+            solver_status: SolverStatus::Solving,
+            interrupted: false,
+            problem: schedule.clone(),
+            solution: schedule,
+            updated_at: Utc::now(),
+        };
+        repo.save(&job).unwrap();
+
+        let loaded = repo.load_all().unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].id, "job-1");
+        assert_eq!(loaded[0].solver_status, SolverStatus::Solving);
+        assert_eq!(loaded[0].solution.shifts.len(), job.solution.shifts.len());
+
+        repo.remove("job-1").unwrap();
+        repo.remove("job-1").unwrap();
+        assert!(repo.load_all().unwrap().is_empty());
+        assert!(repo.remove("../escape").is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
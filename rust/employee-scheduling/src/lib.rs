@@ -9,3 +9,5 @@ pub mod api;
 pub mod constraints;
 pub mod demo_data;
 pub mod domain;
+pub mod job_store;
+pub mod metrics;
@@ -0,0 +1,551 @@
+//! Prometheus metrics.
+//!
+//! Process-wide counters and the HTTP latency histogram live in a global
+//! [`Metrics`] registry; per-job gauges are sampled from the application
+//! state when scraped. [`render`] writes everything in the Prometheus text
+//! exposition format.
+//!
+//! The solver runs inside solverforge, which publishes best solutions but
+//! reports its moves per second and accepted steps only as tracing events.
+//! [`SolveProgress::observe`] runs a solve with those events captured for
+//! the job, on top of whatever the global subscriber does with them.
+
+use axum::extract::{MatchedPath, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use parking_lot::Mutex;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::subscriber::Interest;
+use tracing::{Dispatch, Event, Metadata, Subscriber};
+
+use crate::job_store::SolverStatus;
+
+/// Content type of the text exposition format.
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Upper bounds of the HTTP latency buckets, in seconds.
+const HTTP_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Process-wide metrics.
+#[derive(Default)]
+pub struct Metrics {
+    solves_started: AtomicU64,
+    solves_completed: AtomicU64,
+    best_solutions: AtomicU64,
+    /// Request latency per method, route and status.
+    http: Mutex<BTreeMap<(String, String, u16), Histogram>>,
+}
+
+/// The global registry.
+pub fn global() -> &'static Metrics {
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+    METRICS.get_or_init(Metrics::default)
+}
+
+impl Metrics {
+    /// Counts a job that started solving.
+    pub fn record_solve_started(&self) {
+        self.solves_started.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts a job that stopped solving.
+    pub fn record_solve_completed(&self) {
+        self.solves_completed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts a new best solution published by a solver.
+    pub fn record_best_solution(&self) {
+        self.best_solutions.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records the latency of an HTTP request.
+    pub fn record_http(&self, method: &str, route: &str, status: u16, duration: Duration) {
+        self.http
+            .lock()
+            .entry((method.to_string(), route.to_string(), status))
+            .or_insert_with(|| Histogram::new(HTTP_BUCKETS))
+            .observe(duration.as_secs_f64());
+    }
+
+    fn write(&self, out: &mut String) {
+        let counter = |out: &mut String, name: &str, help: &str, value: &AtomicU64| {
+            header(out, name, help, "counter");
+            let _ = writeln!(out, "{} {}", name, value.load(Ordering::Relaxed));
+        };
+        counter(out, "scheduling_solves_started_total", "Jobs that started solving.", &self.solves_started);
+        counter(out, "scheduling_solves_completed_total", "Jobs that stopped solving.", &self.solves_completed);
+        counter(
+            out,
+            "scheduling_best_solutions_total",
+            "Best solutions published by all solvers.",
+            &self.best_solutions,
+        );
+
+        header(out, "scheduling_http_request_duration_seconds", "HTTP request latency.", "histogram");
+        for ((method, route, status), histogram) in self.http.lock().iter() {
+            let labels = format!(
+                "method=\"{}\",route=\"{}\",status=\"{}\"",
+                escape(method),
+                escape(route),
+                status
+            );
+            histogram.write(out, "scheduling_http_request_duration_seconds", &labels);
+        }
+    }
+}
+
+/// Cumulative histogram with fixed buckets.
+#[derive(Debug, Clone)]
+struct Histogram {
+    bounds: &'static [f64],
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            counts: vec![0; bounds.len()],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    fn observe(&mut self, value: f64) {
+        for (bound, count) in self.bounds.iter().zip(&mut self.counts) {
+            if value <= *bound {
+                *count += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+
+    fn write(&self, out: &mut String, name: &str, labels: &str) {
+        for (bound, count) in self.bounds.iter().zip(&self.counts) {
+            let _ = writeln!(out, "{}_bucket{{{},le=\"{}\"}} {}", name, labels, bound, count);
+        }
+        let _ = writeln!(out, "{}_bucket{{{},le=\"+Inf\"}} {}", name, labels, self.count);
+        let _ = writeln!(out, "{}_sum{{{}}} {}", name, labels, self.sum);
+        let _ = writeln!(out, "{}_count{{{}}} {}", name, labels, self.count);
+    }
+}
+
+/// Target prefix of the solver's tracing events.
+const SOLVER_TARGET: &str = "solverforge_solver";
+
+/// Index of the local search phase in the solver's phase events.
+const LOCAL_SEARCH_PHASE: u64 = 1;
+
+/// Local search progress of one solve, read from the solver's `step`,
+/// `progress` and `phase_end` events.
+#[derive(Debug, Default)]
+pub struct SolveProgress {
+    steps_accepted: AtomicU64,
+    moves_per_second: AtomicU64,
+}
+
+impl SolveProgress {
+    /// Runs `solve` on the current thread, recording the progress the
+    /// solver reports meanwhile. Events are still passed on to the
+    /// subscriber that was active before.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use employee_scheduling::metrics::SolveProgress;
+    /// use std::sync::Arc;
+    ///
+    /// let progress = Arc::new(SolveProgress::default());
+    /// progress.observe(|| {
+    ///     tracing::trace!(target: "solverforge_solver::phase", event = "step", step = 40u64, accepted = true);
+    ///     tracing::debug!(target: "solverforge_solver::phase", event = "progress", steps = 40u64, speed = 9000u64);
+    /// });
+    /// assert_eq!(progress.steps_accepted(), 40);
+    /// assert_eq!(progress.moves_per_second(), 9000);
+    /// ```
+    pub fn observe<R>(self: &Arc<Self>, solve: impl FnOnce() -> R) -> R {
+        let inner = tracing::dispatcher::get_default(Dispatch::clone);
+        let dispatch = Dispatch::new(ProgressSubscriber { inner, progress: self.clone() });
+        tracing::dispatcher::with_default(&dispatch, solve)
+    }
+
+    /// Moves accepted by the local search so far.
+    pub fn steps_accepted(&self) -> u64 {
+        self.steps_accepted.load(Ordering::Relaxed)
+    }
+
+    /// Moves evaluated per second: over the last second while solving,
+    /// over the whole local search once it ended.
+    pub fn moves_per_second(&self) -> u64 {
+        self.moves_per_second.load(Ordering::Relaxed)
+    }
+
+    fn record(&self, event: &Event<'_>) {
+        let mut fields = SolverEvent::default();
+        event.record(&mut fields);
+        match fields.kind {
+            // Every move the local search evaluates; accepted ones carry the step count
+            EventKind::Step if fields.accepted == Some(true) => {
+                if let Some(step) = fields.step {
+                    self.steps_accepted.store(step, Ordering::Relaxed);
+                }
+            }
+            EventKind::Progress => {
+                if let Some(speed) = fields.speed {
+                    self.moves_per_second.store(speed, Ordering::Relaxed);
+                }
+            }
+            EventKind::PhaseEnd if fields.phase_index == Some(LOCAL_SEARCH_PHASE) => {
+                if let Some(steps) = fields.steps {
+                    self.steps_accepted.store(steps, Ordering::Relaxed);
+                }
+                if let Some(speed) = fields.speed {
+                    self.moves_per_second.store(speed, Ordering::Relaxed);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Solver events carrying progress, by their `event` field.
+#[derive(Default, PartialEq)]
+enum EventKind {
+    #[default]
+    Other,
+    Step,
+    Progress,
+    PhaseEnd,
+}
+
+/// The fields of a solver event that carry progress.
+#[derive(Default)]
+struct SolverEvent {
+    kind: EventKind,
+    phase_index: Option<u64>,
+    step: Option<u64>,
+    accepted: Option<bool>,
+    steps: Option<u64>,
+    speed: Option<u64>,
+}
+
+impl Visit for SolverEvent {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "event" {
+            self.kind = match value {
+                "step" => EventKind::Step,
+                "progress" => EventKind::Progress,
+                "phase_end" => EventKind::PhaseEnd,
+                _ => EventKind::Other,
+            };
+        }
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        if field.name() == "accepted" {
+            self.accepted = Some(value);
+        }
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        match field.name() {
+            "phase_index" => self.phase_index = Some(value),
+            "step" => self.step = Some(value),
+            "steps" => self.steps = Some(value),
+            "speed" => self.speed = Some(value),
+            _ => {}
+        }
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        if let Ok(value) = u64::try_from(value) {
+            self.record_u64(field, value);
+        }
+    }
+
+    fn record_debug(&mut self, _field: &Field, _value: &dyn std::fmt::Debug) {}
+}
+
+/// Records solver events into a [`SolveProgress`] and forwards everything
+/// to the subscriber that was active before.
+struct ProgressSubscriber {
+    inner: Dispatch,
+    progress: Arc<SolveProgress>,
+}
+
+fn is_solver_event(metadata: &Metadata<'_>) -> bool {
+    metadata.is_event() && metadata.target().starts_with(SOLVER_TARGET)
+}
+
+impl Subscriber for ProgressSubscriber {
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        let interest = self.inner.register_callsite(metadata);
+        if is_solver_event(metadata) {
+            Interest::sometimes()
+        } else {
+            interest
+        }
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        is_solver_event(metadata) || self.inner.enabled(metadata)
+    }
+
+    fn max_level_hint(&self) -> Option<tracing::level_filters::LevelFilter> {
+        None
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        self.inner.new_span(span)
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        self.inner.record(span, values)
+    }
+
+    fn record_follows_from(&self, span: &Id, follows: &Id) {
+        self.inner.record_follows_from(span, follows)
+    }
+
+    fn event(&self, event: &Event<'_>) {
+        if is_solver_event(event.metadata()) {
+            self.progress.record(event);
+        }
+        if self.inner.enabled(event.metadata()) {
+            self.inner.event(event);
+        }
+    }
+
+    fn enter(&self, span: &Id) {
+        self.inner.enter(span)
+    }
+
+    fn exit(&self, span: &Id) {
+        self.inner.exit(span)
+    }
+
+    fn clone_span(&self, id: &Id) -> Id {
+        self.inner.clone_span(id)
+    }
+
+    fn try_close(&self, id: Id) -> bool {
+        self.inner.try_close(id)
+    }
+}
+
+/// Point-in-time view of one job for the per-job gauges.
+#[derive(Debug, Clone)]
+pub struct JobSample {
+    /// Job identifier.
+    pub id: String,
+    /// Solver status.
+    pub solver_status: SolverStatus,
+    /// Best score, formatted like `-2hard/-15soft`.
+    pub score: Option<String>,
+    /// Best solutions published since the job last started solving.
+    pub best_solutions: u64,
+    /// Moves accepted by the local search since the job last started.
+    pub steps_accepted: u64,
+    /// Moves evaluated per second by the local search.
+    pub moves_per_second: u64,
+    /// Time spent solving since the job last started.
+    pub solving_time: Duration,
+}
+
+impl JobSample {
+    /// Best solutions published per second of solving.
+    fn best_solutions_per_second(&self) -> f64 {
+        let secs = self.solving_time.as_secs_f64();
+        if secs > 0.0 {
+            self.best_solutions as f64 / secs
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Renders the global metrics and the per-job gauges of `jobs`.
+///
+/// # Examples
+///
+/// ```
+/// use employee_scheduling::job_store::SolverStatus;
+/// use employee_scheduling::metrics::{render, JobSample};
+/// use std::time::Duration;
+///
+/// let jobs = [JobSample {
+///     id: "job-1".to_string(),
+///     solver_status: SolverStatus::Solving,
+///     score: Some("-2hard/-15.5soft".to_string()),
+///     best_solutions: 4,
+///     steps_accepted: 1200,
+///     moves_per_second: 85000,
+///     solving_time: Duration::from_secs(2),
+/// }];
+///
+/// let text = render(&jobs);
+/// assert!(text.contains("scheduling_jobs{status=\"SOLVING\"} 1"));
+/// assert!(text.contains("scheduling_job_best_hard_score{job=\"job-1\"} -2"));
+/// assert!(text.contains("scheduling_job_best_soft_score{job=\"job-1\"} -15.5"));
+/// assert!(text.contains("scheduling_job_best_solutions_per_second{job=\"job-1\"} 2"));
+/// assert!(text.contains("scheduling_job_steps_accepted{job=\"job-1\"} 1200"));
+/// assert!(text.contains("scheduling_job_moves_per_second{job=\"job-1\"} 85000"));
+/// ```
+pub fn render(jobs: &[JobSample]) -> String {
+    let mut out = String::new();
+    global().write(&mut out);
+
+    let mut jobs: Vec<&JobSample> = jobs.iter().collect();
+    jobs.sort_by(|a, b| a.id.cmp(&b.id));
+
+    header(&mut out, "scheduling_jobs", "Jobs by solver status.", "gauge");
+    for status in SolverStatus::ALL {
+        let count = jobs.iter().filter(|job| job.solver_status == status).count();
+        let _ = writeln!(out, "scheduling_jobs{{status=\"{}\"}} {}", status.as_str(), count);
+    }
+
+    type Gauge = (&'static str, &'static str, fn(&JobSample) -> Option<f64>);
+    let gauges: [Gauge; 6] = [
+        (
+            "scheduling_job_best_solutions_per_second",
+            "Best solutions published per second of solving.",
+            |job| Some(job.best_solutions_per_second()),
+        ),
+        (
+            "scheduling_job_best_solutions",
+            "Best solutions published by the job's solver.",
+            |job| Some(job.best_solutions as f64),
+        ),
+        (
+            "scheduling_job_steps_accepted",
+            "Moves accepted by the job's local search.",
+            |job| Some(job.steps_accepted as f64),
+        ),
+        (
+            "scheduling_job_moves_per_second",
+            "Moves evaluated per second by the job's local search.",
+            |job| Some(job.moves_per_second as f64),
+        ),
+        ("scheduling_job_best_hard_score", "Hard part of the job's best score.", |job| {
+            score_parts(job.score.as_deref()?).map(|(hard, _)| hard)
+        }),
+        ("scheduling_job_best_soft_score", "Soft part of the job's best score.", |job| {
+            score_parts(job.score.as_deref()?).map(|(_, soft)| soft)
+        }),
+    ];
+    for (name, help, value) in gauges {
+        header(&mut out, name, help, "gauge");
+        for job in &jobs {
+            if let Some(value) = value(job) {
+                let _ = writeln!(out, "{}{{job=\"{}\"}} {}", name, escape(&job.id), value);
+            }
+        }
+    }
+    out
+}
+
+/// Splits a formatted score like `-2hard/-15.5soft` into its hard and soft
+/// parts.
+pub(crate) fn score_parts(score: &str) -> Option<(f64, f64)> {
+    let (hard, soft) = score.split_once('/')?;
+    let hard = hard.trim().strip_suffix("hard")?.parse().ok()?;
+    let soft = soft.trim().strip_suffix("soft")?.parse().ok()?;
+    Some((hard, soft))
+}
+
+/// Middleware recording the latency of routed requests, labelled with the
+/// route template so ids don't multiply the series.
+pub async fn track_http(request: Request, next: Next) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or_else(|| "unmatched".to_string(), |p| p.as_str().to_string());
+    let method = request.method().to_string();
+    let start = Instant::now();
+    let response = next.run(request).await;
+    global().record_http(&method, &route, response.status().as_u16(), start.elapsed());
+    response
+}
+
+fn header(out: &mut String, name: &str, help: &str, kind: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+/// Escapes a label value.
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score_parts() {
+        assert_eq!(score_parts("0hard/-12soft"), Some((0.0, -12.0)));
+        assert_eq!(score_parts("-1.5hard/3.25soft"), Some((-1.5, 3.25)));
+        assert_eq!(score_parts("-3"), None);
+    }
+
+    #[test]
+    fn test_unparsable_score_omitted() {
+        let jobs = [JobSample {
+            id: "job-1".to_string(),
+            solver_status: SolverStatus::NotSolving,
+            score: None,
+            best_solutions: 0,
+            steps_accepted: 0,
+            moves_per_second: 0,
+            solving_time: Duration::ZERO,
+        }];
+        let out = render(&jobs);
+        assert!(out.contains("scheduling_jobs{status=\"NOT_SOLVING\"} 1"));
+        assert!(out.contains("scheduling_job_best_solutions_per_second{job=\"job-1\"} 0"));
+        assert!(!out.contains("scheduling_job_best_hard_score{"));
+    }
+
+    #[test]
+    fn test_solve_progress_recorded() {
+        use solverforge::Solvable;
+        use std::sync::atomic::AtomicBool;
+
+        let progress = Arc::new(SolveProgress::default());
+        let terminate = Arc::new(AtomicBool::new(false));
+        let stopper = {
+            let terminate = terminate.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(300));
+                terminate.store(true, Ordering::Relaxed);
+            })
+        };
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        let schedule = crate::demo_data::generate(crate::demo_data::DemoData::Small);
+        progress.observe(|| schedule.solve(Some(&terminate), tx));
+        stopper.join().unwrap();
+
+        assert!(progress.steps_accepted() > 0);
+        assert!(progress.moves_per_second() > 0);
+    }
+
+    #[test]
+    fn test_http_histogram() {
+        let metrics = Metrics::default();
+        metrics.record_http("GET", "/schedules/{id}", 200, Duration::from_millis(30));
+        let mut out = String::new();
+        metrics.write(&mut out);
+        let labels = "method=\"GET\",route=\"/schedules/{id}\",status=\"200\"";
+        assert!(out.contains(&format!("scheduling_http_request_duration_seconds_bucket{{{},le=\"0.025\"}} 0", labels)));
+        assert!(out.contains(&format!("scheduling_http_request_duration_seconds_bucket{{{},le=\"0.05\"}} 1", labels)));
+        assert!(out.contains(&format!("scheduling_http_request_duration_seconds_count{{{}}} 1", labels)));
+    }
+}
@@ -4,29 +4,44 @@
 //! - Demo data retrieval
 //! - Route plan management (create, get, stop)
 //! - Route geometry for map visualization
+//! - GeoJSON, GPX and KML export of solved routes
+//! - Printable driver manifests
+//! - Best-score timelines as JSON or CSV
+//! - Prometheus metrics at /metrics
 //! - Swagger UI at /q/swagger-ui
 
 use axum::{
     body::Body,
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::{header, StatusCode},
     response::{IntoResponse, Response},
     routing::{delete, get, post, put},
     Json, Router,
 };
-use chrono::{NaiveDateTime, NaiveTime};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{Arc, Weak};
 use tower_http::cors::{Any, CorsLayer};
-use utoipa::{OpenApi, ToSchema};
+use utoipa::{IntoParams, OpenApi, ToSchema};
 use utoipa_swagger_ui::SwaggerUi;
 use uuid::Uuid;
 
 use crate::demo_data::{available_datasets, generate_by_name};
-use crate::domain::{Vehicle, VehicleRoutePlan, Visit};
-use crate::geometry::{encode_routes, EncodedSegment};
-use crate::solver::{SolverConfig, SolverService, SolverStatus};
+use crate::constraints::unassigned_reason;
+use crate::domain::{
+    default_horizon_start, RoutingObjective, UnassignedReason, Vehicle, VehicleRoutePlan, Visit, VisitKind, PRIMARY_DIMENSION,
+};
+use crate::export::{export, ExportFormat};
+use crate::geometry::{encode_legs, encode_routes_simplified, EncodedLeg, EncodedSegment};
+use crate::job_store;
+use crate::manifest::{build_manifests, render_html, Manifest, ManifestFormat, ManifestStop};
+use crate::matrix::{HttpTableProvider, MatrixFileProvider, MatrixProvider, OsmProvider, HaversineProvider, TableApi};
+use crate::metrics;
+use crate::recommendation::{rank_batch, rank_insertions, Insertion};
+use crate::road_cache::{self, CachedNetworkInfo};
+use crate::solver::{history_csv, SolverConfig, SolverLimits, SolverService, SolverStatus};
+use crate::traffic::{SpeedBucket, SpeedProfile, TimeDependentTimes};
 use solverforge::prelude::HardSoftScore;
 use std::time::Duration;
 
@@ -34,10 +49,8 @@ use std::time::Duration;
 // Date/Time Utilities
 // ============================================================================
 
-/// Reference date for time calculations (matches Python frontend).
-const BASE_DATE: &str = "2025-01-05";
-
-/// Converts seconds from midnight to ISO datetime string.
+/// Converts seconds from the default horizon start (midnight of 2025-01-05,
+/// matching the frontend) to ISO datetime string.
 ///
 /// # Examples
 ///
@@ -47,15 +60,13 @@ const BASE_DATE: &str = "2025-01-05";
 /// assert_eq!(seconds_to_iso(0), "2025-01-05T00:00:00");
 /// assert_eq!(seconds_to_iso(8 * 3600), "2025-01-05T08:00:00");
 /// assert_eq!(seconds_to_iso(8 * 3600 + 30 * 60 + 45), "2025-01-05T08:30:45");
+/// assert_eq!(seconds_to_iso(25 * 3600), "2025-01-06T01:00:00"); // Past midnight
 /// ```
 pub fn seconds_to_iso(seconds: i64) -> String {
-    let hours = (seconds / 3600) % 24;
-    let mins = (seconds % 3600) / 60;
-    let secs = seconds % 60;
-    format!("{}T{:02}:{:02}:{:02}", BASE_DATE, hours, mins, secs)
+    offset_to_iso(default_horizon_start(), seconds)
 }
 
-/// Parses ISO datetime string to seconds from midnight.
+/// Parses ISO datetime string to seconds from the default horizon start.
 ///
 /// # Examples
 ///
@@ -64,25 +75,89 @@ pub fn seconds_to_iso(seconds: i64) -> String {
 ///
 /// assert_eq!(iso_to_seconds("2025-01-05T08:00:00"), 8 * 3600);
 /// assert_eq!(iso_to_seconds("2025-01-05T08:30:45"), 8 * 3600 + 30 * 60 + 45);
+/// assert_eq!(iso_to_seconds("2025-01-06T01:00:00"), 25 * 3600);
 /// ```
 pub fn iso_to_seconds(iso: &str) -> i64 {
-    if let Ok(dt) = NaiveDateTime::parse_from_str(iso, "%Y-%m-%dT%H:%M:%S") {
-        let midnight = NaiveDateTime::new(dt.date(), NaiveTime::from_hms_opt(0, 0, 0).unwrap());
-        (dt - midnight).num_seconds()
-    } else {
-        0
+    iso_to_offset(default_horizon_start(), utc(), iso)
+}
+
+/// Formats seconds from `horizon_start` as ISO datetime string.
+pub fn offset_to_iso(horizon_start: NaiveDateTime, offset: i64) -> String {
+    (horizon_start + chrono::Duration::seconds(offset))
+        .format("%Y-%m-%dT%H:%M:%S")
+        .to_string()
+}
+
+/// Parses an ISO datetime string to seconds from `horizon_start`, a
+/// wall-clock time in `timezone` (0 if invalid).
+pub fn iso_to_offset(horizon_start: NaiveDateTime, timezone: FixedOffset, iso: &str) -> i64 {
+    parse_iso_datetime(iso, timezone)
+        .map(|dt| (dt - horizon_start).num_seconds())
+        .unwrap_or(0)
+}
+
+/// The UTC offset, used when no datetime in a plan carries one.
+fn utc() -> FixedOffset {
+    FixedOffset::east_opt(0).expect("zero offset is valid")
+}
+
+/// Returns the UTC offset (`Z`, `+02:00`) an ISO datetime carries, if any.
+pub fn iso_utc_offset(iso: &str) -> Option<FixedOffset> {
+    DateTime::parse_from_rfc3339(iso.trim()).ok().map(|dt| *dt.offset())
+}
+
+/// Parses an ISO 8601 datetime to wall-clock time in `timezone`.
+///
+/// Accepts optional seconds and fractional seconds and a bare date
+/// (midnight). A datetime with a UTC offset (`Z`, `+02:00`) is converted to
+/// `timezone`; one without is taken to be in `timezone` already.
+///
+/// # Examples
+///
+/// ```
+/// use chrono::FixedOffset;
+/// use vehicle_routing::api::parse_iso_datetime;
+///
+/// let cet = FixedOffset::east_opt(3600).unwrap();
+/// let expected = parse_iso_datetime("2026-03-14T09:30:00", cet).unwrap();
+/// assert_eq!(parse_iso_datetime("2026-03-14T09:30", cet), Some(expected));
+/// assert_eq!(parse_iso_datetime("2026-03-14T09:30:00.000", cet), Some(expected));
+/// assert_eq!(parse_iso_datetime("2026-03-14T09:30:00+01:00", cet), Some(expected));
+/// assert_eq!(parse_iso_datetime("2026-03-14T08:30:00Z", cet), Some(expected));
+/// assert_eq!(parse_iso_datetime("2026-03-14T10:30:00+02:00", cet), Some(expected));
+/// assert_eq!(parse_iso_datetime("2026-03-14", cet).unwrap().to_string(), "2026-03-14 00:00:00");
+/// assert_eq!(parse_iso_datetime("not a date", cet), None);
+/// ```
+pub fn parse_iso_datetime(iso: &str, timezone: FixedOffset) -> Option<NaiveDateTime> {
+    let iso = iso.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(iso) {
+        return Some(dt.with_timezone(&timezone).naive_local());
     }
+    ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(iso, fmt).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(iso, "%Y-%m-%d")
+                .ok()
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+        })
 }
 
+/// Time between sweeps for expired jobs.
+const EVICTION_INTERVAL: Duration = Duration::from_secs(60);
+
 /// Application state shared across handlers.
 pub struct AppState {
     pub solver: SolverService,
 }
 
 impl AppState {
+    /// Creates the state with the job repository and solver limits
+    /// selected by the environment (see [`job_store::from_env`] and
+    /// [`SolverLimits::from_env`]), reloading stored jobs.
     pub fn new() -> Self {
         Self {
-            solver: SolverService::new(),
+            solver: SolverService::with_repository(job_store::from_env()).with_limits(SolverLimits::from_env()),
         }
     }
 }
@@ -93,9 +168,26 @@ impl Default for AppState {
     }
 }
 
-/// Creates the API router with CORS and Swagger UI enabled.
+/// Evicts expired jobs every [`EVICTION_INTERVAL`] while the state is alive.
+fn spawn_eviction(state: &Arc<AppState>) {
+    let state: Weak<AppState> = Arc::downgrade(state);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(EVICTION_INTERVAL);
+        loop {
+            interval.tick().await;
+            let Some(state) = state.upgrade() else {
+                break;
+            };
+            state.solver.evict_expired();
+        }
+    });
+}
+
+/// Creates the API router with CORS and Swagger UI enabled, evicting
+/// expired jobs in the background.
 pub fn create_router() -> Router {
     let state = Arc::new(AppState::new());
+    spawn_eviction(&state);
 
     let cors = CorsLayer::new()
         .allow_origin(Any)
@@ -106,6 +198,7 @@ pub fn create_router() -> Router {
         // Health & Info
         .route("/health", get(health))
         .route("/info", get(info))
+        .route("/metrics", get(get_metrics))
         // Demo data
         .route("/demo-data", get(list_demo_data))
         .route("/demo-data/{name}", get(get_demo_data))
@@ -116,11 +209,22 @@ pub fn create_router() -> Router {
         .route("/route-plans/{id}", get(get_route_plan))
         .route("/route-plans/{id}/status", get(get_route_plan_status))
         .route("/route-plans/{id}", delete(stop_solving))
+        .route("/route-plans/{id}/resume", post(resume_solving))
+        .route("/route-plans/{id}/history", get(get_score_history))
         .route("/route-plans/{id}/geometry", get(get_route_geometry))
+        .route("/route-plans/{id}/export", get(export_route_plan))
+        .route("/route-plans/{id}/manifests", get(get_manifests))
         // Analysis and recommendations
         .route("/route-plans/analyze", put(analyze_route_plan))
         .route("/route-plans/recommendation", post(recommend_assignment))
+        .route("/route-plans/recommendation/batch", post(recommend_batch))
         .route("/route-plans/recommendation/apply", post(apply_recommendation))
+        // Road network cache
+        .route("/road-networks", get(list_road_networks))
+        .route("/road-networks", delete(purge_road_networks))
+        .route("/road-networks/{key}", delete(purge_road_network))
+        // Request counts and latencies for every route above
+        .route_layer(axum::middleware::from_fn(metrics::track_http))
         // Swagger UI at /q/swagger-ui (Quarkus-style path)
         .merge(SwaggerUi::new("/q/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi()))
         .layer(cors)
@@ -174,6 +278,72 @@ async fn info() -> Json<InfoResponse> {
     })
 }
 
+/// GET /metrics - Prometheus metrics.
+///
+/// Solve counts and throughput, per-job best scores, cache hit rates,
+/// matrix build times and HTTP latencies in the text exposition format.
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    responses((status = 200, description = "Metrics in Prometheus text format", body = String, content_type = "text/plain"))
+)]
+async fn get_metrics(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, metrics::CONTENT_TYPE)], metrics::render(&state.solver))
+}
+
+// ============================================================================
+// Road Network Cache
+// ============================================================================
+
+/// Result of purging cached road networks.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PurgeResponse {
+    /// Number of cache files removed.
+    pub removed: usize,
+}
+
+/// GET /road-networks - List cached road networks.
+#[utoipa::path(
+    get,
+    path = "/road-networks",
+    responses((status = 200, description = "Cached road networks, most recently used first", body = Vec<CachedNetworkInfo>))
+)]
+async fn list_road_networks() -> Json<Vec<CachedNetworkInfo>> {
+    Json(road_cache::list().await)
+}
+
+/// DELETE /road-networks - Purge all cached road networks.
+#[utoipa::path(
+    delete,
+    path = "/road-networks",
+    responses((status = 200, description = "Cache purged", body = PurgeResponse))
+)]
+async fn purge_road_networks() -> Json<PurgeResponse> {
+    Json(PurgeResponse {
+        removed: road_cache::purge(None).await.files,
+    })
+}
+
+/// DELETE /road-networks/{key} - Purge one cached road network.
+#[utoipa::path(
+    delete,
+    path = "/road-networks/{key}",
+    params(("key" = String, Path, description = "Cache key")),
+    responses(
+        (status = 204, description = "Network purged from memory and disk"),
+        (status = 404, description = "Not found")
+    )
+)]
+async fn purge_road_network(Path(key): Path<String>) -> StatusCode {
+    let purged = road_cache::purge(Some(&key)).await;
+    if purged == road_cache::Purged::default() {
+        StatusCode::NOT_FOUND
+    } else {
+        StatusCode::NO_CONTENT
+    }
+}
+
 // ============================================================================
 // Demo Data
 // ============================================================================
@@ -213,8 +383,8 @@ async fn get_demo_data(Path(name): Path<String>) -> Result<Json<RoutePlanDto>, S
 ///
 /// Progress phases:
 /// - `network` (0-15%): Loading road network from cache or downloading
-/// - `matrix` (15-75%): Computing travel time matrix (Dijkstra per location)
-/// - `geometry` (75-95%): Computing route geometries for visualization
+/// - `matrix` (15-95%): Computing travel time matrix and route geometries
+///   (one parallel Dijkstra per location)
 /// - `complete` (100%): Ready
 async fn get_demo_data_stream(Path(name): Path<String>) -> impl IntoResponse {
     use crate::routing::{BoundingBox, RoadNetwork};
@@ -282,21 +452,21 @@ async fn get_demo_data_stream(Path(name): Path<String>) -> impl IntoResponse {
             }
         };
 
-        // Phase 2: Matrix computation (15-75%) via channel for real-time progress
+        // Phase 2: Matrices and geometries (15-95%) via channel for real-time progress
         let (matrix_tx, mut matrix_rx) = tokio::sync::mpsc::unbounded_channel::<(usize, usize)>();
         let network_for_matrix = std::sync::Arc::clone(&network);
         let coords_for_matrix = coords.clone();
 
         let matrix_handle = tokio::task::spawn_blocking(move || {
-            network_for_matrix.compute_matrix_with_progress(&coords_for_matrix, |row, total| {
+            network_for_matrix.compute_routes_with_progress(&coords_for_matrix, |row, total| {
                 let _ = matrix_tx.send((row, total));
             })
         });
 
         // Stream matrix progress
         while let Some((row, total)) = matrix_rx.recv().await {
-            // Progress from 15% to 75% (60% range)
-            let pct = 15 + (row + 1) * 60 / total;
+            // Progress from 15% to 95% (80% range)
+            let pct = 15 + (row + 1) * 80 / total;
             yield Ok(format!(
                 "data: {{\"event\":\"progress\",\"phase\":\"matrix\",\"message\":\"Computing routes\",\"percent\":{},\"detail\":\"{}/{} locations\"}}\n\n",
                 pct, row + 1, total
@@ -304,7 +474,7 @@ async fn get_demo_data_stream(Path(name): Path<String>) -> impl IntoResponse {
         }
 
         // Get matrix result
-        let matrix = match matrix_handle.await {
+        let (matrices, geometries) = match matrix_handle.await {
             Ok(m) => m,
             Err(e) => {
                 tracing::error!("Matrix computation failed: {}", e);
@@ -319,37 +489,8 @@ async fn get_demo_data_stream(Path(name): Path<String>) -> impl IntoResponse {
                 return;
             }
         };
-        plan.travel_time_matrix = matrix;
-
-        // Phase 3: Geometry computation (75-95%) via channel
-        let (geo_tx, mut geo_rx) = tokio::sync::mpsc::unbounded_channel::<(usize, usize)>();
-        let network_for_geo = std::sync::Arc::clone(&network);
-        let coords_for_geo = coords.clone();
-
-        let geo_handle = tokio::task::spawn_blocking(move || {
-            network_for_geo.compute_all_geometries_with_progress(&coords_for_geo, |row, total| {
-                let _ = geo_tx.send((row, total));
-            })
-        });
-
-        // Stream geometry progress
-        while let Some((row, total)) = geo_rx.recv().await {
-            // Progress from 75% to 95% (20% range)
-            let pct = 75 + (row + 1) * 20 / total;
-            yield Ok(format!(
-                "data: {{\"event\":\"progress\",\"phase\":\"geometry\",\"message\":\"Generating routes\",\"percent\":{},\"detail\":\"{}/{} paths\"}}\n\n",
-                pct, row + 1, total
-            ));
-        }
-
-        // Get geometry result
-        let geometries = match geo_handle.await {
-            Ok(g) => g,
-            Err(e) => {
-                tracing::error!("Geometry computation failed: {}", e);
-                std::collections::HashMap::new()
-            }
-        };
+        plan.travel_time_matrix = matrices.times;
+        plan.distance_matrix = matrices.distances;
         plan.route_geometries = geometries;
 
         // Build response DTO
@@ -392,12 +533,46 @@ pub struct VisitDto {
     pub name: String,
     /// Location as `[latitude, longitude]`.
     pub location: [f64; 2],
-    /// Quantity demanded.
-    pub demand: i32,
+    /// Quantity demanded: a single number, or named dimensions
+    /// (e.g. `{"weight": 120, "volume": 2}`).
+    pub demand: CapacityDto,
+    /// "DELIVERY" (default), "PICKUP" or "RELOAD" (depot reload stop).
+    #[serde(default)]
+    pub kind: VisitKind,
+    /// ID of the other half of a pickup-and-delivery pair.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paired_visit: Option<String>,
+    /// Capabilities the serving vehicle must have.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub required_capabilities: Vec<String>,
+    /// IDs of the only vehicles allowed to serve this visit (empty = any).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_vehicles: Vec<String>,
+    /// IDs of vehicles that must not serve this visit.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub forbidden_vehicles: Vec<String>,
+    /// ID of the preferred vehicle (driver); every shift of it counts as preferred.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preferred_vehicle: Option<String>,
+    /// Soft penalty when not served by the preferred vehicle.
+    #[serde(default)]
+    pub preferred_vehicle_weight: i64,
+    /// Whether the solver may leave this visit unassigned.
+    #[serde(default)]
+    pub optional: bool,
+    /// Importance of an optional visit (higher = dropped less readily).
+    #[serde(default = "default_priority")]
+    pub priority: i64,
     /// Earliest service start time (ISO datetime).
     pub min_start_time: String,
     /// Latest service end time (ISO datetime).
     pub max_end_time: String,
+    /// Time service should finish by; later is penalized softly (ISO datetime).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub soft_deadline: Option<String>,
+    /// Soft penalty per minute past `softDeadline`.
+    #[serde(default = "default_lateness_cost")]
+    pub lateness_cost_per_minute: i64,
     /// Service duration in seconds.
     pub service_duration: i32,
     /// Assigned vehicle ID (null if unassigned).
@@ -423,6 +598,109 @@ pub struct VisitDto {
     pub driving_time_seconds_from_previous_standstill: Option<i32>,
 }
 
+fn default_priority() -> i64 {
+    1
+}
+
+fn default_lateness_cost() -> i64 {
+    1
+}
+
+/// A visit left off every route, with the reason it was dropped.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UnassignedVisitDto {
+    /// Visit ID.
+    pub visit: String,
+    /// Why no vehicle serves it.
+    pub reason: UnassignedReason,
+}
+
+/// Capacity or demand, either as a single quantity or per named dimension.
+///
+/// The single-number form is the primary quantity. In the named form the
+/// primary quantity uses the key `"quantity"`; other keys become capacity
+/// dimensions of the plan.
+///
+/// # Examples
+///
+/// ```
+/// use vehicle_routing::api::CapacityDto;
+///
+/// let single: CapacityDto = serde_json::from_str("25").unwrap();
+/// assert_eq!(single, CapacityDto::Single(25));
+///
+/// let named: CapacityDto = serde_json::from_str(r#"{"weight": 1000, "pallets": 6}"#).unwrap();
+/// assert!(matches!(named, CapacityDto::Named(ref m) if m["pallets"] == 6));
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(untagged)]
+pub enum CapacityDto {
+    /// Primary quantity only.
+    Single(i32),
+    /// Quantities per dimension name.
+    Named(BTreeMap<String, i32>),
+}
+
+impl Default for CapacityDto {
+    fn default() -> Self {
+        CapacityDto::Single(0)
+    }
+}
+
+impl CapacityDto {
+    /// Builds the DTO form from a primary quantity and per-dimension values.
+    ///
+    /// Uses the single-number form when the plan has no named dimensions.
+    /// Values equal to `missing` are omitted from the named form.
+    fn from_domain(primary: i32, values: &[i32], dimensions: &[String], missing: i32) -> Self {
+        if dimensions.is_empty() {
+            return CapacityDto::Single(primary);
+        }
+
+        let mut named = BTreeMap::new();
+        if primary != missing {
+            named.insert(PRIMARY_DIMENSION.to_string(), primary);
+        }
+        for (dimension, name) in dimensions.iter().enumerate() {
+            let value = values.get(dimension).copied().unwrap_or(missing);
+            if value != missing {
+                named.insert(name.clone(), value);
+            }
+        }
+        CapacityDto::Named(named)
+    }
+
+    /// Splits into the primary quantity and values for `dimensions`.
+    ///
+    /// Quantities absent from the named form take the `missing` value.
+    fn to_domain(&self, dimensions: &[String], missing: i32) -> (i32, Vec<i32>) {
+        match self {
+            CapacityDto::Single(primary) => (*primary, vec![missing; dimensions.len()]),
+            CapacityDto::Named(named) => {
+                let primary = named.get(PRIMARY_DIMENSION).copied().unwrap_or(missing);
+                let values = dimensions
+                    .iter()
+                    .map(|d| named.get(d).copied().unwrap_or(missing))
+                    .collect();
+                (primary, values)
+            }
+        }
+    }
+
+    /// Returns the named dimensions (excluding the primary quantity).
+    fn dimension_names(&self) -> impl Iterator<Item = &String> {
+        let named = match self {
+            CapacityDto::Single(_) => None,
+            CapacityDto::Named(named) => Some(named.keys()),
+        };
+        named
+            .into_iter()
+            .flatten()
+            .filter(|k| k.as_str() != PRIMARY_DIMENSION)
+    }
+}
+
 /// Vehicle DTO matching Python API structure.
 ///
 /// Visits are referenced by ID only; full visit data is in the plan's `visits` array.
@@ -433,22 +711,180 @@ pub struct VehicleDto {
     pub id: String,
     /// Vehicle name for display.
     pub name: String,
-    /// Maximum capacity.
-    pub capacity: i32,
-    /// Home depot location as `[latitude, longitude]`.
+    /// Maximum capacity: a single number, or named dimensions
+    /// (e.g. `{"weight": 1000, "volume": 12, "pallets": 6}`).
+    pub capacity: CapacityDto,
+    /// Home depot location (route start) as `[latitude, longitude]`.
     pub home_location: [f64; 2],
+    /// Route end as `[latitude, longitude]`, if not back at `homeLocation`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_location: Option<[f64; 2]>,
+    /// Open route: the vehicle does not return after its last visit.
+    #[serde(default)]
+    pub open_route: bool,
     /// Departure time from depot (ISO datetime).
     pub departure_time: String,
-    /// Visit IDs in route order.
+    /// Latest time the route may end (ISO datetime).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shift_end: Option<String>,
+    /// Working shifts over a multi-day horizon (input only).
+    ///
+    /// When given, the vehicle is planned as one route per shift and
+    /// `departureTime`/`shiftEnd` are ignored; any `visits` are assigned to
+    /// the first shift.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shifts: Vec<ShiftDto>,
+    /// Fixed cost incurred when the vehicle is used.
+    #[serde(default)]
+    pub fixed_cost: i64,
+    /// Variable cost per kilometer driven.
+    #[serde(default)]
+    pub cost_per_km: i64,
+    /// Variable cost per hour on the road.
+    #[serde(default)]
+    pub cost_per_hour: i64,
+    /// Fuel burned per 100 km, used for emissions reporting.
+    #[serde(default, rename = "fuelPer100Km")]
+    pub fuel_per_100km: f64,
+    /// kg of CO2 emitted per unit of fuel.
+    #[serde(default, rename = "co2KgPerFuelUnit")]
+    pub co2_kg_per_fuel_unit: f64,
+    /// Capabilities of the vehicle (e.g. "refrigerated", "tail-lift").
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    /// How many times the vehicle may return to its depot to reload.
+    #[serde(default)]
+    pub max_reloads: usize,
+    /// Time spent at the depot per reload, in seconds.
+    #[serde(default)]
+    pub reload_duration: i64,
+    /// Visit IDs in route order (including reload stops).
     pub visits: Vec<String>,
     /// Total demand of assigned visits.
     pub total_demand: i32,
+    /// Highest on-board load at any point of the route.
+    #[serde(default)]
+    pub peak_load: i32,
     /// Total driving time in seconds.
     pub total_driving_time_seconds: i32,
-    /// Arrival time back at depot (ISO datetime).
+    /// Total road distance in meters.
+    #[serde(default)]
+    pub total_distance_meters: i64,
+    /// Estimated CO2 emissions of the route in kg.
+    #[serde(default)]
+    pub emissions_kg: f64,
+    /// Total cost of the route (0 if unused).
+    #[serde(default)]
+    pub total_cost: i64,
+    /// Arrival time at the end location, or end of the last visit for
+    /// open routes (ISO datetime).
     pub arrival_time: String,
 }
 
+/// A vehicle's working shift.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ShiftDto {
+    /// Shift start, i.e. departure from the depot (ISO datetime).
+    pub start: String,
+    /// Latest time the route may end (ISO datetime).
+    pub end: String,
+}
+
+/// Environment variable naming the directory matrix files are read from.
+pub const MATRIX_DIR_ENV: &str = "MATRIX_DIR";
+
+/// Default directory for matrix files.
+const DEFAULT_MATRIX_DIR: &str = "matrices";
+
+/// Environment variable listing the table service base URLs plans may use,
+/// comma-separated. Without it, `HTTP_TABLE` providers are rejected.
+pub const TABLE_SERVICE_URLS_ENV: &str = "TABLE_SERVICE_URLS";
+
+/// Whether `url` is one of the `allowed` base URLs or a path below one.
+///
+/// # Examples
+///
+/// ```
+/// use vehicle_routing::api::table_url_allowed;
+///
+/// let allowed = "http://osrm:5000, https://valhalla.example.com/";
+/// assert!(table_url_allowed("http://osrm:5000", allowed));
+/// assert!(table_url_allowed("https://valhalla.example.com/route", allowed));
+/// assert!(!table_url_allowed("http://osrm:50001", allowed));
+/// assert!(!table_url_allowed("http://169.254.169.254/latest", allowed));
+/// assert!(!table_url_allowed("http://osrm:5000", ""));
+/// ```
+pub fn table_url_allowed(url: &str, allowed: &str) -> bool {
+    allowed
+        .split(',')
+        .map(|base| base.trim().trim_end_matches('/'))
+        .filter(|base| !base.is_empty())
+        .any(|base| {
+            url.strip_prefix(base)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        })
+}
+
+/// Where travel times for a plan come from.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum MatrixProviderDto {
+    /// Straight-line distances at 50 km/h.
+    Haversine,
+    /// Local OSM road graph (default).
+    Osm,
+    /// Precomputed JSON matrix file, relative to the matrix directory.
+    MatrixFile {
+        /// File name inside the matrix directory.
+        path: String,
+    },
+    /// OSRM- or Valhalla-compatible table service.
+    HttpTable {
+        /// Base URL of the service.
+        url: String,
+        /// API flavor ("OSRM" or "VALHALLA").
+        #[serde(default)]
+        api: TableApi,
+        /// OSRM profile or Valhalla costing model.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        profile: Option<String>,
+    },
+}
+
+impl MatrixProviderDto {
+    /// Builds the provider, rejecting matrix file paths that leave the
+    /// matrix directory and table services not listed in
+    /// [`TABLE_SERVICE_URLS_ENV`].
+    pub fn to_provider(&self) -> Result<Box<dyn MatrixProvider>, String> {
+        match self {
+            MatrixProviderDto::Haversine => Ok(Box::new(HaversineProvider)),
+            MatrixProviderDto::Osm => Ok(Box::new(OsmProvider)),
+            MatrixProviderDto::MatrixFile { path } => {
+                let relative = std::path::Path::new(path);
+                let escapes = relative
+                    .components()
+                    .any(|c| !matches!(c, std::path::Component::Normal(_)));
+                if path.is_empty() || escapes {
+                    return Err(format!("invalid matrix file path {:?}", path));
+                }
+                let dir = std::env::var(MATRIX_DIR_ENV).unwrap_or_else(|_| DEFAULT_MATRIX_DIR.to_string());
+                Ok(Box::new(MatrixFileProvider::new(std::path::Path::new(&dir).join(relative))))
+            }
+            MatrixProviderDto::HttpTable { url, api, profile } => {
+                if !(url.starts_with("http://") || url.starts_with("https://")) {
+                    return Err(format!("invalid table service URL {:?}", url));
+                }
+                let allowed = std::env::var(TABLE_SERVICE_URLS_ENV).unwrap_or_default();
+                if !table_url_allowed(url, &allowed) {
+                    return Err(format!("table service URL {:?} is not in {}", url, TABLE_SERVICE_URLS_ENV));
+                }
+                Ok(Box::new(HttpTableProvider::new(url.clone(), *api, profile.clone())))
+            }
+        }
+    }
+}
+
 /// Termination configuration for the solver.
 ///
 /// Supports multiple termination conditions that combine with OR logic.
@@ -481,6 +917,10 @@ pub struct RoutePlanDto {
     pub south_west_corner: [f64; 2],
     /// North-east corner of bounding box as `[latitude, longitude]`.
     pub north_east_corner: [f64; 2],
+    /// Start of the planning horizon (ISO datetime). Defaults to midnight of
+    /// the earliest date in the plan.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub horizon_start: Option<String>,
     /// Earliest vehicle departure time (ISO datetime).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub start_date_time: Option<String>,
@@ -489,10 +929,34 @@ pub struct RoutePlanDto {
     pub end_date_time: Option<String>,
     /// Total driving time across all vehicles in seconds.
     pub total_driving_time_seconds: i32,
+    /// Total road distance across all vehicles in meters.
+    #[serde(default)]
+    pub total_distance_meters: i64,
+    /// Estimated CO2 emissions across all vehicles in kg.
+    #[serde(default)]
+    pub total_emissions_kg: f64,
+    /// Total cost across all vehicles.
+    #[serde(default)]
+    pub total_cost: i64,
+    /// Number of vehicles with a non-empty route.
+    #[serde(default)]
+    pub vehicles_used: usize,
+    /// Soft objective to minimize ("TRAVEL_TIME", "COST" or "DISTANCE").
+    #[serde(default)]
+    pub objective: RoutingObjective,
+    /// Penalize every used vehicle to reduce fleet size.
+    #[serde(default)]
+    pub minimize_vehicles_used: bool,
+    /// Soft penalty per minute a vehicle waits for a time window to open.
+    #[serde(default)]
+    pub waiting_cost_per_minute: i64,
     /// All vehicles.
     pub vehicles: Vec<VehicleDto>,
     /// All visits (assigned and unassigned).
     pub visits: Vec<VisitDto>,
+    /// Visits not on any route, with the reason they were dropped.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unassigned_visits: Vec<UnassignedVisitDto>,
     /// Current score (e.g., "0hard/-14400soft").
     #[serde(skip_serializing_if = "Option::is_none")]
     pub score: Option<String>,
@@ -503,9 +967,27 @@ pub struct RoutePlanDto {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub termination: Option<TerminationConfigDto>,
     /// Precomputed travel time matrix (optional, from real roads).
-    /// Row/column order: depot locations first, then visit locations.
+    /// Row/column order: depot locations first (unique start and end
+    /// locations in vehicle order), then visit locations.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub travel_time_matrix: Option<Vec<Vec<i64>>>,
+    /// Source of travel times used when solving (input only, defaults to
+    /// the OSM road graph).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matrix_provider: Option<MatrixProviderDto>,
+    /// Time-of-day speed profile. When set, travel times depend on the
+    /// departure time; otherwise a single static matrix is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speed_profile: Option<SpeedProfile>,
+    /// Precomputed travel times per speed profile bucket, same order as
+    /// `travelTimeMatrix`. Without them a provided matrix is used for
+    /// every departure time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_dependent_times: Option<TimeDependentTimes>,
+    /// Precomputed road distance matrix in meters, same order as
+    /// `travelTimeMatrix`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub distance_matrix: Option<Vec<Vec<i64>>>,
 }
 
 impl RoutePlanDto {
@@ -521,6 +1003,9 @@ impl RoutePlanDto {
             }
         }
 
+        // Format times relative to the plan's horizon
+        let iso = |offset: i64| offset_to_iso(plan.horizon_start, offset);
+
         // Build visit ID lookup for next/previous references
         let visit_id = |idx: usize| -> String { format!("v{}", idx) };
 
@@ -528,16 +1013,15 @@ impl RoutePlanDto {
         let mut visit_timings: HashMap<usize, (i64, i64, i64, i32)> = HashMap::new(); // (arrival, service_start, departure, driving_time)
         for v in &plan.vehicles {
             let timings = plan.calculate_route_times(v);
-            let mut prev_loc = v.home_location.index;
+            let mut prev_departure = v.departure_time;
 
             for timing in timings.iter() {
-                let driving_time = plan.travel_time(prev_loc, plan.visits[timing.visit_idx].location.index);
-                let service_start = timing.arrival.max(plan.visits[timing.visit_idx].min_start_time);
+                let driving_time = timing.arrival - prev_departure;
                 visit_timings.insert(
                     timing.visit_idx,
-                    (timing.arrival, service_start, timing.departure, driving_time as i32),
+                    (timing.arrival, timing.service_start, timing.departure, driving_time as i32),
                 );
-                prev_loc = plan.visits[timing.visit_idx].location.index;
+                prev_departure = timing.departure;
             }
         }
 
@@ -567,16 +1051,32 @@ impl RoutePlanDto {
                     id: visit_id(visit.index),
                     name: visit.name.clone(),
                     location: [loc.latitude, loc.longitude],
-                    demand: visit.demand,
-                    min_start_time: seconds_to_iso(visit.min_start_time),
-                    max_end_time: seconds_to_iso(visit.max_end_time),
+                    demand: CapacityDto::from_domain(
+                        visit.demand,
+                        &visit.dimension_demands,
+                        &plan.capacity_dimensions,
+                        0,
+                    ),
+                    kind: visit.kind,
+                    paired_visit: visit.paired_visit.map(visit_id),
+                    required_capabilities: visit.required_capabilities.iter().cloned().collect(),
+                    allowed_vehicles: visit.allowed_vehicles.iter().map(|id| id.to_string()).collect(),
+                    forbidden_vehicles: visit.forbidden_vehicles.iter().map(|id| id.to_string()).collect(),
+                    preferred_vehicle: visit.preferred_vehicles.first().map(|id| id.to_string()),
+                    preferred_vehicle_weight: visit.preferred_vehicle_weight,
+                    optional: visit.optional,
+                    priority: visit.priority,
+                    min_start_time: iso(visit.min_start_time),
+                    max_end_time: iso(visit.max_end_time),
+                    soft_deadline: visit.soft_deadline.map(iso),
+                    lateness_cost_per_minute: visit.lateness_cost_per_minute,
                     service_duration: visit.service_duration as i32,
                     vehicle: vehicle_id,
                     previous_visit: prev_visit,
                     next_visit,
-                    arrival_time: timing.map(|t| seconds_to_iso(t.0)),
-                    start_service_time: timing.map(|t| seconds_to_iso(t.1)),
-                    departure_time: timing.map(|t| seconds_to_iso(t.2)),
+                    arrival_time: timing.map(|t| iso(t.0)),
+                    start_service_time: timing.map(|t| iso(t.1)),
+                    departure_time: timing.map(|t| iso(t.2)),
                     driving_time_seconds_from_previous_standstill: timing.map(|t| t.3),
                 })
             })
@@ -594,18 +1094,9 @@ impl RoutePlanDto {
                     .unwrap_or([0.0, 0.0]);
 
                 let total_driving = plan.total_driving_time(v);
-                let route_times = plan.calculate_route_times(v);
 
                 // Calculate arrival time back at depot
-                let arrival = if v.visits.is_empty() {
-                    v.departure_time
-                } else if let Some(last_timing) = route_times.last() {
-                    let last_visit = &plan.visits[last_timing.visit_idx];
-                    let return_travel = plan.travel_time(last_visit.location.index, v.home_location.index);
-                    last_timing.departure + return_travel
-                } else {
-                    v.departure_time
-                };
+                let arrival = plan.route_end_time(v);
 
                 // Compute total demand by summing visit demands
                 let total_demand: i32 = v
@@ -618,30 +1109,72 @@ impl RoutePlanDto {
                 VehicleDto {
                     id: v.id.to_string(),
                     name: v.name.clone(),
-                    capacity: v.capacity,
+                    capacity: CapacityDto::from_domain(
+                        v.capacity,
+                        &v.dimension_capacities,
+                        &plan.capacity_dimensions,
+                        i32::MAX,
+                    ),
                     home_location: home_loc,
-                    departure_time: seconds_to_iso(v.departure_time),
+                    end_location: v
+                        .end_location
+                        .as_ref()
+                        .and_then(|end| plan.locations.get(end.index))
+                        .map(|l| [l.latitude, l.longitude]),
+                    open_route: v.open_route,
+                    departure_time: iso(v.departure_time),
+                    shift_end: v.shift_end.map(iso),
+                    shifts: Vec::new(),
+                    fixed_cost: v.fixed_cost,
+                    cost_per_km: v.cost_per_km,
+                    cost_per_hour: v.cost_per_hour,
+                    fuel_per_100km: v.fuel_per_100km.0,
+                    co2_kg_per_fuel_unit: v.co2_kg_per_fuel_unit.0,
+                    capabilities: v.capabilities.iter().cloned().collect(),
+                    max_reloads: v.max_reloads,
+                    reload_duration: v.reload_duration,
                     visits: v.visits.iter().map(|&idx| visit_id(idx)).collect(),
                     total_demand,
+                    peak_load: plan.peak_load(v),
                     total_driving_time_seconds: total_driving as i32,
-                    arrival_time: seconds_to_iso(arrival),
+                    total_distance_meters: plan.total_distance_meters(v).round() as i64,
+                    emissions_kg: plan.vehicle_emissions_kg(v),
+                    total_cost: plan.vehicle_cost(v),
+                    arrival_time: iso(arrival),
                 }
             })
             .collect();
 
         // Calculate plan-level times
         let start_dt = plan.vehicles.iter().map(|v| v.departure_time).min();
-        let end_dt = vehicles.iter().map(|v| iso_to_seconds(&v.arrival_time)).max();
+        let end_dt = plan.vehicles.iter().map(|v| plan.route_end_time(v)).max();
 
         Self {
             name: plan.name.clone(),
             south_west_corner: plan.south_west_corner,
             north_east_corner: plan.north_east_corner,
-            start_date_time: start_dt.map(seconds_to_iso),
-            end_date_time: end_dt.map(seconds_to_iso),
+            horizon_start: Some(iso(0)),
+            start_date_time: start_dt.map(iso),
+            end_date_time: end_dt.map(iso),
             total_driving_time_seconds: plan.total_driving_time_all() as i32,
+            total_distance_meters: plan.total_distance_meters_all().round() as i64,
+            total_emissions_kg: plan.total_emissions_kg(),
+            total_cost: plan.total_cost(),
+            vehicles_used: plan.vehicles_used(),
+            objective: plan.objective,
+            minimize_vehicles_used: plan.minimize_vehicles_used,
+            waiting_cost_per_minute: plan.waiting_cost_per_minute,
             vehicles,
             visits,
+            unassigned_visits: plan
+                .unassigned_visits()
+                .into_iter()
+                .filter(|&idx| !plan.visits[idx].is_reload())
+                .map(|idx| UnassignedVisitDto {
+                    visit: visit_id(idx),
+                    reason: unassigned_reason(plan, idx),
+                })
+                .collect(),
             score: plan.score.map(|s| format!("{}", s)),
             solver_status: status.map(|s| s.as_str().to_string()),
             termination: None,
@@ -650,10 +1183,137 @@ impl RoutePlanDto {
             } else {
                 Some(plan.travel_time_matrix.clone())
             },
+            distance_matrix: if plan.distance_matrix.is_empty() {
+                None
+            } else {
+                Some(plan.distance_matrix.clone())
+            },
+            matrix_provider: None,
+            speed_profile: plan.speed_profile.clone(),
+            time_dependent_times: plan.time_dependent_times.clone(),
         }
     }
 
+    /// The input datetimes the domain plan is built from, each with the
+    /// field it came from for error messages.
+    fn input_datetimes(&self) -> Vec<(String, &str)> {
+        let mut datetimes: Vec<(String, &str)> = Vec::new();
+        if let Some(iso) = &self.horizon_start {
+            datetimes.push(("horizonStart".to_string(), iso));
+        }
+        for v in &self.vehicles {
+            datetimes.push((format!("vehicle {} departureTime", v.id), &v.departure_time));
+            if let Some(iso) = &v.shift_end {
+                datetimes.push((format!("vehicle {} shiftEnd", v.id), iso));
+            }
+            for shift in &v.shifts {
+                datetimes.push((format!("vehicle {} shift start", v.id), &shift.start));
+                datetimes.push((format!("vehicle {} shift end", v.id), &shift.end));
+            }
+        }
+        for v in &self.visits {
+            datetimes.push((format!("visit {} minStartTime", v.id), &v.min_start_time));
+            datetimes.push((format!("visit {} maxEndTime", v.id), &v.max_end_time));
+            if let Some(iso) = &v.soft_deadline {
+                datetimes.push((format!("visit {} softDeadline", v.id), iso));
+            }
+        }
+        datetimes
+    }
+
+    /// Timezone of the planning horizon: the UTC offset of `horizonStart`,
+    /// else of the first input datetime that has one, else UTC.
+    ///
+    /// Datetimes with a different offset are converted to it, and all
+    /// output times are wall-clock times in it.
+    pub fn horizon_timezone(&self) -> FixedOffset {
+        self.input_datetimes()
+            .into_iter()
+            .find_map(|(_, iso)| iso_utc_offset(iso))
+            .unwrap_or_else(utc)
+    }
+
+    /// Checks that every input datetime parses and that pickup-and-delivery
+    /// pairs and the speed profile are well-formed.
+    ///
+    /// A `pairedVisit` must name another visit of the opposite kind (one
+    /// pickup, one delivery), and if that visit names a partner too, it
+    /// must be this one.
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(profile) = &self.speed_profile {
+            profile.validate()?;
+        }
+
+        let timezone = self.horizon_timezone();
+        for (field, iso) in self.input_datetimes() {
+            if parse_iso_datetime(iso, timezone).is_none() {
+                return Err(format!("{} is not a valid ISO datetime: {:?}", field, iso));
+            }
+        }
+
+        let by_id: HashMap<&str, &VisitDto> = self.visits.iter().map(|v| (v.id.as_str(), v)).collect();
+        for visit in &self.visits {
+            let Some(partner_id) = visit.paired_visit.as_deref() else {
+                continue;
+            };
+            if partner_id == visit.id {
+                return Err(format!("visit {} is paired with itself", visit.id));
+            }
+            let partner = by_id
+                .get(partner_id)
+                .ok_or_else(|| format!("visit {} is paired with unknown visit {}", visit.id, partner_id))?;
+            if !matches!(
+                (visit.kind, partner.kind),
+                (VisitKind::Pickup, VisitKind::Delivery) | (VisitKind::Delivery, VisitKind::Pickup)
+            ) {
+                return Err(format!(
+                    "visits {} and {} must be one pickup and one delivery",
+                    visit.id, partner_id
+                ));
+            }
+            if partner.paired_visit.as_deref().is_some_and(|back| back != visit.id) {
+                return Err(format!("visit {} is paired with {}, which is paired elsewhere", visit.id, partner_id));
+            }
+        }
+        Ok(())
+    }
+
     /// Converts DTO to domain model for solving.
+    ///
+    /// Times become offsets from the horizon start, so routes may cross
+    /// midnight and span several days. A vehicle with `shifts` becomes one
+    /// domain vehicle per shift.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use vehicle_routing::api::RoutePlanDto;
+    ///
+    /// let dto: RoutePlanDto = serde_json::from_value(serde_json::json!({
+    ///     "name": "two days",
+    ///     "southWestCorner": [39.9, -75.2],
+    ///     "northEastCorner": [40.0, -75.1],
+    ///     "totalDrivingTimeSeconds": 0,
+    ///     "vehicles": [{
+    ///         "id": "van", "name": "Van", "capacity": 10,
+    ///         "homeLocation": [39.95, -75.16],
+    ///         "departureTime": "2026-03-02T07:00:00",
+    ///         "shifts": [
+    ///             {"start": "2026-03-02T07:00:00", "end": "2026-03-02T15:00:00"},
+    ///             {"start": "2026-03-03T22:00:00", "end": "2026-03-04T06:00:00"}
+    ///         ],
+    ///         "visits": [], "totalDemand": 0, "totalDrivingTimeSeconds": 0,
+    ///         "arrivalTime": "2026-03-02T07:00:00"
+    ///     }],
+    ///     "visits": []
+    /// })).unwrap();
+    ///
+    /// let plan = dto.to_domain();
+    /// assert_eq!(plan.horizon_start.to_string(), "2026-03-02 00:00:00");
+    /// assert_eq!(plan.vehicles.len(), 2);
+    /// assert_eq!(plan.vehicles[1].departure_time, (24 + 22) * 3600);
+    /// assert_eq!(plan.vehicles[1].shift_end, Some((48 + 6) * 3600));
+    /// ```
     pub fn to_domain(&self) -> VehicleRoutePlan {
         use crate::domain::Location;
 
@@ -661,19 +1321,31 @@ impl RoutePlanDto {
         let mut locations = Vec::new();
         let mut depot_indices: HashMap<(i64, i64), usize> = HashMap::new();
 
-        // Add unique depot locations
-        for vdto in &self.vehicles {
-            let key = (
-                (vdto.home_location[0] * 1e6) as i64,
-                (vdto.home_location[1] * 1e6) as i64,
-            );
-            depot_indices.entry(key).or_insert_with(|| {
+        // Add unique depot locations (start and end locations)
+        let depot_key = |loc: &[f64; 2]| ((loc[0] * 1e6) as i64, (loc[1] * 1e6) as i64);
+        for loc in self
+            .vehicles
+            .iter()
+            .flat_map(|vdto| std::iter::once(&vdto.home_location).chain(vdto.end_location.as_ref()))
+        {
+            depot_indices.entry(depot_key(loc)).or_insert_with(|| {
                 let idx = locations.len();
-                locations.push(Location::new(idx, vdto.home_location[0], vdto.home_location[1]));
+                locations.push(Location::new(idx, loc[0], loc[1]));
                 idx
             });
         }
 
+        // Collect named capacity dimensions from vehicles and visits
+        let capacity_dimensions: Vec<String> = self
+            .vehicles
+            .iter()
+            .flat_map(|v| v.capacity.dimension_names())
+            .chain(self.visits.iter().flat_map(|v| v.demand.dimension_names()))
+            .cloned()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+
         // Build visit ID to index mapping
         let visit_id_to_idx: HashMap<&str, usize> = self
             .visits
@@ -692,6 +1364,57 @@ impl RoutePlanDto {
             ));
         }
 
+        // Planning horizon: explicit, else midnight of the earliest date in the plan
+        let timezone = self.horizon_timezone();
+        let horizon_start = self
+            .horizon_start
+            .as_deref()
+            .and_then(|iso| parse_iso_datetime(iso, timezone))
+            .or_else(|| {
+                self.vehicles
+                    .iter()
+                    .flat_map(|v| {
+                        std::iter::once(&v.departure_time).chain(v.shifts.iter().map(|s| &s.start))
+                    })
+                    .chain(self.visits.iter().map(|v| &v.min_start_time))
+                    .filter_map(|iso| parse_iso_datetime(iso, timezone))
+                    .min()
+                    .and_then(|dt| dt.date().and_hms_opt(0, 0, 0))
+            })
+            .unwrap_or_else(default_horizon_start);
+        let secs = |iso: &str| iso_to_offset(horizon_start, timezone, iso);
+
+        // Expand vehicles into one domain vehicle per shift:
+        // (DTO vehicle index, departure, shift end)
+        let expanded: Vec<(usize, i64, Option<i64>)> = self
+            .vehicles
+            .iter()
+            .enumerate()
+            .flat_map(|(i, vdto)| {
+                if vdto.shifts.is_empty() {
+                    vec![(i, secs(&vdto.departure_time), vdto.shift_end.as_deref().map(secs))]
+                } else {
+                    vdto.shifts
+                        .iter()
+                        .map(|shift| (i, secs(&shift.start), Some(secs(&shift.end))))
+                        .collect()
+                }
+            })
+            .collect();
+
+        // Build vehicle ID to index mapping (domain vehicle IDs are positions)
+        let mut vehicle_id_to_idx: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (pos, &(i, _, _)) in expanded.iter().enumerate() {
+            vehicle_id_to_idx.entry(self.vehicles[i].id.as_str()).or_default().push(pos);
+        }
+        let vehicle_ids = |ids: &[String]| -> Vec<usize> {
+            ids.iter()
+                .filter_map(|vid| vehicle_id_to_idx.get(vid.as_str()))
+                .flatten()
+                .copied()
+                .collect()
+        };
+
         // Build visits - now needs Location object, not index
         let visits: Vec<Visit> = self
             .visits
@@ -699,57 +1422,114 @@ impl RoutePlanDto {
             .enumerate()
             .map(|(i, vdto)| {
                 let loc = locations[visit_start_idx + i].clone();
-                Visit::new(i, &vdto.name, loc)
-                    .with_demand(vdto.demand)
-                    .with_time_window(
-                        iso_to_seconds(&vdto.min_start_time),
-                        iso_to_seconds(&vdto.max_end_time),
-                    )
-                    .with_service_duration(vdto.service_duration as i64)
+                let (demand, dimension_demands) = vdto.demand.to_domain(&capacity_dimensions, 0);
+                let mut visit = Visit::new(i, &vdto.name, loc)
+                    .with_demand(demand)
+                    .with_dimension_demands(dimension_demands)
+                    .with_time_window(secs(&vdto.min_start_time), secs(&vdto.max_end_time))
+                    .with_service_duration(vdto.service_duration as i64);
+                visit.kind = vdto.kind;
+                visit.paired_visit = vdto
+                    .paired_visit
+                    .as_deref()
+                    .and_then(|pid| visit_id_to_idx.get(pid).copied());
+                visit.required_capabilities = vdto.required_capabilities.iter().cloned().collect();
+                visit.allowed_vehicles = vehicle_ids(&vdto.allowed_vehicles);
+                visit.forbidden_vehicles = vehicle_ids(&vdto.forbidden_vehicles);
+                visit.preferred_vehicles = vehicle_ids(vdto.preferred_vehicle.as_slice());
+                visit.preferred_vehicle_weight = vdto.preferred_vehicle_weight;
+                visit.soft_deadline = vdto.soft_deadline.as_deref().map(secs);
+                visit.lateness_cost_per_minute = vdto.lateness_cost_per_minute;
+                visit.optional = vdto.optional;
+                visit.priority = vdto.priority;
+                visit
             })
             .collect();
 
         // Build vehicles - now needs Location object, not index
-        let vehicles: Vec<Vehicle> = self
-            .vehicles
+        let vehicles: Vec<Vehicle> = expanded
             .iter()
             .enumerate()
-            .map(|(i, vdto)| {
-                let key = (
-                    (vdto.home_location[0] * 1e6) as i64,
-                    (vdto.home_location[1] * 1e6) as i64,
-                );
-                let home_idx = depot_indices[&key];
-                let home_loc = locations[home_idx].clone();
+            .map(|(pos, &(i, departure, shift_end))| {
+                let vdto = &self.vehicles[i];
+                let first_shift = expanded.iter().position(|e| e.0 == i) == Some(pos);
+                let home_loc = locations[depot_indices[&depot_key(&vdto.home_location)]].clone();
+                let end_loc = vdto
+                    .end_location
+                    .as_ref()
+                    .map(|loc| locations[depot_indices[&depot_key(loc)]].clone());
 
                 // Map visit IDs to indices
-                let visit_indices: Vec<usize> = vdto
-                    .visits
-                    .iter()
-                    .filter_map(|vid| visit_id_to_idx.get(vid.as_str()).copied())
-                    .collect();
+                let visit_indices: Vec<usize> = if first_shift {
+                    vdto.visits
+                        .iter()
+                        .filter_map(|vid| visit_id_to_idx.get(vid.as_str()).copied())
+                        .collect()
+                } else {
+                    Vec::new()
+                };
+                let name = if vdto.shifts.is_empty() {
+                    vdto.name.clone()
+                } else {
+                    format!("{} {}", vdto.name, (horizon_start + chrono::Duration::seconds(departure)).date())
+                };
 
-                let mut v = Vehicle::new(i, &vdto.name, vdto.capacity, home_loc);
-                v.departure_time = iso_to_seconds(&vdto.departure_time);
+                let (capacity, dimension_capacities) =
+                    vdto.capacity.to_domain(&capacity_dimensions, i32::MAX);
+                let mut v = Vehicle::new(pos, name, capacity, home_loc)
+                    .with_costs(vdto.fixed_cost, vdto.cost_per_km, vdto.cost_per_hour)
+                    .with_emission_factors(vdto.fuel_per_100km, vdto.co2_kg_per_fuel_unit)
+                    .with_dimension_capacities(dimension_capacities);
+                v.capabilities = vdto.capabilities.iter().cloned().collect();
+                v.max_reloads = vdto.max_reloads;
+                v.reload_duration = vdto.reload_duration;
+                v.end_location = end_loc;
+                v.open_route = vdto.open_route;
+                v.departure_time = departure;
+                v.shift_end = shift_end;
                 v.visits = visit_indices;
                 v
             })
             .collect();
 
         let mut plan = VehicleRoutePlan::new(&self.name, locations, visits, vehicles);
+        plan.capacity_dimensions = capacity_dimensions;
         plan.south_west_corner = self.south_west_corner;
         plan.north_east_corner = self.north_east_corner;
+        plan.objective = self.objective;
+        plan.minimize_vehicles_used = self.minimize_vehicles_used;
+        plan.waiting_cost_per_minute = self.waiting_cost_per_minute;
+        plan.horizon_start = horizon_start;
+        plan.speed_profile = self
+            .speed_profile
+            .as_ref()
+            .map(|profile| SpeedProfile::new(profile.buckets.clone()));
 
         // Use provided matrix (from real roads) if available, otherwise compute haversine
+        // Time-dependent times come from the provider that computed the
+        // matrix; rescaling here would ignore its per-class speeds
         if let Some(matrix) = &self.travel_time_matrix {
             plan.travel_time_matrix = matrix.clone();
+            plan.time_dependent_times = self.time_dependent_times.clone();
         } else {
             plan.finalize();
         }
+        if let Some(matrix) = &self.distance_matrix {
+            plan.distance_matrix = matrix.clone();
+        }
+        plan.add_reload_stops();
         plan
     }
 }
 
+/// Rejects malformed plans with 400 (see [`RoutePlanDto::validate`]).
+fn check_plan(dto: &RoutePlanDto) -> Result<(), StatusCode> {
+    dto.validate().map_err(|e| {
+        tracing::warn!("Rejected route plan: {}", e);
+        StatusCode::BAD_REQUEST
+    })
+}
+
 // ============================================================================
 // Route Plan Handlers
 // ============================================================================
@@ -759,18 +1539,30 @@ impl RoutePlanDto {
     post,
     path = "/route-plans",
     request_body = RoutePlanDto,
-    responses((status = 200, description = "Job ID", body = String))
+    responses(
+        (status = 200, description = "Job ID", body = String),
+        (status = 400, description = "Malformed plan")
+    )
 )]
 async fn create_route_plan(
     State(state): State<Arc<AppState>>,
     Json(dto): Json<RoutePlanDto>,
 ) -> Result<String, StatusCode> {
     let id = Uuid::new_v4().to_string();
+    check_plan(&dto)?;
     let mut plan = dto.to_domain();
 
-    // Initialize road routing (uses cached network - instant after first download)
-    if let Err(e) = plan.init_routing().await {
-        tracing::error!("Road routing initialization failed: {}", e);
+    let provider: Box<dyn MatrixProvider> = match &dto.matrix_provider {
+        Some(p) => p.to_provider().map_err(|e| {
+            tracing::warn!("Rejected matrix provider: {}", e);
+            StatusCode::BAD_REQUEST
+        })?,
+        None => Box::new(OsmProvider),
+    };
+
+    // Initialize routing (OSM uses cached network - instant after first download)
+    if let Err(e) = plan.init_with(provider.as_ref()).await {
+        tracing::error!("Routing initialization via {} failed: {}", provider.name(), e);
         return Err(StatusCode::SERVICE_UNAVAILABLE);
     }
 
@@ -787,7 +1579,7 @@ async fn create_route_plan(
     };
 
     let job = state.solver.create_job_with_config(id.clone(), plan, config);
-    state.solver.start_solving(job);
+    state.solver.start_solving(job).map_err(|_| StatusCode::CONFLICT)?;
     Ok(id)
 }
 
@@ -835,6 +1627,11 @@ pub struct StatusResponse {
     pub score: Option<String>,
     /// Solver status.
     pub solver_status: String,
+    /// Solving was interrupted by a restart; the job can be resumed.
+    pub interrupted: bool,
+    /// 1-based position in the solve queue while `SOLVING_SCHEDULED`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub queue_position: Option<usize>,
 }
 
 /// GET /route-plans/{id}/status - Get route plan status only.
@@ -853,16 +1650,95 @@ async fn get_route_plan_status(
 ) -> Result<Json<StatusResponse>, StatusCode> {
     match state.solver.get_job(&id) {
         Some(job) => {
+            let queue_position = state.solver.queue_position(&id);
             let guard = job.read();
             Ok(Json(StatusResponse {
                 score: guard.plan.score.map(|s| format!("{}", s)),
                 solver_status: guard.status.as_str().to_string(),
+                interrupted: guard.interrupted,
+                queue_position,
             }))
         }
         None => Err(StatusCode::NOT_FOUND),
     }
 }
 
+/// Best-score timeline output format.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryFormat {
+    /// Points as JSON.
+    #[default]
+    Json,
+    /// Points as CSV with a header row.
+    Csv,
+}
+
+/// Query parameters for the best-score timeline.
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct HistoryQuery {
+    /// Output format: `json` (default) or `csv`.
+    #[serde(default)]
+    pub format: HistoryFormat,
+}
+
+/// A new best score found while solving.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScorePointDto {
+    /// Milliseconds since solving started.
+    pub elapsed_ms: u64,
+    /// Local search steps taken (0 for the construction heuristic).
+    pub step: u64,
+    /// The new best score.
+    pub score: String,
+    /// Hard part of the score.
+    pub hard_score: i64,
+    /// Soft part of the score.
+    pub soft_score: i64,
+}
+
+/// GET /route-plans/{id}/history - Best scores of the current (or last)
+/// solve over time, to chart convergence.
+#[utoipa::path(
+    get,
+    path = "/route-plans/{id}/history",
+    params(("id" = String, Path, description = "Route plan ID"), HistoryQuery),
+    responses(
+        (status = 200, description = "Best scores, oldest first, as JSON or CSV", body = Vec<ScorePointDto>),
+        (status = 404, description = "Not found")
+    )
+)]
+async fn get_score_history(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Response, StatusCode> {
+    let job = state.solver.get_job(&id).ok_or(StatusCode::NOT_FOUND)?;
+    let guard = job.read();
+    Ok(match query.format {
+        HistoryFormat::Json => {
+            let points: Vec<ScorePointDto> = guard
+                .history
+                .iter()
+                .map(|point| ScorePointDto {
+                    elapsed_ms: point.elapsed.as_millis() as u64,
+                    step: point.step,
+                    score: point.score.to_string(),
+                    hard_score: point.score.hard(),
+                    soft_score: point.score.soft(),
+                })
+                .collect();
+            Json(points).into_response()
+        }
+        HistoryFormat::Csv => Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, "text/csv; charset=utf-8")
+            .body(Body::from(history_csv(&guard.history)))
+            .unwrap(),
+    })
+}
+
 /// DELETE /route-plans/{id} - Stop solving and get final solution.
 #[utoipa::path(
     delete,
@@ -890,38 +1766,193 @@ async fn stop_solving(
     }
 }
 
+/// POST /route-plans/{id}/resume - Continue solving a stopped route plan.
+///
+/// Solving restarts from the best solution with the job's termination
+/// configuration; this is how jobs interrupted by a restart are resumed.
+#[utoipa::path(
+    post,
+    path = "/route-plans/{id}/resume",
+    params(("id" = String, Path, description = "Route plan ID")),
+    responses(
+        (status = 200, description = "Solving resumed", body = StatusResponse),
+        (status = 404, description = "Not found"),
+        (status = 409, description = "Already solving")
+    )
+)]
+async fn resume_solving(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<StatusResponse>, StatusCode> {
+    let job = state.solver.get_job(&id).ok_or(StatusCode::NOT_FOUND)?;
+    state.solver.start_solving(job.clone()).map_err(|_| StatusCode::CONFLICT)?;
+
+    // Before locking the job: the queue is always locked first
+    let queue_position = state.solver.queue_position(&id);
+    let guard = job.read();
+    Ok(Json(StatusResponse {
+        score: guard.plan.score.map(|s| format!("{}", s)),
+        solver_status: guard.status.as_str().to_string(),
+        interrupted: guard.interrupted,
+        queue_position,
+    }))
+}
+
 /// Geometry response with encoded polylines for map rendering.
 #[derive(Debug, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GeometryResponse {
     /// Encoded route segments per vehicle.
     pub segments: Vec<EncodedSegment>,
+    /// Encoded geometry per route leg, when requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub legs: Option<Vec<EncodedLeg>>,
+}
+
+/// Query parameters for route geometry.
+#[derive(Debug, Default, Deserialize, IntoParams)]
+pub struct GeometryQuery {
+    /// Douglas–Peucker tolerance in meters; omitted or 0 for full detail.
+    pub tolerance: Option<f64>,
+    /// Also return geometry, travel time and distance per leg.
+    #[serde(default)]
+    pub legs: bool,
+}
+
+impl GeometryQuery {
+    /// The simplification tolerance, rejecting NaN and negative values.
+    fn tolerance(&self) -> Result<f64, StatusCode> {
+        match self.tolerance {
+            None => Ok(0.0),
+            Some(t) if t >= 0.0 => Ok(t),
+            Some(_) => Err(StatusCode::BAD_REQUEST),
+        }
+    }
 }
 
 /// GET /route-plans/{id}/geometry - Get encoded polylines for routes.
 #[utoipa::path(
     get,
     path = "/route-plans/{id}/geometry",
-    params(("id" = String, Path, description = "Route plan ID")),
+    params(("id" = String, Path, description = "Route plan ID"), GeometryQuery),
     responses(
         (status = 200, description = "Geometry retrieved", body = GeometryResponse),
+        (status = 400, description = "Invalid tolerance"),
         (status = 404, description = "Not found")
     )
 )]
 async fn get_route_geometry(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
+    Query(query): Query<GeometryQuery>,
 ) -> Result<Json<GeometryResponse>, StatusCode> {
+    let tolerance = query.tolerance()?;
     match state.solver.get_job(&id) {
         Some(job) => {
             let guard = job.read();
-            let segments = encode_routes(&guard.plan);
-            Ok(Json(GeometryResponse { segments }))
+            let segments = encode_routes_simplified(&guard.plan, tolerance);
+            let legs = query.legs.then(|| encode_legs(&guard.plan, tolerance));
+            Ok(Json(GeometryResponse { segments, legs }))
         }
         None => Err(StatusCode::NOT_FOUND),
     }
 }
 
+/// Query parameters for route export.
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ExportQuery {
+    /// Export format: `geojson`, `gpx` or `kml`.
+    pub format: ExportFormat,
+    /// Only export this vehicle's route.
+    pub vehicle: Option<String>,
+}
+
+/// GET /route-plans/{id}/export - Download routes as GeoJSON, GPX or KML.
+#[utoipa::path(
+    get,
+    path = "/route-plans/{id}/export",
+    params(("id" = String, Path, description = "Route plan ID"), ExportQuery),
+    responses(
+        (status = 200, description = "Routes exported as an attachment"),
+        (status = 404, description = "Route plan or vehicle not found")
+    )
+)]
+async fn export_route_plan(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(query): Query<ExportQuery>,
+) -> Result<Response, StatusCode> {
+    let job = state.solver.get_job(&id).ok_or(StatusCode::NOT_FOUND)?;
+    let guard = job.read();
+    let plan = &guard.plan;
+
+    let vehicle_id = find_vehicle(plan, query.vehicle.as_deref())?;
+    let document = export(plan, query.format, vehicle_id);
+    let filename = match vehicle_id {
+        Some(v) => format!("{}-vehicle-{}.{}", id, v, query.format.extension()),
+        None => format!("{}.{}", id, query.format.extension()),
+    };
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, query.format.content_type())
+        .header(header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename))
+        .body(Body::from(document))
+        .unwrap())
+}
+
+/// Resolves an optional vehicle ID from a query string (404 if unknown).
+fn find_vehicle(plan: &VehicleRoutePlan, vehicle: Option<&str>) -> Result<Option<usize>, StatusCode> {
+    match vehicle {
+        Some(raw) => {
+            let found = plan.vehicles.iter().find(|v| v.id.to_string() == raw);
+            Ok(Some(found.ok_or(StatusCode::NOT_FOUND)?.id))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Query parameters for driver manifests.
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ManifestQuery {
+    /// Output format: `json` (default) or `html`.
+    #[serde(default)]
+    pub format: ManifestFormat,
+    /// Only this vehicle's manifest (also when its route is empty).
+    pub vehicle: Option<String>,
+}
+
+/// GET /route-plans/{id}/manifests - Driver run sheets per vehicle.
+#[utoipa::path(
+    get,
+    path = "/route-plans/{id}/manifests",
+    params(("id" = String, Path, description = "Route plan ID"), ManifestQuery),
+    responses(
+        (status = 200, description = "Manifests as JSON, or a printable HTML page", body = Vec<Manifest>),
+        (status = 404, description = "Route plan or vehicle not found")
+    )
+)]
+async fn get_manifests(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(query): Query<ManifestQuery>,
+) -> Result<Response, StatusCode> {
+    let job = state.solver.get_job(&id).ok_or(StatusCode::NOT_FOUND)?;
+    let guard = job.read();
+    let plan = &guard.plan;
+
+    let vehicle_id = find_vehicle(plan, query.vehicle.as_deref())?;
+    let manifests = build_manifests(plan, vehicle_id);
+    Ok(match query.format {
+        ManifestFormat::Json => Json(manifests).into_response(),
+        ManifestFormat::Html => Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, "text/html; charset=utf-8")
+            .body(Body::from(render_html(&plan.name, &manifests)))
+            .unwrap(),
+    })
+}
+
 // ============================================================================
 // Score Analysis
 // ============================================================================
@@ -962,11 +1993,21 @@ pub struct AnalyzeResponse {
     put,
     path = "/route-plans/analyze",
     request_body = RoutePlanDto,
-    responses((status = 200, description = "Constraint analysis", body = AnalyzeResponse))
+    responses(
+        (status = 200, description = "Constraint analysis", body = AnalyzeResponse),
+        (status = 400, description = "Malformed plan")
+    )
 )]
-async fn analyze_route_plan(Json(dto): Json<RoutePlanDto>) -> Json<AnalyzeResponse> {
-    use crate::constraints::{calculate_late_minutes, calculate_excess_capacity};
+async fn analyze_route_plan(Json(dto): Json<RoutePlanDto>) -> Result<Json<AnalyzeResponse>, StatusCode> {
+    use crate::constraints::{
+        calculate_excess_capacity, calculate_excess_dimension, calculate_incompatible_visits,
+        calculate_late_minutes, calculate_objective_penalty, calculate_pair_violations,
+        calculate_preference_penalty, INCOMPATIBLE_VEHICLE_PENALTY, PAIR_VIOLATION_PENALTY,
+        calculate_overtime_minutes, calculate_soft_lateness_penalty, calculate_waiting_minutes,
+        RELOAD_PENALTY, UNASSIGNED_PRIORITY_WEIGHT, UNASSIGNED_VISIT_PENALTY, VEHICLE_USED_PENALTY,
+    };
 
+    check_plan(&dto)?;
     let plan = dto.to_domain();
 
     // Calculate constraint scores
@@ -984,37 +2025,145 @@ async fn analyze_route_plan(Json(dto): Json<RoutePlanDto>) -> Json<AnalyzeRespon
 
     let cap_score = HardSoftScore::of_hard(-cap_total);
     let tw_score = HardSoftScore::of_hard(-tw_total);
+    let soft_late_total: i64 = plan.vehicles.iter()
+        .map(|v| calculate_soft_lateness_penalty(&plan, v))
+        .sum();
     let travel_score = HardSoftScore::of_soft(-travel_total);
 
-    // Helper to compute total demand
-    let total_demand = |v: &Vehicle| -> i32 {
-        v.visits.iter()
-            .filter_map(|&idx| plan.visits.get(idx))
-            .map(|visit| visit.demand)
-            .sum()
-    };
-
     // Build detailed matches for capacity constraint
     let cap_matches: Vec<MatchAnalysisDto> = plan.vehicles.iter()
-        .filter(|v| total_demand(v) > v.capacity)
+        .filter(|v| calculate_excess_capacity(&plan, v) > 0)
         .map(|v| {
-            let demand = total_demand(v);
-            let excess = demand - v.capacity;
+            let peak = plan.peak_load(v);
+            let excess = peak - v.capacity;
             MatchAnalysisDto {
                 name: "Vehicle capacity".to_string(),
                 score: format!("{}hard/0soft", -excess),
-                justification: format!("{} is over capacity by {} (demand {} > capacity {})",
-                    v.name, excess, demand, v.capacity),
+                justification: format!("{} is over capacity by {} (peak load {} > capacity {})",
+                    v.name, excess, peak, v.capacity),
             }
         })
         .collect();
 
-    // Build detailed matches for time window constraint
+    // Build one constraint per named capacity dimension
+    let dimension_constraints: Vec<ConstraintAnalysisDto> = plan.capacity_dimensions.iter()
+        .enumerate()
+        .map(|(dimension, dim_name)| {
+            let name = format!("Vehicle capacity ({})", dim_name);
+            let matches: Vec<MatchAnalysisDto> = plan.vehicles.iter()
+                .filter_map(|v| {
+                    let excess = calculate_excess_dimension(&plan, v, dimension);
+                    (excess > 0).then(|| MatchAnalysisDto {
+                        name: name.clone(),
+                        score: format!("{}hard/0soft", -excess),
+                        justification: format!("{} is over {} capacity by {} (peak load {} > capacity {})",
+                            v.name, dim_name, excess,
+                            plan.peak_dimension_load(v, dimension),
+                            v.dimension_capacity(dimension)),
+                    })
+                })
+                .collect();
+            let total: i64 = plan.vehicles.iter()
+                .map(|v| calculate_excess_dimension(&plan, v, dimension) as i64)
+                .sum();
+
+            ConstraintAnalysisDto {
+                name,
+                weight: "1hard/0soft".to_string(),
+                score: format!("{}", HardSoftScore::of_hard(-total)),
+                matches,
+            }
+        })
+        .collect();
+
+    // Build detailed matches for vehicle compatibility
+    let compat_matches: Vec<MatchAnalysisDto> = plan.vehicles.iter()
+        .flat_map(|v| {
+            calculate_incompatible_visits(&plan, v)
+                .into_iter()
+                .map(|idx| (v, &plan.visits[idx]))
+                .collect::<Vec<_>>()
+        })
+        .map(|(v, visit)| {
+            let missing = visit.missing_capabilities(v);
+            let reason = if !missing.is_empty() {
+                format!("lacks {}", missing.join(", "))
+            } else if visit.forbidden_vehicles.contains(&v.id) {
+                "is forbidden".to_string()
+            } else {
+                "is not in the allowed vehicles".to_string()
+            };
+            MatchAnalysisDto {
+                name: "Vehicle compatibility".to_string(),
+                score: format!("{}hard/0soft", -INCOMPATIBLE_VEHICLE_PENALTY),
+                justification: format!("{} cannot serve {}: vehicle {}", v.name, visit.name, reason),
+            }
+        })
+        .collect();
+    let compat_score = HardSoftScore::of_hard(-(compat_matches.len() as i64) * INCOMPATIBLE_VEHICLE_PENALTY);
+
+    // Build detailed matches for preferred vehicles
+    let pref_matches: Vec<MatchAnalysisDto> = plan.vehicles.iter()
+        .flat_map(|v| v.visits.iter().filter_map(|&idx| plan.visits.get(idx)).map(move |visit| (v, visit)))
+        .filter(|(v, visit)| visit.preference_penalty(v) > 0)
+        .map(|(v, visit)| {
+            let preferred = plan.vehicles.iter()
+                .filter(|pv| visit.preferred_vehicles.contains(&pv.id))
+                .map(|pv| pv.name.as_str())
+                .collect::<Vec<_>>()
+                .join(" or ");
+            MatchAnalysisDto {
+                name: "Preferred vehicle".to_string(),
+                score: format!("0hard/{}soft", -visit.preference_penalty(v)),
+                justification: format!("{} is served by {} instead of preferred {}", visit.name, v.name, preferred),
+            }
+        })
+        .collect();
+    let pref_total: i64 = plan.vehicles.iter().map(|v| calculate_preference_penalty(&plan, v)).sum();
+
+    // Build detailed matches for pickup-and-delivery precedence
+    let pair_violations = calculate_pair_violations(&plan);
+    let pair_score = HardSoftScore::of_hard(-(pair_violations.len() as i64) * PAIR_VIOLATION_PENALTY);
+    let pair_matches: Vec<MatchAnalysisDto> = pair_violations.iter()
+        .map(|&(pickup, delivery)| MatchAnalysisDto {
+            name: "Pickup before delivery".to_string(),
+            score: format!("{}hard/0soft", -PAIR_VIOLATION_PENALTY),
+            justification: format!("{} must be picked up before {} on the same vehicle",
+                plan.visits[pickup].name,
+                plan.visits.get(delivery).map(|v| v.name.as_str()).unwrap_or("?")),
+        })
+        .collect();
+
+    // Build detailed matches for time window constraints (hard and soft lateness)
     let mut tw_matches: Vec<MatchAnalysisDto> = Vec::new();
+    let mut soft_late_matches: Vec<MatchAnalysisDto> = Vec::new();
+    let mut waiting_matches: Vec<MatchAnalysisDto> = Vec::new();
     for vehicle in &plan.vehicles {
         let timings = plan.calculate_route_times(vehicle);
         for timing in &timings {
             if let Some(visit) = plan.get_visit(timing.visit_idx) {
+                if let Some(deadline) = visit.soft_deadline.filter(|&d| timing.departure > d) {
+                    let late_mins = (timing.departure - deadline + 59) / 60;
+                    soft_late_matches.push(MatchAnalysisDto {
+                        name: "Service finished after soft deadline".to_string(),
+                        score: format!("0hard/{}soft", -late_mins * visit.lateness_cost_per_minute),
+                        justification: format!("{} finishes {} mins after its soft deadline (ends at {}, deadline {})",
+                            visit.name, late_mins,
+                            offset_to_iso(plan.horizon_start, timing.departure),
+                            offset_to_iso(plan.horizon_start, deadline)),
+                    });
+                }
+                if plan.waiting_cost_per_minute != 0 && timing.waiting_minutes() > 0 {
+                    let wait_mins = timing.waiting_minutes();
+                    waiting_matches.push(MatchAnalysisDto {
+                        name: "Waiting time".to_string(),
+                        score: format!("0hard/{}soft", -wait_mins * plan.waiting_cost_per_minute),
+                        justification: format!("{} waits {} mins at {} (arrives {}, opens {})",
+                            vehicle.name, wait_mins, visit.name,
+                            offset_to_iso(plan.horizon_start, timing.arrival),
+                            offset_to_iso(plan.horizon_start, visit.min_start_time)),
+                    });
+                }
                 if timing.departure > visit.max_end_time {
                     let late_secs = timing.departure - visit.max_end_time;
                     let late_mins = (late_secs + 59) / 60;
@@ -1023,34 +2172,101 @@ async fn analyze_route_plan(Json(dto): Json<RoutePlanDto>) -> Json<AnalyzeRespon
                         score: format!("{}hard/0soft", -late_mins),
                         justification: format!("{} finishes {} mins late (ends at {}, max {})",
                             visit.name, late_mins,
-                            seconds_to_iso(timing.departure),
-                            seconds_to_iso(visit.max_end_time)),
+                            offset_to_iso(plan.horizon_start, timing.departure),
+                            offset_to_iso(plan.horizon_start, visit.max_end_time)),
                     });
                 }
             }
         }
     }
 
-    // Build matches for travel time
-    let travel_matches: Vec<MatchAnalysisDto> = plan.vehicles.iter()
-        .filter(|v| !v.visits.is_empty())
-        .map(|v| {
-            let time = plan.total_driving_time(v);
-            MatchAnalysisDto {
+    // Build matches for the soft objective
+    let objective_constraint = match plan.objective {
+        RoutingObjective::TravelTime => {
+            let travel_matches: Vec<MatchAnalysisDto> = plan.vehicles.iter()
+                .filter(|v| !v.visits.is_empty())
+                .map(|v| {
+                    let time = plan.total_driving_time(v);
+                    MatchAnalysisDto {
+                        name: "Minimize travel time".to_string(),
+                        score: format!("0hard/{}soft", -time),
+                        justification: format!("{} drives {} seconds", v.name, time),
+                    }
+                })
+                .collect();
+
+            ConstraintAnalysisDto {
                 name: "Minimize travel time".to_string(),
-                score: format!("0hard/{}soft", -time),
-                justification: format!("{} drives {} seconds", v.name, time),
+                weight: "0hard/1soft".to_string(),
+                score: format!("{}", travel_score),
+                matches: travel_matches,
             }
-        })
-        .collect();
+        }
+        RoutingObjective::Cost => {
+            let cost_matches: Vec<MatchAnalysisDto> = plan.vehicles.iter()
+                .filter(|v| !v.visits.is_empty())
+                .map(|v| {
+                    let cost = calculate_objective_penalty(&plan, v);
+                    MatchAnalysisDto {
+                        name: "Minimize vehicle cost".to_string(),
+                        score: format!("0hard/{}soft", -cost),
+                        justification: format!("{} costs {} (fixed {}, {:.1} km, {} seconds on the road)",
+                            v.name, cost, v.fixed_cost,
+                            plan.total_distance_meters(v) / 1000.0,
+                            plan.route_end_time(v) - v.departure_time),
+                    }
+                })
+                .collect();
 
-    let constraints = vec![
+            ConstraintAnalysisDto {
+                name: "Minimize vehicle cost".to_string(),
+                weight: "0hard/1soft".to_string(),
+                score: format!("{}", HardSoftScore::of_soft(-plan.total_cost())),
+                matches: cost_matches,
+            }
+        }
+        RoutingObjective::Distance => {
+            let distance_matches: Vec<MatchAnalysisDto> = plan.vehicles.iter()
+                .filter(|v| !v.visits.is_empty())
+                .map(|v| {
+                    let meters = calculate_objective_penalty(&plan, v);
+                    MatchAnalysisDto {
+                        name: "Minimize distance".to_string(),
+                        score: format!("0hard/{}soft", -meters),
+                        justification: format!("{} drives {:.1} km", v.name, meters as f64 / 1000.0),
+                    }
+                })
+                .collect();
+            let total: i64 = plan.vehicles.iter().map(|v| calculate_objective_penalty(&plan, v)).sum();
+
+            ConstraintAnalysisDto {
+                name: "Minimize distance".to_string(),
+                weight: "0hard/1soft".to_string(),
+                score: format!("{}", HardSoftScore::of_soft(-total)),
+                matches: distance_matches,
+            }
+        }
+    };
+
+    let mut constraints = vec![
         ConstraintAnalysisDto {
             name: "Vehicle capacity".to_string(),
             weight: "1hard/0soft".to_string(),
             score: format!("{}", cap_score),
             matches: cap_matches,
         },
+        ConstraintAnalysisDto {
+            name: "Vehicle compatibility".to_string(),
+            weight: format!("{}hard/0soft", INCOMPATIBLE_VEHICLE_PENALTY),
+            score: format!("{}", compat_score),
+            matches: compat_matches,
+        },
+        ConstraintAnalysisDto {
+            name: "Pickup before delivery".to_string(),
+            weight: format!("{}hard/0soft", PAIR_VIOLATION_PENALTY),
+            score: format!("{}", pair_score),
+            matches: pair_matches,
+        },
         ConstraintAnalysisDto {
             name: "Service finished after max end time".to_string(),
             weight: "1hard/0soft".to_string(),
@@ -1058,14 +2274,139 @@ async fn analyze_route_plan(Json(dto): Json<RoutePlanDto>) -> Json<AnalyzeRespon
             matches: tw_matches,
         },
         ConstraintAnalysisDto {
-            name: "Minimize travel time".to_string(),
+            name: "Service finished after soft deadline".to_string(),
+            weight: "0hard/1soft".to_string(),
+            score: format!("{}", HardSoftScore::of_soft(-soft_late_total)),
+            matches: soft_late_matches,
+        },
+        objective_constraint,
+        ConstraintAnalysisDto {
+            name: "Preferred vehicle".to_string(),
             weight: "0hard/1soft".to_string(),
-            score: format!("{}", travel_score),
-            matches: travel_matches,
+            score: format!("{}", HardSoftScore::of_soft(-pref_total)),
+            matches: pref_matches,
         },
     ];
+    constraints.splice(1..1, dimension_constraints);
+
+    // Unassigned visits (mandatory ones are hard, optional ones soft)
+    let mut unassigned = plan.unassigned_visits();
+    unassigned.retain(|&idx| !plan.visits[idx].is_reload());
+    if !unassigned.is_empty() {
+        let penalty = |visit: &Visit| {
+            if visit.optional {
+                HardSoftScore::of_soft(-visit.priority * UNASSIGNED_PRIORITY_WEIGHT)
+            } else {
+                HardSoftScore::of_hard(-UNASSIGNED_VISIT_PENALTY)
+            }
+        };
+        let unassigned_matches: Vec<MatchAnalysisDto> = unassigned.iter()
+            .map(|&idx| {
+                let visit = &plan.visits[idx];
+                MatchAnalysisDto {
+                    name: "Unassigned visit".to_string(),
+                    score: format!("{}", penalty(visit)),
+                    justification: format!(
+                        "{} is unassigned ({:?})",
+                        visit.name,
+                        unassigned_reason(&plan, idx)
+                    ),
+                }
+            })
+            .collect();
+        let unassigned_score = HardSoftScore::of(
+            unassigned.iter().map(|&idx| penalty(&plan.visits[idx]).hard()).sum(),
+            unassigned.iter().map(|&idx| penalty(&plan.visits[idx]).soft()).sum(),
+        );
+
+        constraints.push(ConstraintAnalysisDto {
+            name: "Unassigned visit".to_string(),
+            weight: format!("{}hard/{}soft", UNASSIGNED_VISIT_PENALTY, UNASSIGNED_PRIORITY_WEIGHT),
+            score: format!("{}", unassigned_score),
+            matches: unassigned_matches,
+        });
+    }
 
-    Json(AnalyzeResponse { constraints })
+    // Fleet-size penalty (only when enabled)
+    if plan.minimize_vehicles_used {
+        let used_matches: Vec<MatchAnalysisDto> = plan.vehicles.iter()
+            .filter(|v| !v.visits.is_empty())
+            .map(|v| MatchAnalysisDto {
+                name: "Minimize vehicles used".to_string(),
+                score: format!("0hard/{}soft", -VEHICLE_USED_PENALTY),
+                justification: format!("{} is used ({} visits)", v.name, v.visits.len()),
+            })
+            .collect();
+
+        constraints.push(ConstraintAnalysisDto {
+            name: "Minimize vehicles used".to_string(),
+            weight: format!("0hard/{}soft", VEHICLE_USED_PENALTY),
+            score: format!("{}", HardSoftScore::of_soft(-(plan.vehicles_used() as i64) * VEHICLE_USED_PENALTY)),
+            matches: used_matches,
+        });
+    }
+
+    // Waiting time (only when it has a cost)
+    if plan.waiting_cost_per_minute != 0 {
+        let waiting_total: i64 = plan.vehicles.iter()
+            .map(|v| calculate_waiting_minutes(&plan, v) * plan.waiting_cost_per_minute)
+            .sum();
+
+        constraints.push(ConstraintAnalysisDto {
+            name: "Waiting time".to_string(),
+            weight: format!("0hard/{}soft", plan.waiting_cost_per_minute),
+            score: format!("{}", HardSoftScore::of_soft(-waiting_total)),
+            matches: waiting_matches,
+        });
+    }
+
+    // Shift ends (only when any vehicle has one)
+    if plan.vehicles.iter().any(|v| v.shift_end.is_some()) {
+        let overtime_matches: Vec<MatchAnalysisDto> = plan.vehicles.iter()
+            .filter(|v| calculate_overtime_minutes(&plan, v) > 0)
+            .map(|v| {
+                let overtime = calculate_overtime_minutes(&plan, v);
+                MatchAnalysisDto {
+                    name: "Shift end".to_string(),
+                    score: format!("{}hard/0soft", -overtime),
+                    justification: format!("{} ends {} mins after its shift (ends at {}, shift end {})",
+                        v.name, overtime,
+                        offset_to_iso(plan.horizon_start, plan.route_end_time(v)),
+                        offset_to_iso(plan.horizon_start, v.shift_end.unwrap_or_default())),
+                }
+            })
+            .collect();
+        let overtime_total: i64 = plan.vehicles.iter().map(|v| calculate_overtime_minutes(&plan, v)).sum();
+
+        constraints.push(ConstraintAnalysisDto {
+            name: "Shift end".to_string(),
+            weight: "1hard/0soft".to_string(),
+            score: format!("{}", HardSoftScore::of_hard(-overtime_total)),
+            matches: overtime_matches,
+        });
+    }
+
+    // Depot reloads (only when any vehicle may reload)
+    if plan.vehicles.iter().any(|v| v.max_reloads > 0) {
+        let reload_matches: Vec<MatchAnalysisDto> = plan.vehicles.iter()
+            .filter(|v| plan.reload_count(v) > 0)
+            .map(|v| MatchAnalysisDto {
+                name: "Minimize reloads".to_string(),
+                score: format!("0hard/{}soft", -(plan.reload_count(v) as i64) * RELOAD_PENALTY),
+                justification: format!("{} reloads {} time(s)", v.name, plan.reload_count(v)),
+            })
+            .collect();
+        let reload_total: i64 = plan.vehicles.iter().map(|v| plan.reload_count(v) as i64).sum();
+
+        constraints.push(ConstraintAnalysisDto {
+            name: "Minimize reloads".to_string(),
+            weight: format!("0hard/{}soft", RELOAD_PENALTY),
+            score: format!("{}", HardSoftScore::of_soft(-reload_total * RELOAD_PENALTY)),
+            matches: reload_matches,
+        });
+    }
+
+    Ok(Json(AnalyzeResponse { constraints }))
 }
 
 // ============================================================================
@@ -1116,64 +2457,142 @@ pub struct ApplyRecommendationRequest {
     pub index: usize,
 }
 
+/// Request for recommendations for several visits at once.
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchRecommendationRequest {
+    /// Current solution.
+    pub solution: RoutePlanDto,
+    /// Visit IDs to rank; all unassigned visits when empty.
+    #[serde(default)]
+    pub visit_ids: Vec<String>,
+    /// Recommendations per visit.
+    #[serde(default = "default_recommendation_limit")]
+    pub limit: usize,
+}
+
+fn default_recommendation_limit() -> usize {
+    5
+}
+
+/// Ranked recommendations for one visit.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct VisitRecommendations {
+    /// Visit ID.
+    pub visit_id: String,
+    /// Best recommendations first.
+    pub recommendations: Vec<RecommendedAssignment>,
+}
+
+impl RoutePlanDto {
+    /// Domain index of the visit with this ID.
+    fn visit_index(&self, id: &str) -> Option<usize> {
+        self.visits.iter().position(|v| v.id == id)
+    }
+
+    /// ID of the DTO vehicle behind each domain vehicle, in domain order.
+    ///
+    /// Vehicles with shifts expand into one domain vehicle per shift, all
+    /// sharing the DTO vehicle's ID.
+    fn domain_vehicle_ids(&self) -> Vec<&str> {
+        self.vehicles
+            .iter()
+            .flat_map(|v| std::iter::repeat_n(v.id.as_str(), v.shifts.len().max(1)))
+            .collect()
+    }
+}
+
+/// Converts ranked insertions to recommendations, keeping the best `limit`.
+///
+/// Only the first domain vehicle of each DTO vehicle can be addressed by
+/// ID, so insertions into later shifts are skipped.
+fn to_recommendations(insertions: &[Insertion], vehicle_ids: &[&str], limit: usize) -> Vec<RecommendedAssignment> {
+    insertions
+        .iter()
+        .filter(|i| vehicle_ids.iter().position(|&id| id == vehicle_ids[i.vehicle_idx]) == Some(i.vehicle_idx))
+        .take(limit)
+        .map(|i| RecommendedAssignment {
+            proposition: VehicleRecommendation {
+                vehicle_id: vehicle_ids[i.vehicle_idx].to_string(),
+                index: i.position,
+            },
+            score_diff: format!("{}", i.score_diff),
+        })
+        .collect()
+}
+
 /// POST /route-plans/recommendation - Get recommendations for assigning a visit.
+///
+/// Insertions are scored incrementally on the solution's own travel matrix.
 #[utoipa::path(
     post,
     path = "/route-plans/recommendation",
     request_body = RecommendationRequest,
-    responses((status = 200, description = "Recommendations", body = Vec<RecommendedAssignment>))
+    responses(
+        (status = 200, description = "Recommendations", body = Vec<RecommendedAssignment>),
+        (status = 400, description = "Malformed plan"),
+        (status = 404, description = "Visit not found")
+    )
 )]
-async fn recommend_assignment(Json(request): Json<RecommendationRequest>) -> Json<Vec<RecommendedAssignment>> {
-    use crate::constraints::calculate_score;
-
-    let mut plan = request.solution.to_domain();
-
-    // Find the visit index by ID
-    let visit_id_num: usize = request.visit_id.trim_start_matches('v').parse().unwrap_or(usize::MAX);
-    if visit_id_num >= plan.visits.len() {
-        return Json(vec![]);
-    }
-
-    // Remove visit from any current assignment
-    for vehicle in &mut plan.vehicles {
-        vehicle.visits.retain(|&v| v != visit_id_num);
-    }
-    plan.finalize();
-
-    // Get baseline score
-    let baseline = calculate_score(&plan);
-
-    // Try inserting at each position in each vehicle
-    let mut recommendations: Vec<(RecommendedAssignment, HardSoftScore)> = Vec::new();
-
-    for (v_idx, vehicle) in plan.vehicles.iter().enumerate() {
-        for insert_pos in 0..=vehicle.visits.len() {
-            // Clone and insert
-            let mut test_plan = plan.clone();
-            test_plan.vehicles[v_idx].visits.insert(insert_pos, visit_id_num);
-            test_plan.finalize();
-
-            let new_score = calculate_score(&test_plan);
-            let diff = new_score - baseline;
-
-            recommendations.push((
-                RecommendedAssignment {
-                    proposition: VehicleRecommendation {
-                        vehicle_id: vehicle.id.to_string(),
-                        index: insert_pos,
-                    },
-                    score_diff: format!("{}", diff),
-                },
-                diff,
-            ));
-        }
-    }
+async fn recommend_assignment(
+    Json(request): Json<RecommendationRequest>,
+) -> Result<Json<Vec<RecommendedAssignment>>, StatusCode> {
+    let solution = &request.solution;
+    let visit_idx = solution.visit_index(&request.visit_id).ok_or(StatusCode::NOT_FOUND)?;
+    check_plan(solution)?;
+    let plan = solution.to_domain();
+
+    let insertions = rank_insertions(&plan, visit_idx);
+    Ok(Json(to_recommendations(&insertions, &solution.domain_vehicle_ids(), 5)))
+}
 
-    // Sort by score (best first) and take top 5
-    recommendations.sort_by(|a, b| b.1.cmp(&a.1));
-    let top5: Vec<RecommendedAssignment> = recommendations.into_iter().take(5).map(|(r, _)| r).collect();
+/// POST /route-plans/recommendation/batch - Rank recommendations for several visits.
+///
+/// Each visit is ranked against the current solution independently;
+/// visits come best first by their top recommendation.
+#[utoipa::path(
+    post,
+    path = "/route-plans/recommendation/batch",
+    request_body = BatchRecommendationRequest,
+    responses(
+        (status = 200, description = "Recommendations per visit", body = Vec<VisitRecommendations>),
+        (status = 400, description = "Malformed plan"),
+        (status = 404, description = "Visit not found")
+    )
+)]
+async fn recommend_batch(
+    Json(request): Json<BatchRecommendationRequest>,
+) -> Result<Json<Vec<VisitRecommendations>>, StatusCode> {
+    let solution = &request.solution;
+    check_plan(solution)?;
+    let plan = solution.to_domain();
+
+    let visit_indices: Vec<usize> = if request.visit_ids.is_empty() {
+        plan.unassigned_visits()
+            .into_iter()
+            .filter(|&idx| !plan.visits[idx].is_reload())
+            .collect()
+    } else {
+        request
+            .visit_ids
+            .iter()
+            .map(|id| solution.visit_index(id).ok_or(StatusCode::NOT_FOUND))
+            .collect::<Result<_, _>>()?
+    };
 
-    Json(top5)
+    // Rank everything so later shifts can be skipped without losing results
+    let vehicle_ids = solution.domain_vehicle_ids();
+    let ranked = rank_batch(&plan, &visit_indices, usize::MAX);
+    Ok(Json(
+        ranked
+            .into_iter()
+            .map(|visit| VisitRecommendations {
+                visit_id: solution.visits[visit.visit_idx].id.clone(),
+                recommendations: to_recommendations(&visit.insertions, &vehicle_ids, request.limit),
+            })
+            .collect(),
+    ))
 }
 
 /// POST /route-plans/recommendation/apply - Apply a recommendation.
@@ -1181,33 +2600,40 @@ async fn recommend_assignment(Json(request): Json<RecommendationRequest>) -> Jso
     post,
     path = "/route-plans/recommendation/apply",
     request_body = ApplyRecommendationRequest,
-    responses((status = 200, description = "Updated solution", body = RoutePlanDto))
+    responses(
+        (status = 200, description = "Updated solution", body = RoutePlanDto),
+        (status = 400, description = "Malformed plan"),
+        (status = 404, description = "Visit or vehicle not found")
+    )
 )]
-async fn apply_recommendation(Json(request): Json<ApplyRecommendationRequest>) -> Json<RoutePlanDto> {
-    let mut plan = request.solution.to_domain();
-
-    // Find the visit index by ID
-    let visit_id_num: usize = request.visit_id.trim_start_matches('v').parse().unwrap_or(usize::MAX);
-    let vehicle_id_num: usize = request.vehicle_id.parse().unwrap_or(usize::MAX);
+async fn apply_recommendation(
+    Json(request): Json<ApplyRecommendationRequest>,
+) -> Result<Json<RoutePlanDto>, StatusCode> {
+    let solution = &request.solution;
+    let visit_idx = solution.visit_index(&request.visit_id).ok_or(StatusCode::NOT_FOUND)?;
+    let vehicle_idx = solution
+        .domain_vehicle_ids()
+        .iter()
+        .position(|&id| id == request.vehicle_id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    check_plan(solution)?;
+    let mut plan = solution.to_domain();
 
     // Remove visit from any current assignment
     for vehicle in &mut plan.vehicles {
-        vehicle.visits.retain(|&v| v != visit_id_num);
+        vehicle.visits.retain(|&v| v != visit_idx);
     }
 
     // Insert at specified position
-    if let Some(vehicle) = plan.vehicles.iter_mut().find(|v| v.id == vehicle_id_num) {
-        let insert_idx = request.index.min(vehicle.visits.len());
-        vehicle.visits.insert(insert_idx, visit_id_num);
-    }
-
-    plan.finalize();
+    let vehicle = &mut plan.vehicles[vehicle_idx];
+    let insert_idx = request.index.min(vehicle.visits.len());
+    vehicle.visits.insert(insert_idx, visit_idx);
 
     // Recalculate score
     use crate::constraints::calculate_score;
     plan.score = Some(calculate_score(&plan));
 
-    Json(RoutePlanDto::from_plan(&plan, None))
+    Ok(Json(RoutePlanDto::from_plan(&plan, None)))
 }
 
 // ============================================================================
@@ -1219,6 +2645,10 @@ async fn apply_recommendation(Json(request): Json<ApplyRecommendationRequest>) -
     paths(
         health,
         info,
+        get_metrics,
+        list_road_networks,
+        purge_road_networks,
+        purge_road_network,
         list_demo_data,
         get_demo_data,
         create_route_plan,
@@ -1226,27 +2656,254 @@ async fn apply_recommendation(Json(request): Json<ApplyRecommendationRequest>) -
         get_route_plan,
         get_route_plan_status,
         stop_solving,
+        resume_solving,
+        get_score_history,
         get_route_geometry,
+        export_route_plan,
+        get_manifests,
         analyze_route_plan,
         recommend_assignment,
+        recommend_batch,
         apply_recommendation,
     ),
     components(schemas(
         HealthResponse,
         InfoResponse,
+        CachedNetworkInfo,
+        PurgeResponse,
         VisitDto,
+        VisitKind,
+        UnassignedReason,
+        UnassignedVisitDto,
+        RoutingObjective,
+        CapacityDto,
         VehicleDto,
+        ShiftDto,
+        MatrixProviderDto,
+        TableApi,
+        SpeedProfile,
+        SpeedBucket,
+        TimeDependentTimes,
         RoutePlanDto,
         TerminationConfigDto,
         StatusResponse,
+        ScorePointDto,
+        HistoryFormat,
         GeometryResponse,
+        ExportFormat,
+        ManifestFormat,
+        Manifest,
+        ManifestStop,
         MatchAnalysisDto,
         ConstraintAnalysisDto,
         AnalyzeResponse,
         VehicleRecommendation,
         RecommendedAssignment,
         RecommendationRequest,
+        BatchRecommendationRequest,
+        VisitRecommendations,
         ApplyRecommendationRequest,
     ))
 )]
 struct ApiDoc;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Plan with a pickup `p` and a delivery `d` at the same spot.
+    fn pair_plan(pickup_partner: Option<&str>, delivery_partner: Option<&str>) -> RoutePlanDto {
+        serde_json::from_value(serde_json::json!({
+            "name": "test",
+            "southWestCorner": [0.0, 0.0],
+            "northEastCorner": [1.0, 1.0],
+            "totalDrivingTimeSeconds": 0,
+            "vehicles": [],
+            "visits": [
+                {
+                    "id": "p", "name": "Pickup", "location": [0.5, 0.5], "demand": 1,
+                    "kind": "PICKUP", "pairedVisit": pickup_partner,
+                    "minStartTime": "2025-01-05T08:00:00", "maxEndTime": "2025-01-05T18:00:00",
+                    "serviceDuration": 0
+                },
+                {
+                    "id": "d", "name": "Delivery", "location": [0.5, 0.5], "demand": 1,
+                    "kind": "DELIVERY", "pairedVisit": delivery_partner,
+                    "minStartTime": "2025-01-05T08:00:00", "maxEndTime": "2025-01-05T18:00:00",
+                    "serviceDuration": 0
+                }
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_round_trip_with_custom_horizon() {
+        let dto: RoutePlanDto = serde_json::from_value(serde_json::json!({
+            "name": "test",
+            "southWestCorner": [0.0, 0.0],
+            "northEastCorner": [1.0, 1.0],
+            "horizonStart": "2026-03-02T00:00:00",
+            "totalDrivingTimeSeconds": 0,
+            "vehicles": [{
+                "id": "van", "name": "Van", "capacity": 10,
+                "homeLocation": [0.5, 0.5],
+                "departureTime": "2026-03-02T07:00:00",
+                "visits": ["a"], "totalDemand": 0, "totalDrivingTimeSeconds": 0,
+                "arrivalTime": "2026-03-02T07:00:00"
+            }],
+            "visits": [{
+                "id": "a", "name": "A", "location": [0.5, 0.5], "demand": 1,
+                "minStartTime": "2026-03-02T08:00:00", "maxEndTime": "2026-03-02T18:00:00",
+                "serviceDuration": 3600
+            }]
+        }))
+        .unwrap();
+        let mut plan = dto.to_domain();
+        plan.finalize();
+
+        let out = RoutePlanDto::from_plan(&plan, None);
+        assert_eq!(out.horizon_start.as_deref(), Some("2026-03-02T00:00:00"));
+        assert_eq!(out.start_date_time.as_deref(), Some("2026-03-02T07:00:00"));
+        assert_eq!(out.end_date_time.as_deref(), Some("2026-03-02T09:00:00"));
+
+        let back = out.to_domain();
+        assert_eq!(back.horizon_start, plan.horizon_start);
+        assert_eq!(back.vehicles[0].departure_time, plan.vehicles[0].departure_time);
+        assert_eq!(back.visits[0].min_start_time, plan.visits[0].min_start_time);
+    }
+
+    #[tokio::test]
+    async fn test_waiting_total_matches_visits() {
+        // Each visit waits 90 seconds: 1 minute each, 2 in total
+        let dto: RoutePlanDto = serde_json::from_value(serde_json::json!({
+            "name": "test",
+            "southWestCorner": [0.0, 0.0],
+            "northEastCorner": [1.0, 1.0],
+            "waitingCostPerMinute": 3,
+            "totalDrivingTimeSeconds": 0,
+            "vehicles": [{
+                "id": "van", "name": "Van", "capacity": 10,
+                "homeLocation": [0.5, 0.5],
+                "departureTime": "2025-01-05T08:00:00",
+                "visits": ["a", "b"], "totalDemand": 0, "totalDrivingTimeSeconds": 0,
+                "arrivalTime": "2025-01-05T08:00:00"
+            }],
+            "visits": [
+                {
+                    "id": "a", "name": "A", "location": [0.5, 0.5], "demand": 1,
+                    "minStartTime": "2025-01-05T08:01:30", "maxEndTime": "2025-01-05T18:00:00",
+                    "serviceDuration": 0
+                },
+                {
+                    "id": "b", "name": "B", "location": [0.5, 0.5], "demand": 1,
+                    "minStartTime": "2025-01-05T08:03:00", "maxEndTime": "2025-01-05T18:00:00",
+                    "serviceDuration": 0
+                }
+            ]
+        }))
+        .unwrap();
+
+        let Json(analysis) = analyze_route_plan(Json(dto)).await.unwrap();
+        let waiting = analysis.constraints.iter().find(|c| c.name == "Waiting time").unwrap();
+        let soft = |score: &str| -> i64 {
+            score.split('/').nth(1).unwrap().trim_end_matches("soft").parse().unwrap()
+        };
+        let per_visit: i64 = waiting.matches.iter().map(|m| soft(&m.score)).sum();
+        assert_eq!(waiting.matches.len(), 2);
+        assert_eq!(per_visit, -6);
+        assert_eq!(soft(&waiting.score), per_visit);
+    }
+
+    #[test]
+    fn test_geometry_tolerance() {
+        let query = |tolerance| GeometryQuery { tolerance, legs: false };
+        assert_eq!(query(None).tolerance(), Ok(0.0));
+        assert_eq!(query(Some(12.5)).tolerance(), Ok(12.5));
+        assert_eq!(query(Some(-1.0)).tolerance(), Err(StatusCode::BAD_REQUEST));
+        assert_eq!(query(Some(f64::NAN)).tolerance(), Err(StatusCode::BAD_REQUEST));
+    }
+
+    #[test]
+    fn test_validate_paired_visits() {
+        assert!(pair_plan(Some("d"), Some("p")).validate().is_ok());
+        assert!(pair_plan(Some("d"), None).validate().is_ok());
+
+        // Dangling and self-referencing partners
+        assert!(pair_plan(Some("x"), None).validate().is_err());
+        assert!(pair_plan(Some("p"), None).validate().is_err());
+
+        // Partner paired elsewhere
+        assert!(pair_plan(Some("d"), Some("d")).validate().is_err());
+
+        // Two deliveries
+        let mut plan = pair_plan(Some("d"), None);
+        plan.visits[0].kind = VisitKind::Delivery;
+        assert!(plan.validate().is_err());
+        assert_eq!(check_plan(&plan), Err(StatusCode::BAD_REQUEST));
+    }
+
+    #[test]
+    fn test_validate_datetimes() {
+        let mut plan = pair_plan(None, None);
+        plan.visits[1].max_end_time = "tomorrow".to_string();
+        assert_eq!(check_plan(&plan), Err(StatusCode::BAD_REQUEST));
+
+        plan.visits[1].max_end_time = "2025-01-05T18:00:00".to_string();
+        plan.visits[1].soft_deadline = Some("2025-13-01T00:00:00".to_string());
+        assert_eq!(check_plan(&plan), Err(StatusCode::BAD_REQUEST));
+    }
+
+    #[test]
+    fn test_offsets_convert_to_horizon_timezone() {
+        let mut dto = pair_plan(None, None);
+        dto.horizon_start = Some("2025-01-05T00:00:00+02:00".to_string());
+        dto.visits[0].min_start_time = "2025-01-05T08:00:00Z".to_string();
+        dto.visits[1].min_start_time = "2025-01-05T08:00:00+02:00".to_string();
+        assert!(dto.validate().is_ok());
+
+        let plan = dto.to_domain();
+        assert_eq!(plan.horizon_start.to_string(), "2025-01-05 00:00:00");
+        assert_eq!(plan.visits[0].min_start_time, 10 * 3600);
+        assert_eq!(plan.visits[1].min_start_time, 8 * 3600);
+    }
+
+    #[test]
+    fn test_preferred_vehicle_covers_every_shift() {
+        let dto: RoutePlanDto = serde_json::from_value(serde_json::json!({
+            "name": "test",
+            "southWestCorner": [0.0, 0.0],
+            "northEastCorner": [1.0, 1.0],
+            "totalDrivingTimeSeconds": 0,
+            "vehicles": [{
+                "id": "alice", "name": "Alice", "capacity": 10,
+                "homeLocation": [0.5, 0.5],
+                "departureTime": "2026-03-02T07:00:00",
+                "shifts": [
+                    {"start": "2026-03-02T07:00:00", "end": "2026-03-02T15:00:00"},
+                    {"start": "2026-03-03T07:00:00", "end": "2026-03-03T15:00:00"}
+                ],
+                "visits": [], "totalDemand": 0, "totalDrivingTimeSeconds": 0,
+                "arrivalTime": "2026-03-02T07:00:00"
+            }, {
+                "id": "bob", "name": "Bob", "capacity": 10,
+                "homeLocation": [0.5, 0.5],
+                "departureTime": "2026-03-03T07:00:00",
+                "visits": [], "totalDemand": 0, "totalDrivingTimeSeconds": 0,
+                "arrivalTime": "2026-03-03T07:00:00"
+            }],
+            "visits": [{
+                "id": "a", "name": "A", "location": [0.5, 0.5], "demand": 1,
+                "preferredVehicle": "alice", "preferredVehicleWeight": 25,
+                "minStartTime": "2026-03-03T08:00:00", "maxEndTime": "2026-03-03T18:00:00",
+                "serviceDuration": 0
+            }]
+        }))
+        .unwrap();
+        let plan = dto.to_domain();
+        let visit = &plan.visits[0];
+        assert_eq!(visit.preferred_vehicles, vec![0, 1]);
+        assert_eq!(visit.preference_penalty(&plan.vehicles[1]), 0);
+        assert_eq!(visit.preference_penalty(&plan.vehicles[2]), 25);
+    }
+}
@@ -2,9 +2,26 @@
 //!
 //! # Constraints
 //!
-//! - **Vehicle capacity** (hard): Total demand must not exceed vehicle capacity
+//! - **Vehicle capacity** (hard): On-board load must not exceed vehicle capacity
+//!   at any point of the route (checked per trip between depot reloads)
+//! - **Dimension capacity** (hard): Same per named dimension (weight, volume, ...)
+//! - **Pickup before delivery** (hard): Both halves of a pair on the same
+//!   vehicle, pickup first
+//! - **Vehicle compatibility** (hard): Required capabilities, allowed and
+//!   forbidden vehicles
 //! - **Time windows** (hard): Service must complete before max end time
+//! - **Soft deadline** (soft): Per-visit cost per minute past the soft deadline
+//! - **Waiting time** (soft): Optional cost per minute waiting for a window to open
+//! - **Shift end** (hard): Routes must end before the vehicle's shift ends
+//! - **Unassigned visits** (hard for mandatory, soft by priority for optional
+//!   visits)
 //! - **Minimize travel time** (soft): Reduce total driving time
+//! - **Minimize vehicle cost** / **Minimize distance** (soft): Alternative
+//!   objectives, see [`RoutingObjective`]
+//! - **Minimize vehicles used** (soft): Optional fleet-size penalty
+//! - **Minimize reloads** (soft): Tie-breaker against pointless depot reloads
+//! - **Preferred vehicle** (soft): Per-visit weight when not served by the
+//!   preferred vehicle
 //!
 //! # Design
 //!
@@ -14,16 +31,55 @@
 
 use solverforge::prelude::*;
 
-use crate::domain::{Vehicle, VehicleRoutePlan};
+use crate::domain::{RoutingObjective, UnassignedReason, Vehicle, VehicleRoutePlan, Visit};
+
+/// Soft penalty per used vehicle when `minimize_vehicles_used` is enabled.
+///
+/// Large enough to dominate the travel time or cost of a typical route,
+/// so removing a vehicle is preferred over shorter routes.
+pub const VEHICLE_USED_PENALTY: i64 = 100_000;
+
+/// Hard penalty per pickup-and-delivery pair that is split across vehicles,
+/// partly unassigned, or delivered before it is picked up.
+pub const PAIR_VIOLATION_PENALTY: i64 = 100;
+
+/// Hard penalty per visit served by an incompatible vehicle.
+pub const INCOMPATIBLE_VEHICLE_PENALTY: i64 = 100;
+
+/// Soft penalty per depot reload stop on a route.
+///
+/// Only a tie-breaker: keeps reloads that do not save anything off the route.
+pub const RELOAD_PENALTY: i64 = 1;
+
+/// Hard penalty per mandatory visit left unassigned.
+pub const UNASSIGNED_VISIT_PENALTY: i64 = 1_000;
+
+/// Soft penalty per priority point of an optional visit left unassigned.
+///
+/// With the travel time objective, a priority-1 visit is dropped when
+/// serving it adds more than this many driving minutes.
+pub const UNASSIGNED_PRIORITY_WEIGHT: i64 = 60;
 
 /// Calculates the score for a vehicle routing solution.
 ///
 /// # Hard constraints
-/// - Vehicle capacity: penalize excess demand
+/// - Vehicle capacity: penalize peak on-board load above capacity
+/// - Dimension capacity: same, for each named capacity dimension
+/// - Pickup before delivery: [`PAIR_VIOLATION_PENALTY`] per violated pair
+/// - Vehicle compatibility: [`INCOMPATIBLE_VEHICLE_PENALTY`] per incompatible visit
 /// - Time windows: penalize late arrivals
+/// - Shift end: penalize minutes past the vehicle's shift end
+/// - Unassigned visits: [`UNASSIGNED_VISIT_PENALTY`] per mandatory visit
 ///
 /// # Soft constraints
-/// - Minimize total travel time (in minutes)
+/// - Minimize total travel time (in minutes), or total vehicle cost or
+///   distance (in meters) depending on the plan's [`RoutingObjective`]
+/// - Minimize vehicles used (if enabled): [`VEHICLE_USED_PENALTY`] per used vehicle
+/// - Minimize reloads: [`RELOAD_PENALTY`] per depot reload stop
+/// - Preferred vehicle: the visit's weight when served by another vehicle
+/// - Soft deadlines: each visit's cost per minute late
+/// - Waiting time: the plan's cost per minute of early-arrival waiting
+/// - Unassigned visits: priority × [`UNASSIGNED_PRIORITY_WEIGHT`] per optional visit
 ///
 /// # Examples
 ///
@@ -45,42 +101,138 @@ use crate::domain::{Vehicle, VehicleRoutePlan};
 /// assert!(score.is_feasible()); // Demand 5 <= capacity 10
 /// ```
 pub fn calculate_score(plan: &VehicleRoutePlan) -> HardSoftScore {
-    let mut hard = 0i64;
-    let mut soft = 0i64;
+    let (mut hard, mut soft) = (0i64, 0i64);
 
     for vehicle in &plan.vehicles {
-        // =====================================================================
-        // HARD: Vehicle Capacity
-        // =====================================================================
-        let total_demand: i32 = vehicle
-            .visits
-            .iter()
-            .filter_map(|&idx| plan.visits.get(idx))
-            .map(|v| v.demand)
-            .sum();
-
-        if total_demand > vehicle.capacity {
-            hard -= (total_demand - vehicle.capacity) as i64;
-        }
+        let (h, s) = vehicle_penalties(plan, vehicle);
+        hard += h;
+        soft += s;
+    }
 
-        // =====================================================================
-        // HARD: Time Windows
-        // =====================================================================
-        let late_minutes = calculate_late_minutes_for_vehicle(plan, vehicle);
-        if late_minutes > 0 {
-            hard -= late_minutes;
-        }
+    // =========================================================================
+    // HARD: Pickup Before Delivery
+    // =========================================================================
+    hard -= calculate_pair_violations(plan).len() as i64 * PAIR_VIOLATION_PENALTY;
 
-        // =====================================================================
-        // SOFT: Minimize Travel Time
-        // =====================================================================
-        let driving_seconds = plan.total_driving_time(vehicle);
-        soft -= driving_seconds / 60; // Convert to minutes
+    // =========================================================================
+    // HARD/SOFT: Unassigned Visits
+    // =========================================================================
+    for idx in plan.unassigned_visits() {
+        let (h, s) = unassigned_penalty(&plan.visits[idx]);
+        hard -= h;
+        soft -= s;
     }
 
     HardSoftScore::of(hard, soft)
 }
 
+/// Calculates the score terms that depend on a single route.
+///
+/// The vehicle need not be part of the plan, so trial routes can be scored
+/// without touching the rest of the solution. Pair violations and
+/// unassigned visits are not included.
+///
+/// # Examples
+///
+/// ```
+/// use vehicle_routing::constraints::{calculate_score, calculate_vehicle_score};
+/// use vehicle_routing::domain::{Location, Visit, Vehicle, VehicleRoutePlan};
+///
+/// let depot = Location::new(0, 0.0, 0.0);
+/// let customer = Location::new(1, 0.0, 0.01);
+/// let mut vehicle = Vehicle::new(0, "V1", 10, depot.clone());
+/// vehicle.visits = vec![0];
+///
+/// let mut plan = VehicleRoutePlan::new(
+///     "test",
+///     vec![depot, customer.clone()],
+///     vec![Visit::new(0, "A", customer)],
+///     vec![vehicle],
+/// );
+/// plan.finalize();
+///
+/// // With every visit assigned, the route is the whole score
+/// assert_eq!(calculate_vehicle_score(&plan, &plan.vehicles[0]), calculate_score(&plan));
+/// ```
+pub fn calculate_vehicle_score(plan: &VehicleRoutePlan, vehicle: &Vehicle) -> HardSoftScore {
+    let (hard, soft) = vehicle_penalties(plan, vehicle);
+    HardSoftScore::of(hard, soft)
+}
+
+/// Per-route score terms as `(hard, soft)`, both zero or negative.
+pub(crate) fn vehicle_penalties(plan: &VehicleRoutePlan, vehicle: &Vehicle) -> (i64, i64) {
+    let mut hard = 0i64;
+    let mut soft = 0i64;
+
+    // =========================================================================
+    // HARD: Vehicle Capacity
+    // =========================================================================
+    hard -= calculate_excess_capacity(plan, vehicle) as i64;
+    for dimension in 0..plan.capacity_dimensions.len() {
+        hard -= calculate_excess_dimension(plan, vehicle, dimension) as i64;
+    }
+
+    // =========================================================================
+    // HARD: Vehicle Compatibility
+    // =========================================================================
+    hard -= calculate_incompatible_visits(plan, vehicle).len() as i64 * INCOMPATIBLE_VEHICLE_PENALTY;
+
+    // =========================================================================
+    // HARD: Time Windows
+    // =========================================================================
+    let late_minutes = calculate_late_minutes_for_vehicle(plan, vehicle);
+    if late_minutes > 0 {
+        hard -= late_minutes;
+    }
+
+    // =========================================================================
+    // HARD: Shift End
+    // =========================================================================
+    hard -= calculate_overtime_minutes(plan, vehicle);
+
+    // =========================================================================
+    // SOFT: Minimize Travel Time or Vehicle Cost
+    // =========================================================================
+    soft -= calculate_objective_penalty(plan, vehicle);
+
+    // =========================================================================
+    // SOFT: Minimize Vehicles Used
+    // =========================================================================
+    if plan.minimize_vehicles_used && plan.is_vehicle_used(vehicle) {
+        soft -= VEHICLE_USED_PENALTY;
+    }
+
+    // =========================================================================
+    // SOFT: Soft Deadlines and Waiting Time
+    // =========================================================================
+    soft -= calculate_soft_lateness_penalty(plan, vehicle);
+    if plan.waiting_cost_per_minute != 0 {
+        soft -= calculate_waiting_minutes(plan, vehicle) * plan.waiting_cost_per_minute;
+    }
+
+    // =========================================================================
+    // SOFT: Minimize Reloads
+    // =========================================================================
+    soft -= plan.reload_count(vehicle) as i64 * RELOAD_PENALTY;
+
+    // =========================================================================
+    // SOFT: Preferred Vehicle
+    // =========================================================================
+    soft -= calculate_preference_penalty(plan, vehicle);
+
+    (hard, soft)
+}
+
+/// Penalty for leaving a visit unassigned as `(hard, soft)`, both zero or
+/// positive.
+pub(crate) fn unassigned_penalty(visit: &Visit) -> (i64, i64) {
+    if visit.optional {
+        (0, visit.priority * UNASSIGNED_PRIORITY_WEIGHT)
+    } else {
+        (UNASSIGNED_VISIT_PENALTY, 0)
+    }
+}
+
 /// Calculates total late minutes for a vehicle's route.
 ///
 /// A visit is late if service finishes after `max_end_time`.
@@ -99,7 +251,7 @@ fn calculate_late_minutes_for_vehicle(plan: &VehicleRoutePlan, vehicle: &Vehicle
         };
 
         // Travel to this visit
-        let travel = plan.travel_time(current_loc_idx, visit.location.index);
+        let travel = plan.travel_time_at(current_loc_idx, visit.location.index, current_time);
         let arrival = current_time + travel;
 
         // Service starts at max(arrival, min_start_time)
@@ -159,7 +311,143 @@ pub fn calculate_late_minutes(plan: &VehicleRoutePlan, vehicle: &Vehicle) -> i64
     calculate_late_minutes_for_vehicle(plan, vehicle)
 }
 
-/// Calculates excess demand for a vehicle (0 if under capacity).
+/// Calculates the soft deadline penalty for a vehicle's route.
+///
+/// Each visit finishing after its soft deadline costs its
+/// `lateness_cost_per_minute` per started minute late.
+///
+/// # Examples
+///
+/// ```
+/// use vehicle_routing::constraints::{calculate_late_minutes, calculate_soft_lateness_penalty};
+/// use vehicle_routing::domain::{Location, Visit, Vehicle, VehicleRoutePlan};
+///
+/// let depot = Location::new(0, 0.0, 0.0);
+/// let visits = vec![
+///     Visit::new(0, "A", depot.clone())
+///         .with_service_duration(3600)
+///         .with_time_window(8 * 3600, 10 * 3600)
+///         .with_soft_deadline(8 * 3600 + 30 * 60, 5), // Should be done by 8:30
+/// ];
+/// let mut vehicle = Vehicle::new(0, "V1", 10, depot.clone()).with_departure_time(8 * 3600);
+/// vehicle.visits = vec![0];
+///
+/// let mut plan = VehicleRoutePlan::new("test", vec![depot], visits, vec![vehicle.clone()]);
+/// plan.finalize();
+///
+/// // Done at 9:00: 30 minutes past the soft deadline, within the hard window
+/// assert_eq!(calculate_soft_lateness_penalty(&plan, &vehicle), 150);
+/// assert_eq!(calculate_late_minutes(&plan, &vehicle), 0);
+/// ```
+pub fn calculate_soft_lateness_penalty(plan: &VehicleRoutePlan, vehicle: &Vehicle) -> i64 {
+    plan.calculate_route_times(vehicle)
+        .iter()
+        .filter_map(|timing| {
+            let visit = plan.visits.get(timing.visit_idx)?;
+            let deadline = visit.soft_deadline?;
+            let late_seconds = timing.departure - deadline;
+            // Round up to minutes
+            (late_seconds > 0).then(|| (late_seconds + 59) / 60 * visit.lateness_cost_per_minute)
+        })
+        .sum()
+}
+
+/// Calculates minutes a vehicle waits for time windows to open.
+///
+/// Each visit's wait is rounded down to whole minutes before summing, so
+/// the total matches the per-visit figures reported by analysis.
+///
+/// # Examples
+///
+/// ```
+/// use vehicle_routing::constraints::calculate_waiting_minutes;
+/// use vehicle_routing::domain::{Location, Visit, Vehicle, VehicleRoutePlan};
+///
+/// let depot = Location::new(0, 0.0, 0.0);
+/// let visits = vec![Visit::new(0, "A", depot.clone()).with_time_window(9 * 3600, 12 * 3600)];
+/// let mut vehicle = Vehicle::new(0, "V1", 10, depot.clone()).with_departure_time(8 * 3600);
+/// vehicle.visits = vec![0];
+///
+/// let mut plan = VehicleRoutePlan::new("test", vec![depot], visits, vec![vehicle.clone()]);
+/// plan.finalize();
+///
+/// assert_eq!(calculate_waiting_minutes(&plan, &vehicle), 60);
+/// ```
+pub fn calculate_waiting_minutes(plan: &VehicleRoutePlan, vehicle: &Vehicle) -> i64 {
+    plan.calculate_route_times(vehicle)
+        .iter()
+        .map(|timing| timing.waiting_minutes())
+        .sum()
+}
+
+/// Calculates minutes the route ends after the vehicle's shift end.
+///
+/// # Examples
+///
+/// ```
+/// use vehicle_routing::constraints::calculate_overtime_minutes;
+/// use vehicle_routing::domain::{Location, Visit, Vehicle, VehicleRoutePlan};
+///
+/// let depot = Location::new(0, 0.0, 0.0);
+/// let visits = vec![Visit::new(0, "A", depot.clone()).with_service_duration(2 * 3600)];
+/// let mut vehicle = Vehicle::new(0, "V1", 10, depot.clone()).with_shift(22 * 3600, 23 * 3600);
+/// vehicle.visits = vec![0];
+///
+/// let mut plan = VehicleRoutePlan::new("test", vec![depot], visits, vec![vehicle.clone()]);
+/// plan.finalize();
+///
+/// // Night shift 22:00-23:00, but service runs until midnight
+/// assert_eq!(calculate_overtime_minutes(&plan, &vehicle), 60);
+/// ```
+pub fn calculate_overtime_minutes(plan: &VehicleRoutePlan, vehicle: &Vehicle) -> i64 {
+    match vehicle.shift_end {
+        Some(shift_end) if !vehicle.visits.is_empty() => {
+            let overtime = plan.route_end_time(vehicle) - shift_end;
+            // Round up to minutes
+            if overtime > 0 { (overtime + 59) / 60 } else { 0 }
+        }
+        _ => 0,
+    }
+}
+
+/// Calculates the soft objective penalty for a vehicle's route.
+///
+/// Driving minutes for [`RoutingObjective::TravelTime`], vehicle cost for
+/// [`RoutingObjective::Cost`], meters driven for [`RoutingObjective::Distance`].
+///
+/// # Examples
+///
+/// ```
+/// use vehicle_routing::constraints::calculate_objective_penalty;
+/// use vehicle_routing::domain::{Location, RoutingObjective, Visit, Vehicle, VehicleRoutePlan};
+///
+/// let depot = Location::new(0, 0.0, 0.0);
+/// let locations = vec![depot.clone()];
+/// let visits = vec![Visit::new(0, "A", depot.clone())];
+/// let mut vehicle = Vehicle::new(0, "V1", 10, depot).with_costs(1_000, 0, 0);
+/// vehicle.visits = vec![0];
+///
+/// let mut plan = VehicleRoutePlan::new("test", locations, visits, vec![vehicle.clone()]);
+/// plan.finalize();
+/// assert_eq!(calculate_objective_penalty(&plan, &vehicle), 0);
+///
+/// plan.objective = RoutingObjective::Cost;
+/// assert_eq!(calculate_objective_penalty(&plan, &vehicle), 1_000);
+/// ```
+#[inline]
+pub fn calculate_objective_penalty(plan: &VehicleRoutePlan, vehicle: &Vehicle) -> i64 {
+    match plan.objective {
+        RoutingObjective::TravelTime => plan.total_driving_time(vehicle) / 60,
+        RoutingObjective::Cost => plan.vehicle_cost(vehicle),
+        RoutingObjective::Distance => plan.total_distance_meters(vehicle).round() as i64,
+    }
+}
+
+/// Calculates excess load for a vehicle (0 if under capacity).
+///
+/// Uses the peak on-board load, so pickups that are delivered later in the
+/// route count while on board. For delivery-only routes this equals total
+/// demand minus capacity.
 ///
 /// # Examples
 ///
@@ -184,12 +472,439 @@ pub fn calculate_late_minutes(plan: &VehicleRoutePlan, vehicle: &Vehicle) -> i64
 /// ```
 #[inline]
 pub fn calculate_excess_capacity(plan: &VehicleRoutePlan, vehicle: &Vehicle) -> i32 {
-    let total_demand: i32 = vehicle
+    plan.peak_load(vehicle).saturating_sub(vehicle.capacity).max(0)
+}
+
+/// Calculates excess load of a named capacity dimension (0 if under capacity).
+///
+/// # Examples
+///
+/// ```
+/// use vehicle_routing::constraints::calculate_excess_dimension;
+/// use vehicle_routing::domain::{Location, Visit, Vehicle, VehicleRoutePlan};
+///
+/// let depot = Location::new(0, 0.0, 0.0);
+/// let visits = vec![
+///     Visit::new(0, "A", depot.clone()).with_dimension_demands(vec![600]),
+///     Visit::new(1, "B", depot.clone()).with_dimension_demands(vec![500]),
+/// ];
+/// let mut vehicle = Vehicle::new(0, "V1", 10, depot.clone()).with_dimension_capacities(vec![1000]);
+/// vehicle.visits = vec![0, 1];
+///
+/// let mut plan = VehicleRoutePlan::new("test", vec![depot], visits, vec![vehicle.clone()]);
+/// plan.capacity_dimensions = vec!["weight".into()];
+///
+/// assert_eq!(calculate_excess_dimension(&plan, &vehicle, 0), 100);
+/// ```
+#[inline]
+pub fn calculate_excess_dimension(plan: &VehicleRoutePlan, vehicle: &Vehicle, dimension: usize) -> i32 {
+    let capacity = vehicle.dimension_capacity(dimension);
+    plan.peak_dimension_load(vehicle, dimension).saturating_sub(capacity).max(0)
+}
+
+/// Returns the visits on a vehicle's route that the vehicle may not serve.
+///
+/// # Examples
+///
+/// ```
+/// use vehicle_routing::constraints::calculate_incompatible_visits;
+/// use vehicle_routing::domain::{Location, Visit, Vehicle, VehicleRoutePlan};
+///
+/// let depot = Location::new(0, 0.0, 0.0);
+/// let visits = vec![
+///     Visit::new(0, "Frozen", depot.clone()).with_required_capability("refrigerated"),
+///     Visit::new(1, "Dry", depot.clone()),
+/// ];
+/// let mut vehicle = Vehicle::new(0, "Van", 10, depot.clone());
+/// vehicle.visits = vec![0, 1];
+///
+/// let plan = VehicleRoutePlan::new("test", vec![depot], visits, vec![vehicle.clone()]);
+/// assert_eq!(calculate_incompatible_visits(&plan, &vehicle), vec![0]);
+/// ```
+pub fn calculate_incompatible_visits(plan: &VehicleRoutePlan, vehicle: &Vehicle) -> Vec<usize> {
+    vehicle
+        .visits
+        .iter()
+        .copied()
+        .filter(|&idx| {
+            plan.visits
+                .get(idx)
+                .is_some_and(|visit| !visit.is_compatible_with(vehicle))
+        })
+        .collect()
+}
+
+/// Calculates the preferred-vehicle penalty for a vehicle's route.
+#[inline]
+pub fn calculate_preference_penalty(plan: &VehicleRoutePlan, vehicle: &Vehicle) -> i64 {
+    vehicle
         .visits
         .iter()
         .filter_map(|&idx| plan.visits.get(idx))
-        .map(|v| v.demand)
-        .sum();
+        .map(|visit| visit.preference_penalty(vehicle))
+        .sum()
+}
 
-    (total_demand - vehicle.capacity).max(0)
+/// Returns the `(pickup, delivery)` pairs that violate pickup-before-delivery.
+///
+/// A pair is violated unless both halves are on the same vehicle with the
+/// pickup first, or both are unassigned.
+///
+/// # Examples
+///
+/// ```
+/// use vehicle_routing::constraints::calculate_pair_violations;
+/// use vehicle_routing::domain::{Location, Visit, Vehicle, VehicleRoutePlan};
+///
+/// let depot = Location::new(0, 0.0, 0.0);
+/// let visits = vec![
+///     Visit::new(0, "Pickup", depot.clone()).with_pickup_for(1),
+///     Visit::new(1, "Delivery", depot.clone()).with_delivery_for(0),
+/// ];
+/// let mut vehicle = Vehicle::new(0, "V1", 10, depot.clone());
+/// vehicle.visits = vec![1, 0]; // Delivery before pickup
+///
+/// let mut plan = VehicleRoutePlan::new("test", vec![depot], visits, vec![vehicle]);
+/// assert_eq!(calculate_pair_violations(&plan), vec![(0, 1)]);
+///
+/// plan.vehicles[0].visits = vec![0, 1];
+/// assert!(calculate_pair_violations(&plan).is_empty());
+/// ```
+pub fn calculate_pair_violations(plan: &VehicleRoutePlan) -> Vec<(usize, usize)> {
+    let pairs = plan.pickup_delivery_pairs();
+    if pairs.is_empty() {
+        return Vec::new();
+    }
+
+    let positions = plan.visit_positions();
+    pairs
+        .into_iter()
+        .filter(|&(pickup, delivery)| {
+            let p = positions.get(pickup).copied().flatten();
+            let d = positions.get(delivery).copied().flatten();
+            match (p, d) {
+                (Some((pv, pp)), Some((dv, dp))) => pv != dv || pp >= dp,
+                (None, None) => false,
+                _ => true,
+            }
+        })
+        .collect()
+}
+
+/// Explains why an unassigned visit is not on any route.
+///
+/// Tries inserting the visit (with its pair partner) at every position of
+/// every compatible vehicle. If some insertion adds no capacity or time
+/// window violation, the visit was dropped for its low priority.
+///
+/// # Examples
+///
+/// ```
+/// use vehicle_routing::constraints::unassigned_reason;
+/// use vehicle_routing::domain::{Location, UnassignedReason, Visit, Vehicle, VehicleRoutePlan};
+///
+/// let depot = Location::new(0, 0.0, 0.0);
+/// let visits = vec![
+///     Visit::new(0, "Bulky", depot.clone()).with_demand(20).with_optional(1),
+///     Visit::new(1, "Frozen", depot.clone()).with_required_capability("refrigerated"),
+///     Visit::new(2, "Small", depot.clone()).with_optional(1),
+/// ];
+/// let vehicle = Vehicle::new(0, "Van", 10, depot.clone());
+///
+/// let mut plan = VehicleRoutePlan::new("test", vec![depot], visits, vec![vehicle]);
+/// plan.finalize();
+///
+/// assert_eq!(unassigned_reason(&plan, 0), UnassignedReason::Capacity);
+/// assert_eq!(unassigned_reason(&plan, 1), UnassignedReason::NoCompatibleVehicle);
+/// assert_eq!(unassigned_reason(&plan, 2), UnassignedReason::LowPriority);
+/// ```
+pub fn unassigned_reason(plan: &VehicleRoutePlan, visit_idx: usize) -> UnassignedReason {
+    let positions = plan.visit_positions();
+    let mut group = plan.visit_group(visit_idx);
+    group.retain(|&idx| positions[idx].is_none());
+
+    let mut compatible = plan
+        .vehicles
+        .iter()
+        .filter(|v| group.iter().all(|&idx| plan.visits[idx].is_compatible_with(v)))
+        .peekable();
+    if compatible.peek().is_none() {
+        return UnassignedReason::NoCompatibleVehicle;
+    }
+
+    let mut fits_capacity = false;
+    for vehicle in compatible {
+        let excess = capacity_excess(plan, vehicle);
+        let late = calculate_late_minutes_for_vehicle(plan, vehicle);
+
+        for pos in 0..=vehicle.visits.len() {
+            let mut trial = vehicle.clone();
+            for (offset, &idx) in group.iter().enumerate() {
+                trial.visits.insert(pos + offset, idx);
+            }
+
+            if capacity_excess(plan, &trial) > excess {
+                continue;
+            }
+            fits_capacity = true;
+            if calculate_late_minutes_for_vehicle(plan, &trial) <= late {
+                return UnassignedReason::LowPriority;
+            }
+        }
+    }
+
+    if fits_capacity {
+        UnassignedReason::TimeWindow
+    } else {
+        UnassignedReason::Capacity
+    }
+}
+
+/// Total excess load over the primary capacity and all named dimensions.
+fn capacity_excess(plan: &VehicleRoutePlan, vehicle: &Vehicle) -> i64 {
+    (0..plan.capacity_dimensions.len())
+        .map(|d| calculate_excess_dimension(plan, vehicle, d) as i64)
+        .sum::<i64>()
+        + calculate_excess_capacity(plan, vehicle) as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::Location;
+
+    fn depot() -> Location {
+        Location::new(0, 0.0, 0.0)
+    }
+
+    /// Plan with every visit at the depot, so travel costs nothing.
+    fn plan(visits: Vec<Visit>, vehicles: Vec<Vehicle>) -> VehicleRoutePlan {
+        let mut plan = VehicleRoutePlan::new("test", vec![depot()], visits, vehicles);
+        plan.finalize();
+        plan
+    }
+
+    fn route(mut vehicle: Vehicle, visits: Vec<usize>) -> Vehicle {
+        vehicle.visits = visits;
+        vehicle
+    }
+
+    #[test]
+    fn test_vehicle_used_penalty() {
+        let visits = vec![Visit::new(0, "A", depot())];
+        let vehicles = vec![
+            route(Vehicle::new(0, "Used", 10, depot()).with_costs(500, 0, 0), vec![0]),
+            Vehicle::new(1, "Idle", 10, depot()).with_costs(500, 0, 0),
+        ];
+        let mut plan = plan(visits, vehicles);
+        assert_eq!(calculate_score(&plan), HardSoftScore::of(0, 0));
+
+        plan.minimize_vehicles_used = true;
+        assert_eq!(calculate_score(&plan), HardSoftScore::of(0, -VEHICLE_USED_PENALTY));
+
+        // Only the used vehicle pays its fixed cost
+        plan.minimize_vehicles_used = false;
+        plan.objective = RoutingObjective::Cost;
+        assert_eq!(calculate_score(&plan), HardSoftScore::of(0, -500));
+    }
+
+    #[test]
+    fn test_pair_violations() {
+        let visits = vec![
+            Visit::new(0, "Pickup", depot()).with_pickup_for(1),
+            Visit::new(1, "Delivery", depot()).with_delivery_for(0),
+        ];
+        let vehicles = vec![
+            route(Vehicle::new(0, "V1", 10, depot()), vec![0, 1]),
+            Vehicle::new(1, "V2", 10, depot()),
+        ];
+        let mut plan = plan(visits, vehicles);
+        assert!(calculate_pair_violations(&plan).is_empty());
+        assert_eq!(calculate_score(&plan).hard(), 0);
+
+        plan.vehicles[0].visits = vec![1, 0];
+        assert_eq!(calculate_score(&plan).hard(), -PAIR_VIOLATION_PENALTY);
+
+        // Split across vehicles
+        plan.vehicles[0].visits = vec![0];
+        plan.vehicles[1].visits = vec![1];
+        assert_eq!(calculate_pair_violations(&plan), vec![(0, 1)]);
+    }
+
+    #[test]
+    fn test_dimension_capacity() {
+        let visits = vec![
+            Visit::new(0, "A", depot()).with_demand(4).with_dimension_demands(vec![600, 3]),
+            Visit::new(1, "B", depot()).with_demand(4).with_dimension_demands(vec![500, 2]),
+        ];
+        let vehicle = Vehicle::new(0, "V1", 10, depot()).with_dimension_capacities(vec![1000, 4]);
+        let mut plan = plan(visits, vec![route(vehicle, vec![0, 1])]);
+        plan.capacity_dimensions = vec!["weight".into(), "pallets".into()];
+
+        // 100 kg and 1 pallet over, primary capacity fine
+        assert_eq!(calculate_excess_capacity(&plan, &plan.vehicles[0]), 0);
+        assert_eq!(calculate_score(&plan).hard(), -101);
+    }
+
+    #[test]
+    fn test_excess_per_dimension_saturates() {
+        let visits = vec![
+            Visit::new(0, "A", depot()).with_demand(i32::MAX).with_dimension_demands(vec![30, 2, 7]),
+            Visit::new(1, "B", depot()).with_demand(0).with_dimension_demands(vec![30, 3, 1]),
+        ];
+        // Negative capacity (bad input) must not overflow the subtraction
+        let vehicle = Vehicle::new(0, "V1", -1, depot()).with_dimension_capacities(vec![50, 10]);
+        let plan = plan(visits, vec![route(vehicle, vec![0, 1])]);
+        let v = &plan.vehicles[0];
+
+        assert_eq!(calculate_excess_capacity(&plan, v), i32::MAX);
+        assert_eq!(calculate_excess_dimension(&plan, v, 0), 10);
+        assert_eq!(calculate_excess_dimension(&plan, v, 1), 0);
+        // Undeclared dimension is unconstrained
+        assert_eq!(calculate_excess_dimension(&plan, v, 2), 0);
+    }
+
+    #[test]
+    fn test_compatibility_and_preference() {
+        let visits = vec![
+            Visit::new(0, "Frozen", depot()).with_required_capability("refrigerated"),
+            Visit::new(1, "Regular", depot()).with_preferred_vehicle(1, 25),
+        ];
+        let vehicles = vec![
+            route(Vehicle::new(0, "Van", 10, depot()), vec![0, 1]),
+            Vehicle::new(1, "Reefer", 10, depot()).with_capability("refrigerated"),
+        ];
+        let mut plan = plan(visits, vehicles);
+        assert_eq!(
+            calculate_score(&plan),
+            HardSoftScore::of(-INCOMPATIBLE_VEHICLE_PENALTY, -25)
+        );
+
+        plan.vehicles[0].visits.clear();
+        plan.vehicles[1].visits = vec![0, 1];
+        assert_eq!(calculate_score(&plan), HardSoftScore::of(0, 0));
+    }
+
+    #[test]
+    fn test_unassigned_penalty_by_priority() {
+        let visits = vec![
+            Visit::new(0, "Mandatory", depot()),
+            Visit::new(1, "Optional", depot()).with_optional(3),
+        ];
+        let plan = plan(visits, vec![Vehicle::new(0, "V1", 10, depot())]);
+        assert_eq!(
+            calculate_score(&plan),
+            HardSoftScore::of(-UNASSIGNED_VISIT_PENALTY, -3 * UNASSIGNED_PRIORITY_WEIGHT)
+        );
+    }
+
+    #[test]
+    fn test_end_location_and_open_route() {
+        let home = depot();
+        let customer = Location::new(1, 0.0, 0.1);
+        let hub = Location::new(2, 0.0, 0.2);
+        let visits = vec![Visit::new(0, "A", customer.clone())];
+        let vehicles = vec![
+            route(Vehicle::new(0, "Round trip", 10, home.clone()), vec![0]),
+            route(Vehicle::new(1, "To hub", 10, home.clone()).with_end_location(hub.clone()), vec![0]),
+            route(Vehicle::new(2, "Open", 10, home.clone()).with_open_route(), vec![0]),
+        ];
+        let mut plan = VehicleRoutePlan::new("test", vec![home, customer, hub], visits, vehicles);
+        plan.finalize();
+
+        let leg = plan.travel_time(0, 1);
+        assert_eq!(plan.total_driving_time(&plan.vehicles[0]), 2 * leg);
+        assert_eq!(plan.total_driving_time(&plan.vehicles[1]), leg + plan.travel_time(1, 2));
+        assert_eq!(plan.total_driving_time(&plan.vehicles[2]), leg);
+        assert_eq!(calculate_objective_penalty(&plan, &plan.vehicles[2]), leg / 60);
+    }
+
+    #[test]
+    fn test_reloads_reset_capacity() {
+        let visits = vec![
+            Visit::new(0, "A", depot()).with_demand(20),
+            Visit::new(1, "B", depot()).with_demand(20),
+        ];
+        let vehicle = Vehicle::new(0, "Van", 30, depot()).with_reloads(1, 900);
+        let mut plan = plan(visits, vec![route(vehicle, vec![0, 1])]);
+        plan.add_reload_stops();
+        assert_eq!(calculate_score(&plan), HardSoftScore::of(-10, 0));
+
+        plan.vehicles[0].visits = vec![0, 2, 1];
+        assert_eq!(calculate_score(&plan), HardSoftScore::of(0, -RELOAD_PENALTY));
+    }
+
+    #[test]
+    fn test_reload_only_route_is_unused() {
+        let vehicle = Vehicle::new(0, "Van", 10, depot())
+            .with_costs(500, 0, 0)
+            .with_shift(32 * 3600, 40 * 3600)
+            .with_reloads(1, 900);
+        let mut plan = plan(Vec::new(), vec![vehicle]);
+        plan.add_reload_stops();
+        plan.vehicles[0].visits = vec![0];
+        plan.minimize_vehicles_used = true;
+        plan.objective = RoutingObjective::Cost;
+
+        // Day-two reload is on time, and the vehicle pays neither fixed cost nor usage
+        assert_eq!(plan.vehicles_used(), 0);
+        assert_eq!(plan.vehicle_cost(&plan.vehicles[0]), 0);
+        assert_eq!(calculate_score(&plan), HardSoftScore::of(0, -RELOAD_PENALTY));
+    }
+
+    #[test]
+    fn test_shift_across_midnight() {
+        let visits = vec![Visit::new(0, "A", depot())
+            .with_service_duration(3 * 3600)
+            .with_time_window(0, 48 * 3600)];
+        let vehicle = Vehicle::new(0, "Night", 10, depot()).with_shift(22 * 3600, 26 * 3600);
+        let mut plan = plan(visits, vec![route(vehicle, vec![0])]);
+
+        // Ends at 1am the next day, within the shift
+        let end = plan.route_end_time(&plan.vehicles[0]);
+        assert_eq!(plan.datetime_at(end).to_string(), "2025-01-06 01:00:00");
+        assert_eq!(calculate_score(&plan).hard(), 0);
+
+        plan.vehicles[0].shift_end = Some(24 * 3600);
+        assert_eq!(calculate_overtime_minutes(&plan, &plan.vehicles[0]), 60);
+        assert_eq!(calculate_score(&plan).hard(), -60);
+    }
+
+    #[test]
+    fn test_soft_deadline_and_waiting() {
+        let visits = vec![
+            Visit::new(0, "Early", depot()).with_time_window(9 * 3600, 12 * 3600),
+            Visit::new(1, "Slow", depot())
+                .with_service_duration(3600)
+                .with_time_window(0, 12 * 3600)
+                .with_soft_deadline(9 * 3600 + 30 * 60, 2),
+        ];
+        let vehicle = Vehicle::new(0, "V1", 10, depot()).with_departure_time(8 * 3600);
+        let mut plan = plan(visits, vec![route(vehicle, vec![0, 1])]);
+
+        // Waits an hour for A, then B finishes at 10:00, 30 minutes past its deadline
+        assert_eq!(calculate_score(&plan), HardSoftScore::of(0, -60));
+
+        plan.waiting_cost_per_minute = 1;
+        assert_eq!(calculate_score(&plan), HardSoftScore::of(0, -120));
+    }
+
+    #[test]
+    fn test_distance_objective_and_emissions() {
+        let home = depot();
+        let customer = Location::new(1, 0.0, 0.1);
+        let visits = vec![Visit::new(0, "A", customer.clone())];
+        let vehicle = Vehicle::new(0, "Van", 10, home.clone()).with_emission_factors(10.0, 2.5);
+        let vehicles = vec![route(vehicle, vec![0])];
+        let mut plan = VehicleRoutePlan::new("test", vec![home, customer], visits, vehicles);
+        plan.finalize();
+        plan.objective = RoutingObjective::Distance;
+
+        let meters = plan.total_distance_meters(&plan.vehicles[0]);
+        assert!(meters > 20_000.0 && meters < 24_000.0);
+        assert_eq!(calculate_score(&plan).soft(), -(meters.round() as i64));
+
+        let expected_kg = meters / 1000.0 * 0.1 * 2.5;
+        assert!((plan.vehicle_emissions_kg(&plan.vehicles[0]) - expected_kg).abs() < 1e-9);
+    }
 }
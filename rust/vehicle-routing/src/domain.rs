@@ -5,21 +5,50 @@
 //! Models a vehicle routing problem with:
 //! - Geographic [`Location`]s with haversine distance calculation
 //! - Customer [`Visit`]s with time windows, demand, and service duration
-//! - [`Vehicle`]s with capacity constraints and routes
-//! - [`VehicleRoutePlan`] as the complete planning solution
+//! - Pickup-and-delivery pairs via [`VisitKind`] and `Visit.paired_visit`
+//! - Vehicle capabilities, visit requirements and preferred vehicles
+//! - Optional visits with a priority, which the solver may leave unassigned
+//! - Multi-trip routes: depot reload stops ([`VisitKind::Reload`]) split a
+//!   route into trips with capacity checked per trip
+//! - Named capacity dimensions (weight, volume, ...) alongside the primary
+//!   `capacity`/`demand` quantity
+//! - [`Vehicle`]s with capacity constraints, operating costs and routes,
+//!   optionally ending somewhere other than their start or not returning at all
+//! - [`VehicleRoutePlan`] as the complete planning solution, with times as
+//!   offsets from an absolute planning-horizon start (multi-day capable)
 //!
 //! # Design
 //!
 //! All scoring uses direct access to the plan's travel time matrix.
 //! No global state or RwLock overhead.
 
+use crate::traffic::{SpeedProfile, TimeDependentTimes};
+use chrono::{NaiveDate, NaiveDateTime, Timelike};
+use ordered_float::OrderedFloat;
 use serde::{Deserialize, Serialize};
 use solverforge::prelude::*;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
+use utoipa::ToSchema;
 
 /// Average driving speed in km/h for travel time estimation.
 pub const AVERAGE_SPEED_KMPH: f64 = 50.0;
 
+/// Name of the primary capacity dimension (`Vehicle.capacity` and
+/// `Visit.demand`) when capacities are given in named form.
+pub const PRIMARY_DIMENSION: &str = "quantity";
+
+/// Time window end for stops without a deadline: 100 years past the horizon
+/// start, effectively unbounded while still formatting as a date.
+pub const UNBOUNDED_END_TIME: i64 = 100 * 365 * 24 * 3600;
+
+/// Default planning-horizon start: midnight of 2025-01-05 (matches the
+/// frontend's base date).
+pub fn default_horizon_start() -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2025, 1, 5)
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .expect("valid default horizon date")
+}
+
 /// Earth radius in meters for haversine calculation.
 const EARTH_RADIUS_M: f64 = 6_371_000.0;
 
@@ -127,9 +156,12 @@ impl Location {
 ///
 /// - `min_start_time`: Earliest time service can begin (vehicle may wait)
 /// - `max_end_time`: Latest time service must finish (hard constraint)
+/// - `soft_deadline`: Optional earlier time service should finish by;
+///   each minute past it costs `lateness_cost_per_minute` (soft constraint)
 /// - `service_duration`: Time required to complete the visit
 ///
-/// All times are in seconds from midnight.
+/// All times are in seconds from the plan's `horizon_start` (midnight of the
+/// first planning day), so values beyond 86400 fall on later days.
 ///
 /// # Examples
 ///
@@ -158,15 +190,96 @@ pub struct Visit {
     pub location: Location,
     /// Quantity demanded (must fit in vehicle capacity).
     pub demand: i32,
-    /// Earliest service start time (seconds from midnight).
+    /// Earliest service start time (seconds from horizon start).
     #[serde(rename = "minStartTime")]
     pub min_start_time: i64,
-    /// Latest service end time (seconds from midnight).
+    /// Latest service end time (seconds from horizon start).
     #[serde(rename = "maxEndTime")]
     pub max_end_time: i64,
     /// Service duration in seconds.
     #[serde(rename = "serviceDuration")]
     pub service_duration: i64,
+    /// Time service should finish by (seconds from horizon start); later
+    /// is tolerated at a cost until `max_end_time`.
+    #[serde(rename = "softDeadline", default)]
+    pub soft_deadline: Option<i64>,
+    /// Soft penalty per minute past `soft_deadline`.
+    #[serde(rename = "latenessCostPerMinute", default = "default_lateness_cost")]
+    pub lateness_cost_per_minute: i64,
+    /// Whether goods are loaded (pickup) or unloaded (delivery) here.
+    #[serde(default)]
+    pub kind: VisitKind,
+    /// The other half of a pickup-and-delivery pair, if any.
+    ///
+    /// A paired delivery unloads goods picked up at its pickup rather than
+    /// goods loaded at the depot.
+    #[serde(rename = "pairedVisit", default)]
+    pub paired_visit: Option<usize>,
+    /// Demand per named dimension, indexed like `VehicleRoutePlan.capacity_dimensions`.
+    ///
+    /// Missing entries count as zero demand.
+    #[serde(rename = "dimensionDemands", default)]
+    pub dimension_demands: Vec<i32>,
+    /// Capabilities the serving vehicle must have (e.g. "refrigerated", "tail-lift").
+    #[serde(rename = "requiredCapabilities", default)]
+    pub required_capabilities: BTreeSet<String>,
+    /// IDs of the only vehicles allowed to serve this visit (empty = any).
+    #[serde(rename = "allowedVehicles", default)]
+    pub allowed_vehicles: Vec<usize>,
+    /// IDs of vehicles that must not serve this visit.
+    #[serde(rename = "forbiddenVehicles", default)]
+    pub forbidden_vehicles: Vec<usize>,
+    /// IDs of the vehicles the customer prefers (empty = no preference),
+    /// e.g. every shift of the preferred driver.
+    #[serde(rename = "preferredVehicles", default)]
+    pub preferred_vehicles: Vec<usize>,
+    /// Soft penalty when served by a vehicle other than the preferred one.
+    #[serde(rename = "preferredVehicleWeight", default)]
+    pub preferred_vehicle_weight: i64,
+    /// Whether the solver may leave this visit unassigned.
+    #[serde(default)]
+    pub optional: bool,
+    /// Importance of an optional visit; leaving it unassigned costs
+    /// `priority` times [`UNASSIGNED_PRIORITY_WEIGHT`](crate::constraints::UNASSIGNED_PRIORITY_WEIGHT)
+    /// soft points.
+    #[serde(default = "default_priority")]
+    pub priority: i64,
+}
+
+fn default_priority() -> i64 {
+    1
+}
+
+fn default_lateness_cost() -> i64 {
+    1
+}
+
+/// Why a visit was left unassigned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum UnassignedReason {
+    /// No vehicle has the required capabilities or is allowed to serve it.
+    NoCompatibleVehicle,
+    /// No compatible vehicle has room for its demand.
+    Capacity,
+    /// No compatible vehicle can reach it within its time window.
+    TimeWindow,
+    /// It could be served, but its priority does not cover the extra cost.
+    LowPriority,
+}
+
+/// Whether a visit loads or unloads goods.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum VisitKind {
+    /// Unloads `demand` (loaded at the depot unless paired with a pickup).
+    #[default]
+    Delivery,
+    /// Loads `demand` on board, to be unloaded at the paired delivery.
+    Pickup,
+    /// Return to the vehicle's depot between trips: unloads collected goods
+    /// and loads the depot deliveries of the next trip.
+    Reload,
 }
 
 impl Visit {
@@ -180,6 +293,18 @@ impl Visit {
             min_start_time: 0,
             max_end_time: 24 * 3600,
             service_duration: 0,
+            soft_deadline: None,
+            lateness_cost_per_minute: default_lateness_cost(),
+            kind: VisitKind::Delivery,
+            paired_visit: None,
+            dimension_demands: Vec::new(),
+            required_capabilities: BTreeSet::new(),
+            allowed_vehicles: Vec::new(),
+            forbidden_vehicles: Vec::new(),
+            preferred_vehicles: Vec::new(),
+            preferred_vehicle_weight: 0,
+            optional: false,
+            priority: default_priority(),
         }
     }
 
@@ -189,7 +314,7 @@ impl Visit {
         self
     }
 
-    /// Sets the time window (min_start_time, max_end_time) in seconds from midnight.
+    /// Sets the time window (min_start_time, max_end_time) in seconds from horizon start.
     pub fn with_time_window(mut self, min_start: i64, max_end: i64) -> Self {
         self.min_start_time = min_start;
         self.max_end_time = max_end;
@@ -202,6 +327,215 @@ impl Visit {
         self
     }
 
+    /// Sets a soft deadline and the soft penalty per minute past it.
+    pub fn with_soft_deadline(mut self, deadline: i64, cost_per_minute: i64) -> Self {
+        self.soft_deadline = Some(deadline);
+        self.lateness_cost_per_minute = cost_per_minute;
+        self
+    }
+
+    /// Makes this visit the pickup for the delivery at `delivery_idx`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use vehicle_routing::domain::{Location, Visit, VisitKind};
+    ///
+    /// let loc = Location::new(0, 39.95, -75.17);
+    /// let pickup = Visit::new(0, "Warehouse", loc.clone()).with_demand(4).with_pickup_for(1);
+    /// let delivery = Visit::new(1, "Customer", loc).with_demand(4).with_delivery_for(0);
+    ///
+    /// assert_eq!(pickup.kind, VisitKind::Pickup);
+    /// assert_eq!(pickup.load_delta(), 4);
+    /// assert_eq!(delivery.load_delta(), -4);
+    /// assert!(!delivery.is_depot_delivery());
+    /// ```
+    pub fn with_pickup_for(mut self, delivery_idx: usize) -> Self {
+        self.kind = VisitKind::Pickup;
+        self.paired_visit = Some(delivery_idx);
+        self
+    }
+
+    /// Makes this visit the delivery for the pickup at `pickup_idx`.
+    pub fn with_delivery_for(mut self, pickup_idx: usize) -> Self {
+        self.kind = VisitKind::Delivery;
+        self.paired_visit = Some(pickup_idx);
+        self
+    }
+
+    /// Sets the demand per named dimension (see `VehicleRoutePlan.capacity_dimensions`).
+    pub fn with_dimension_demands(mut self, demands: Vec<i32>) -> Self {
+        self.dimension_demands = demands;
+        self
+    }
+
+    /// Gets the demand for a named dimension (0 if not set).
+    #[inline]
+    pub fn dimension_demand(&self, dimension: usize) -> i32 {
+        self.dimension_demands.get(dimension).copied().unwrap_or(0)
+    }
+
+    /// Adds a capability the serving vehicle must have.
+    pub fn with_required_capability(mut self, capability: impl Into<String>) -> Self {
+        self.required_capabilities.insert(capability.into());
+        self
+    }
+
+    /// Restricts this visit to the given vehicle IDs.
+    pub fn with_allowed_vehicles(mut self, vehicle_ids: Vec<usize>) -> Self {
+        self.allowed_vehicles = vehicle_ids;
+        self
+    }
+
+    /// Forbids the given vehicle IDs from serving this visit.
+    pub fn with_forbidden_vehicles(mut self, vehicle_ids: Vec<usize>) -> Self {
+        self.forbidden_vehicles = vehicle_ids;
+        self
+    }
+
+    /// Sets the preferred vehicle and the soft penalty for using another one.
+    pub fn with_preferred_vehicle(self, vehicle_id: usize, weight: i64) -> Self {
+        self.with_preferred_vehicles(vec![vehicle_id], weight)
+    }
+
+    /// Sets the preferred vehicles and the soft penalty for using any other.
+    pub fn with_preferred_vehicles(mut self, vehicle_ids: Vec<usize>, weight: i64) -> Self {
+        self.preferred_vehicles = vehicle_ids;
+        self.preferred_vehicle_weight = weight;
+        self
+    }
+
+    /// Makes this visit optional with the given priority.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use vehicle_routing::domain::{Location, Visit};
+    ///
+    /// let visit = Visit::new(0, "Flyer drop", Location::new(0, 39.95, -75.17)).with_optional(3);
+    /// assert!(visit.optional);
+    /// assert_eq!(visit.priority, 3);
+    /// ```
+    pub fn with_optional(mut self, priority: i64) -> Self {
+        self.optional = true;
+        self.priority = priority;
+        self
+    }
+
+    /// Returns the required capabilities the vehicle lacks, sorted.
+    pub fn missing_capabilities(&self, vehicle: &Vehicle) -> Vec<&str> {
+        self.required_capabilities
+            .iter()
+            .filter(|c| !vehicle.capabilities.contains(*c))
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Returns true if the vehicle may serve this visit.
+    ///
+    /// The vehicle must have every required capability, be in the allowed
+    /// list (if any) and not be in the forbidden list.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use vehicle_routing::domain::{Location, Visit, Vehicle};
+    ///
+    /// let loc = Location::new(0, 39.95, -75.17);
+    /// let reefer = Vehicle::new(0, "Reefer", 10, loc.clone()).with_capability("refrigerated");
+    /// let van = Vehicle::new(1, "Van", 10, loc.clone());
+    ///
+    /// let frozen = Visit::new(0, "Ice cream", loc.clone()).with_required_capability("refrigerated");
+    /// assert!(frozen.is_compatible_with(&reefer));
+    /// assert!(!frozen.is_compatible_with(&van));
+    ///
+    /// let not_van = Visit::new(1, "Narrow street", loc).with_forbidden_vehicles(vec![1]);
+    /// assert!(!not_van.is_compatible_with(&van));
+    /// ```
+    pub fn is_compatible_with(&self, vehicle: &Vehicle) -> bool {
+        self.required_capabilities.is_subset(&vehicle.capabilities)
+            && (self.allowed_vehicles.is_empty() || self.allowed_vehicles.contains(&vehicle.id))
+            && !self.forbidden_vehicles.contains(&vehicle.id)
+    }
+
+    /// Soft penalty for serving this visit with the given vehicle.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use vehicle_routing::domain::{Location, Visit, Vehicle};
+    ///
+    /// let loc = Location::new(0, 39.95, -75.17);
+    /// // Alice drives vehicles 0 and 1 (two shifts), Bob drives vehicle 2
+    /// let visit = Visit::new(0, "Regular", loc.clone()).with_preferred_vehicles(vec![0, 1], 25);
+    /// assert_eq!(visit.preference_penalty(&Vehicle::new(1, "Alice day 2", 10, loc.clone())), 0);
+    /// assert_eq!(visit.preference_penalty(&Vehicle::new(2, "Bob", 10, loc)), 25);
+    /// ```
+    #[inline]
+    pub fn preference_penalty(&self, vehicle: &Vehicle) -> i64 {
+        if self.preferred_vehicles.is_empty() || self.preferred_vehicles.contains(&vehicle.id) {
+            0
+        } else {
+            self.preferred_vehicle_weight
+        }
+    }
+
+    /// Creates a reload stop at the vehicle's depot, usable by that vehicle only.
+    ///
+    /// Reload stops are optional with zero priority, so unused ones cost nothing.
+    /// They are open until the vehicle's shift end (unbounded without one), so
+    /// reloads on later days of a multi-day horizon are never late.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use vehicle_routing::domain::{Location, Visit, Vehicle, UNBOUNDED_END_TIME};
+    ///
+    /// let depot = Location::new(0, 0.0, 0.0);
+    /// let day_two = Vehicle::new(0, "Van", 10, depot.clone()).with_shift(32 * 3600, 40 * 3600);
+    /// assert_eq!(Visit::reload(0, &day_two).max_end_time, 40 * 3600);
+    ///
+    /// let no_shift = Vehicle::new(1, "Van", 10, depot);
+    /// assert_eq!(Visit::reload(1, &no_shift).max_end_time, UNBOUNDED_END_TIME);
+    /// ```
+    pub fn reload(index: usize, vehicle: &Vehicle) -> Self {
+        let mut visit = Self::new(index, format!("Reload {}", vehicle.name), vehicle.home_location.clone())
+            .with_demand(0)
+            .with_time_window(0, vehicle.shift_end.unwrap_or(UNBOUNDED_END_TIME))
+            .with_service_duration(vehicle.reload_duration)
+            .with_allowed_vehicles(vec![vehicle.id])
+            .with_optional(0);
+        visit.kind = VisitKind::Reload;
+        visit
+    }
+
+    /// Returns true for depot reload stops.
+    #[inline]
+    pub fn is_reload(&self) -> bool {
+        self.kind == VisitKind::Reload
+    }
+
+    /// Returns true for deliveries whose goods are loaded at the depot.
+    #[inline]
+    pub fn is_depot_delivery(&self) -> bool {
+        self.kind == VisitKind::Delivery && self.paired_visit.is_none()
+    }
+
+    /// Change in on-board load after serving this visit.
+    #[inline]
+    pub fn load_delta(&self) -> i32 {
+        self.signed(self.demand)
+    }
+
+    /// Applies the load direction of this visit to a demand quantity.
+    #[inline]
+    fn signed(&self, demand: i32) -> i32 {
+        match self.kind {
+            VisitKind::Pickup => demand,
+            VisitKind::Delivery => -demand,
+            VisitKind::Reload => 0,
+        }
+    }
 }
 
 /// A delivery vehicle with capacity and assigned route.
@@ -230,12 +564,54 @@ pub struct Vehicle {
     pub name: String,
     /// Maximum capacity (sum of visit demands must not exceed).
     pub capacity: i32,
-    /// Home depot location.
+    /// Home depot location, where the route starts.
     #[serde(rename = "homeLocation")]
     pub home_location: Location,
-    /// Departure time from depot (seconds from midnight).
+    /// Where the route ends, if not back at `home_location`.
+    #[serde(rename = "endLocation", default)]
+    pub end_location: Option<Location>,
+    /// Open route: the vehicle does not return after its last visit
+    /// (e.g. subcontractors). Overrides `end_location`.
+    #[serde(rename = "openRoute", default)]
+    pub open_route: bool,
+    /// Latest time the route may end (seconds from horizon start).
+    #[serde(rename = "shiftEnd", default)]
+    pub shift_end: Option<i64>,
+    /// Departure time from depot (seconds from horizon start).
     #[serde(rename = "departureTime")]
     pub departure_time: i64,
+    /// Fixed cost incurred when the vehicle is used (non-empty route).
+    #[serde(rename = "fixedCost", default)]
+    pub fixed_cost: i64,
+    /// Variable cost per kilometer driven.
+    #[serde(rename = "costPerKm", default)]
+    pub cost_per_km: i64,
+    /// Variable cost per hour on the road (departure until return to depot).
+    #[serde(rename = "costPerHour", default)]
+    pub cost_per_hour: i64,
+    /// Capacity per named dimension, indexed like `VehicleRoutePlan.capacity_dimensions`.
+    ///
+    /// Dimensions the vehicle does not declare are unconstrained.
+    #[serde(rename = "dimensionCapacities", default)]
+    pub dimension_capacities: Vec<i32>,
+    /// Capabilities of the vehicle (e.g. "refrigerated", "tail-lift").
+    #[serde(default)]
+    pub capabilities: BTreeSet<String>,
+    /// Fuel or energy use per 100 km (e.g. liters of diesel, kWh).
+    ///
+    /// Wrapped in [`OrderedFloat`] so the vehicle stays `Eq` and `Hash`.
+    #[serde(rename = "fuelPer100Km", default)]
+    pub fuel_per_100km: OrderedFloat<f64>,
+    /// CO2 emitted per unit of fuel or energy, in kg (e.g. 2.68 per liter
+    /// of diesel, grid intensity per kWh).
+    #[serde(rename = "co2KgPerFuelUnit", default)]
+    pub co2_kg_per_fuel_unit: OrderedFloat<f64>,
+    /// How many times the vehicle may return to its depot to reload.
+    #[serde(rename = "maxReloads", default)]
+    pub max_reloads: usize,
+    /// Time spent at the depot per reload, in seconds.
+    #[serde(rename = "reloadDuration", default)]
+    pub reload_duration: i64,
     /// Ordered list of visit indices (the route).
     #[serde(default)]
     pub visits: Vec<usize>,
@@ -249,29 +625,173 @@ impl Vehicle {
             name: name.into(),
             capacity,
             home_location,
+            end_location: None,
+            open_route: false,
+            shift_end: None,
             departure_time: 8 * 3600, // Default 8am
+            fixed_cost: 0,
+            cost_per_km: 0,
+            cost_per_hour: 0,
+            dimension_capacities: Vec::new(),
+            capabilities: BTreeSet::new(),
+            fuel_per_100km: OrderedFloat(0.0),
+            co2_kg_per_fuel_unit: OrderedFloat(0.0),
+            max_reloads: 0,
+            reload_duration: 0,
             visits: Vec::new(),
         }
     }
 
-    /// Sets the departure time in seconds from midnight.
+    /// Sets the departure time in seconds from horizon start.
     pub fn with_departure_time(mut self, time: i64) -> Self {
         self.departure_time = time;
         self
     }
+
+    /// Sets the fuel (or energy) use per 100 km and CO2 kg per fuel unit.
+    pub fn with_emission_factors(mut self, fuel_per_100km: f64, co2_kg_per_fuel_unit: f64) -> Self {
+        self.fuel_per_100km = OrderedFloat(fuel_per_100km);
+        self.co2_kg_per_fuel_unit = OrderedFloat(co2_kg_per_fuel_unit);
+        self
+    }
+
+    /// Sets the shift (departure and latest route end) in seconds from horizon start.
+    pub fn with_shift(mut self, start: i64, end: i64) -> Self {
+        self.departure_time = start;
+        self.shift_end = Some(end);
+        self
+    }
+
+    /// Allows up to `max_reloads` depot reloads of `duration` seconds each.
+    ///
+    /// Call [`VehicleRoutePlan::add_reload_stops`] afterwards to create the
+    /// reload stops the solver can insert into the route.
+    pub fn with_reloads(mut self, max_reloads: usize, duration: i64) -> Self {
+        self.max_reloads = max_reloads;
+        self.reload_duration = duration;
+        self
+    }
+
+    /// Ends the route at `location` instead of returning home.
+    pub fn with_end_location(mut self, location: Location) -> Self {
+        self.end_location = Some(location);
+        self
+    }
+
+    /// Makes this an open route that ends at the last visit.
+    pub fn with_open_route(mut self) -> Self {
+        self.open_route = true;
+        self
+    }
+
+    /// Location index where the route ends (`None` for open routes).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use vehicle_routing::domain::{Vehicle, Location};
+    ///
+    /// let home = Location::new(0, 39.95, -75.17);
+    /// let hub = Location::new(1, 39.98, -75.12);
+    ///
+    /// let round_trip = Vehicle::new(0, "A", 10, home.clone());
+    /// assert_eq!(round_trip.end_location_index(), Some(0));
+    ///
+    /// let to_hub = Vehicle::new(1, "B", 10, home.clone()).with_end_location(hub);
+    /// assert_eq!(to_hub.end_location_index(), Some(1));
+    ///
+    /// let open = Vehicle::new(2, "C", 10, home).with_open_route();
+    /// assert_eq!(open.end_location_index(), None);
+    /// ```
+    #[inline]
+    pub fn end_location_index(&self) -> Option<usize> {
+        if self.open_route {
+            None
+        } else {
+            Some(self.end_location.as_ref().unwrap_or(&self.home_location).index)
+        }
+    }
+
+    /// Sets the operating costs (in the smallest currency unit, e.g. cents).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use vehicle_routing::domain::{Vehicle, Location};
+    ///
+    /// let depot = Location::new(0, 39.95, -75.17);
+    /// let van = Vehicle::new(0, "Van 1", 50, depot).with_costs(5_000, 40, 2_500);
+    ///
+    /// assert_eq!(van.fixed_cost, 5_000);
+    /// assert_eq!(van.cost_per_km, 40);
+    /// assert_eq!(van.cost_per_hour, 2_500);
+    /// ```
+    pub fn with_costs(mut self, fixed_cost: i64, cost_per_km: i64, cost_per_hour: i64) -> Self {
+        self.fixed_cost = fixed_cost;
+        self.cost_per_km = cost_per_km;
+        self.cost_per_hour = cost_per_hour;
+        self
+    }
+
+    /// Sets the capacity per named dimension (see `VehicleRoutePlan.capacity_dimensions`).
+    pub fn with_dimension_capacities(mut self, capacities: Vec<i32>) -> Self {
+        self.dimension_capacities = capacities;
+        self
+    }
+
+    /// Adds a capability.
+    pub fn with_capability(mut self, capability: impl Into<String>) -> Self {
+        self.capabilities.insert(capability.into());
+        self
+    }
+
+    /// Gets the capacity for a named dimension (unconstrained if not set).
+    #[inline]
+    pub fn dimension_capacity(&self, dimension: usize) -> i32 {
+        self.dimension_capacities.get(dimension).copied().unwrap_or(i32::MAX)
+    }
+}
+
+/// Soft objective minimized by the solver.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RoutingObjective {
+    /// Minimize total driving time (in minutes).
+    #[default]
+    TravelTime,
+    /// Minimize total vehicle cost (fixed plus distance and time costs).
+    Cost,
+    /// Minimize total distance driven (in meters).
+    Distance,
 }
 
-/// Arrival and departure times for a visit in a route.
+/// Arrival, service start and departure times for a visit in a route.
 #[derive(Debug, Clone, Copy)]
 pub struct VisitTiming {
     /// Visit index.
     pub visit_idx: usize,
-    /// Arrival time at the visit (seconds from midnight).
+    /// Arrival time at the visit (seconds from horizon start).
     pub arrival: i64,
-    /// Departure time from the visit (seconds from midnight).
+    /// Service start, after waiting for the time window to open
+    /// (seconds from horizon start).
+    pub service_start: i64,
+    /// Departure time from the visit (seconds from horizon start).
     pub departure: i64,
 }
 
+impl VisitTiming {
+    /// Seconds spent waiting for the time window to open.
+    pub fn waiting(&self) -> i64 {
+        self.service_start - self.arrival
+    }
+
+    /// Whole minutes spent waiting, the unit waiting time is charged in.
+    #[inline]
+    pub fn waiting_minutes(&self) -> i64 {
+        self.waiting() / 60
+    }
+}
+
 /// The complete vehicle routing solution.
 ///
 /// Contains all problem facts (locations, visits) and planning entities (vehicles).
@@ -316,6 +836,10 @@ pub struct VehicleRoutePlan {
     /// All customer visits.
     #[problem_fact_collection]
     pub visits: Vec<Visit>,
+    /// Names of the capacity dimensions beyond the primary quantity
+    /// (e.g. `["weight", "volume", "pallets"]`).
+    #[serde(rename = "capacityDimensions", default)]
+    pub capacity_dimensions: Vec<String>,
     /// All vehicles.
     #[planning_entity_collection]
     pub vehicles: Vec<Vehicle>,
@@ -325,9 +849,33 @@ pub struct VehicleRoutePlan {
     /// Solver status for REST API.
     #[serde(rename = "solverStatus", skip_serializing_if = "Option::is_none")]
     pub solver_status: Option<String>,
+    /// Soft objective to minimize.
+    #[serde(default)]
+    pub objective: RoutingObjective,
+    /// Penalize every used vehicle to reduce fleet size.
+    #[serde(rename = "minimizeVehiclesUsed", default)]
+    pub minimize_vehicles_used: bool,
+    /// Soft penalty per minute a vehicle waits for a time window to open.
+    #[serde(rename = "waitingCostPerMinute", default)]
+    pub waiting_cost_per_minute: i64,
+    /// Absolute start of the planning horizon; all times are offsets from it.
+    #[serde(rename = "horizonStart", default = "default_horizon_start")]
+    pub horizon_start: NaiveDateTime,
+    /// Time-of-day speed profile. Without one, travel times don't depend
+    /// on the departure time.
+    #[serde(rename = "speedProfile", default, skip_serializing_if = "Option::is_none")]
+    pub speed_profile: Option<SpeedProfile>,
     /// Precomputed travel times: `travel_time_matrix[from][to]` in seconds.
     #[serde(skip)]
     pub travel_time_matrix: Vec<Vec<i64>>,
+    /// Travel times per bucket of the speed profile (`None` without one).
+    #[serde(skip)]
+    pub time_dependent_times: Option<TimeDependentTimes>,
+    /// Precomputed distances: `distance_matrix[from][to]` in meters.
+    ///
+    /// Empty means straight-line distances are used.
+    #[serde(skip)]
+    pub distance_matrix: Vec<Vec<i64>>,
     /// Route geometries: `(from_loc, to_loc)` -> list of (lat, lng) waypoints.
     #[serde(skip)]
     pub route_geometries: HashMap<(usize, usize), Vec<(f64, f64)>>,
@@ -350,10 +898,18 @@ impl VehicleRoutePlan {
             north_east_corner: ne,
             locations,
             visits,
+            capacity_dimensions: Vec::new(),
             vehicles,
             score: None,
             solver_status: None,
+            objective: RoutingObjective::default(),
+            minimize_vehicles_used: false,
+            waiting_cost_per_minute: 0,
+            horizon_start: default_horizon_start(),
+            speed_profile: None,
             travel_time_matrix: Vec::new(),
+            time_dependent_times: None,
+            distance_matrix: Vec::new(),
             route_geometries: HashMap::new(),
         }
     }
@@ -380,58 +936,77 @@ impl VehicleRoutePlan {
         ([min_lat, min_lon], [max_lat, max_lon])
     }
 
-    /// Populates travel time matrix using haversine distances.
+    /// Populates travel time and distance matrices using haversine distances.
     ///
     /// Must be called after construction and before solving.
-    /// For real road routing, use `init_routing()` instead.
+    /// For real road routing, use `init_routing()` or `init_with()` instead.
     pub fn finalize(&mut self) {
-        let n = self.locations.len();
-        self.travel_time_matrix = vec![vec![0; n]; n];
-
-        for i in 0..n {
-            for j in 0..n {
-                if i != j {
-                    self.travel_time_matrix[i][j] =
-                        self.locations[i].travel_time_seconds(&self.locations[j]);
-                }
-            }
-        }
+        let matrices = crate::matrix::haversine_matrices(&self.locations);
+        self.travel_time_matrix = matrices.times;
+        self.distance_matrix = matrices.distances;
+        self.scale_by_profile();
+    }
+
+    /// Derives time-dependent travel times from the static matrix by
+    /// scaling it with the speed profile (if any).
+    pub fn scale_by_profile(&mut self) {
+        self.time_dependent_times = self
+            .speed_profile
+            .as_ref()
+            .map(|profile| TimeDependentTimes::scaled(&self.travel_time_matrix, profile));
     }
 
     /// Initializes with real road routing from OSM data.
     ///
-    /// Downloads road network via Overpass API (cached), builds graph,
+    /// Downloads road network via Overpass API or reads the configured
+    /// local OSM extract (cached), builds graph,
     /// and computes travel times using Dijkstra shortest paths.
     /// Also stores route geometries for visualization.
     pub async fn init_routing(&mut self) -> Result<(), crate::routing::RoutingError> {
-        use crate::routing::{BoundingBox, RoadNetwork};
-
-        // Build bounding box from plan bounds (with expansion)
-        let bbox = BoundingBox::new(
-            self.south_west_corner[0],
-            self.south_west_corner[1],
-            self.north_east_corner[0],
-            self.north_east_corner[1],
-        )
-        .expand(0.05); // 5% expansion to catch nearby roads
-
-        // Load or fetch road network
-        let network = RoadNetwork::load_or_fetch(&bbox).await?;
-
-        // Extract coordinates
-        let coords: Vec<(f64, f64)> = self
-            .locations
-            .iter()
-            .map(|l| (l.latitude, l.longitude))
-            .collect();
+        self.init_with(&crate::matrix::OsmProvider).await
+    }
 
-        // Compute travel time matrix
-        self.travel_time_matrix = network.compute_matrix(&coords);
+    /// Populates travel matrices (and geometries, if any) from a provider,
+    /// with time-dependent travel times if the plan has a speed profile.
+    pub async fn init_with(
+        &mut self,
+        provider: &dyn crate::matrix::MatrixProvider,
+    ) -> Result<(), crate::routing::RoutingError> {
+        let bbox = self.bounding_box();
+        let start = std::time::Instant::now();
+        let data = match &self.speed_profile {
+            Some(profile) => provider.fetch_with_profile(&self.locations, bbox, profile).await?,
+            None => provider.fetch(&self.locations, bbox).await?,
+        };
+        crate::metrics::global().record_matrix(provider.name(), start.elapsed());
+        self.travel_time_matrix = data.matrices.times;
+        self.distance_matrix = data.matrices.distances;
+        self.route_geometries = data.geometries;
+        self.time_dependent_times = data.time_dependent;
+        Ok(())
+    }
 
-        // Compute route geometries for visualization
-        self.route_geometries = network.compute_all_geometries(&coords);
+    /// Converts a time offset to an absolute timestamp.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use vehicle_routing::domain::VehicleRoutePlan;
+    ///
+    /// let plan = VehicleRoutePlan::new("test", vec![], vec![], vec![]);
+    /// let late = plan.datetime_at(26 * 3600); // 2am the next day
+    /// assert_eq!(late.to_string(), "2025-01-06 02:00:00");
+    /// assert_eq!(plan.offset_of(late), 26 * 3600);
+    /// ```
+    #[inline]
+    pub fn datetime_at(&self, offset: i64) -> NaiveDateTime {
+        self.horizon_start + chrono::Duration::seconds(offset)
+    }
 
-        Ok(())
+    /// Converts an absolute timestamp to seconds from horizon start.
+    #[inline]
+    pub fn offset_of(&self, datetime: NaiveDateTime) -> i64 {
+        (datetime - self.horizon_start).num_seconds()
     }
 
     /// Returns the bounding box for this plan.
@@ -456,6 +1031,59 @@ impl VehicleRoutePlan {
             .unwrap_or(0)
     }
 
+    /// Gets travel time between two locations when departing at `departure`
+    /// (seconds from horizon start).
+    ///
+    /// Uses the speed profile bucket covering the departure's time of day,
+    /// or the static matrix if the plan has no time-dependent times.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use vehicle_routing::domain::{Location, VehicleRoutePlan};
+    /// use vehicle_routing::traffic::{SpeedBucket, SpeedProfile};
+    ///
+    /// let locations = vec![Location::new(0, 39.95, -75.16), Location::new(1, 39.96, -75.17)];
+    /// let mut plan = VehicleRoutePlan::new("test", locations, vec![], vec![]);
+    /// plan.speed_profile = Some(SpeedProfile::new(vec![
+    ///     SpeedBucket::uniform(0, 1.0),
+    ///     SpeedBucket::uniform(7, 0.5),
+    ///     SpeedBucket::uniform(9, 1.0),
+    /// ]));
+    /// plan.finalize();
+    ///
+    /// let free_flow = plan.travel_time(0, 1);
+    /// assert_eq!(plan.travel_time_at(0, 1, 22 * 3600), free_flow);
+    /// assert_eq!(plan.travel_time_at(0, 1, 7 * 3600 + 1800), 2 * free_flow);
+    /// // Offsets past midnight use the next day's time of day
+    /// assert_eq!(plan.travel_time_at(0, 1, 24 * 3600 + 8 * 3600), 2 * free_flow);
+    /// ```
+    #[inline]
+    pub fn travel_time_at(&self, from_idx: usize, to_idx: usize, departure: i64) -> i64 {
+        let Some(times) = &self.time_dependent_times else {
+            return self.travel_time(from_idx, to_idx);
+        };
+        let second_of_day = self.horizon_start.time().num_seconds_from_midnight() as i64 + departure;
+        times
+            .travel_time(from_idx, to_idx, second_of_day)
+            .unwrap_or_else(|| self.travel_time(from_idx, to_idx))
+    }
+
+    /// Gets the distance between two locations in meters.
+    ///
+    /// Uses the road distance matrix when available, else the straight-line
+    /// distance. Returns 0 if either index is out of bounds.
+    #[inline]
+    pub fn distance_meters(&self, from_idx: usize, to_idx: usize) -> f64 {
+        if let Some(&meters) = self.distance_matrix.get(from_idx).and_then(|row| row.get(to_idx)) {
+            return meters as f64;
+        }
+        match (self.locations.get(from_idx), self.locations.get(to_idx)) {
+            (Some(from), Some(to)) => from.distance_meters(to),
+            _ => 0.0,
+        }
+    }
+
     /// Gets route geometry between two locations.
     ///
     /// Returns the waypoints if real road routing was initialized,
@@ -518,7 +1146,7 @@ impl VehicleRoutePlan {
             };
 
             // Travel to this visit
-            let travel = self.travel_time(current_loc, visit.location.index);
+            let travel = self.travel_time_at(current_loc, visit.location.index, current_time);
             let arrival = current_time + travel;
 
             // Service starts at max(arrival, min_start_time)
@@ -528,6 +1156,7 @@ impl VehicleRoutePlan {
             timings.push(VisitTiming {
                 visit_idx,
                 arrival,
+                service_start,
                 departure,
             });
 
@@ -540,24 +1169,30 @@ impl VehicleRoutePlan {
 
     /// Calculates total driving time for a vehicle's route in seconds.
     ///
-    /// Includes travel from depot, between visits, and back to depot.
+    /// Includes travel from depot, between visits, and on to the end
+    /// location (none for open routes).
     pub fn total_driving_time(&self, vehicle: &Vehicle) -> i64 {
         if vehicle.visits.is_empty() {
             return 0;
         }
 
         let mut total = 0i64;
+        let mut current_time = vehicle.departure_time;
         let mut current_loc = vehicle.home_location.index;
 
         for &visit_idx in &vehicle.visits {
             if let Some(visit) = self.visits.get(visit_idx) {
-                total += self.travel_time(current_loc, visit.location.index);
+                let travel = self.travel_time_at(current_loc, visit.location.index, current_time);
+                total += travel;
+                current_time = (current_time + travel).max(visit.min_start_time) + visit.service_duration;
                 current_loc = visit.location.index;
             }
         }
 
-        // Return to depot
-        total += self.travel_time(current_loc, vehicle.home_location.index);
+        // Drive to the end location
+        if let Some(end) = vehicle.end_location_index() {
+            total += self.travel_time_at(current_loc, end, current_time);
+        }
         total
     }
 
@@ -565,4 +1200,405 @@ impl VehicleRoutePlan {
     pub fn total_driving_time_all(&self) -> i64 {
         self.vehicles.iter().map(|v| self.total_driving_time(v)).sum()
     }
+
+    /// Calculates the load on board when the vehicle leaves the depot.
+    ///
+    /// This is the total demand of depot deliveries in the first trip;
+    /// pickups and paired deliveries start empty.
+    pub fn start_load(&self, vehicle: &Vehicle) -> i32 {
+        self.start_load_by(vehicle, |v| v.demand)
+    }
+
+    /// Calculates the on-board load after each visit in route order.
+    ///
+    /// At a reload stop, goods collected for the depot are unloaded and the
+    /// depot deliveries of the next trip are loaded; paired cargo stays on
+    /// board.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use vehicle_routing::domain::{Location, Visit, Vehicle, VehicleRoutePlan};
+    ///
+    /// let depot = Location::new(0, 0.0, 0.0);
+    /// let visits = vec![
+    ///     Visit::new(0, "Drop", depot.clone()).with_demand(3),
+    ///     Visit::new(1, "Pickup", depot.clone()).with_demand(5).with_pickup_for(2),
+    ///     Visit::new(2, "Delivery", depot.clone()).with_demand(5).with_delivery_for(1),
+    /// ];
+    /// let mut vehicle = Vehicle::new(0, "V1", 10, depot.clone());
+    /// vehicle.visits = vec![0, 1, 2];
+    ///
+    /// let plan = VehicleRoutePlan::new("test", vec![depot], visits, vec![vehicle]);
+    /// let v = &plan.vehicles[0];
+    ///
+    /// assert_eq!(plan.start_load(v), 3);
+    /// assert_eq!(plan.load_profile(v), vec![0, 5, 0]);
+    /// assert_eq!(plan.peak_load(v), 5);
+    /// ```
+    pub fn load_profile(&self, vehicle: &Vehicle) -> Vec<i32> {
+        self.load_profile_by(vehicle, |v| v.demand)
+    }
+
+    /// Calculates the highest on-board load at any point of the route.
+    pub fn peak_load(&self, vehicle: &Vehicle) -> i32 {
+        self.peak_load_by(vehicle, |v| v.demand)
+    }
+
+    /// Calculates the highest on-board load of a named dimension.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use vehicle_routing::domain::{Location, Visit, Vehicle, VehicleRoutePlan};
+    ///
+    /// let depot = Location::new(0, 0.0, 0.0);
+    /// let visits = vec![
+    ///     Visit::new(0, "A", depot.clone()).with_dimension_demands(vec![400, 2]),
+    ///     Visit::new(1, "B", depot.clone()).with_dimension_demands(vec![700, 1]),
+    /// ];
+    /// let mut vehicle = Vehicle::new(0, "V1", 10, depot.clone())
+    ///     .with_dimension_capacities(vec![1000, 4]);
+    /// vehicle.visits = vec![0, 1];
+    ///
+    /// let mut plan = VehicleRoutePlan::new("test", vec![depot], visits, vec![vehicle]);
+    /// plan.capacity_dimensions = vec!["weight".into(), "pallets".into()];
+    ///
+    /// assert_eq!(plan.peak_dimension_load(&plan.vehicles[0], 0), 1100); // weight
+    /// assert_eq!(plan.peak_dimension_load(&plan.vehicles[0], 1), 3); // pallets
+    /// ```
+    pub fn peak_dimension_load(&self, vehicle: &Vehicle, dimension: usize) -> i32 {
+        self.peak_load_by(vehicle, |v| v.dimension_demand(dimension))
+    }
+
+    /// Start load for an arbitrary demand quantity.
+    fn start_load_by(&self, vehicle: &Vehicle, demand: impl Fn(&Visit) -> i32) -> i32 {
+        Self::trip_load_by(&self.route_visits(vehicle), demand)
+    }
+
+    /// Load profile for an arbitrary demand quantity.
+    fn load_profile_by(&self, vehicle: &Vehicle, demand: impl Fn(&Visit) -> i32) -> Vec<i32> {
+        let route = self.route_visits(vehicle);
+        let mut load = Self::trip_load_by(&route, &demand);
+        let mut paired_on_board = 0;
+        route
+            .iter()
+            .enumerate()
+            .map(|(pos, visit)| {
+                if visit.is_reload() {
+                    load = paired_on_board + Self::trip_load_by(&route[pos + 1..], &demand);
+                } else {
+                    let delta = visit.signed(demand(visit));
+                    load += delta;
+                    if visit.paired_visit.is_some() {
+                        paired_on_board += delta;
+                    }
+                }
+                load
+            })
+            .collect()
+    }
+
+    /// Depot deliveries loaded for the trip starting at `route[0]`.
+    fn trip_load_by(route: &[&Visit], demand: impl Fn(&Visit) -> i32) -> i32 {
+        route
+            .iter()
+            .take_while(|v| !v.is_reload())
+            .filter(|v| v.is_depot_delivery())
+            .map(|v| demand(v))
+            .sum()
+    }
+
+    /// The visits on a vehicle's route, in order.
+    fn route_visits(&self, vehicle: &Vehicle) -> Vec<&Visit> {
+        vehicle
+            .visits
+            .iter()
+            .filter_map(|&idx| self.visits.get(idx))
+            .collect()
+    }
+
+    /// Number of reload stops on a vehicle's route.
+    pub fn reload_count(&self, vehicle: &Vehicle) -> usize {
+        self.route_visits(vehicle).iter().filter(|v| v.is_reload()).count()
+    }
+
+    /// Appends reload stops so each vehicle has `max_reloads` of them.
+    ///
+    /// Existing reload stops (e.g. from a previous solve) are kept, so this
+    /// is safe to call repeatedly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use vehicle_routing::domain::{Location, Visit, Vehicle, VehicleRoutePlan};
+    ///
+    /// let depot = Location::new(0, 0.0, 0.0);
+    /// let visits = vec![
+    ///     Visit::new(0, "A", depot.clone()).with_demand(20),
+    ///     Visit::new(1, "B", depot.clone()).with_demand(20),
+    /// ];
+    /// let vehicle = Vehicle::new(0, "Van", 30, depot.clone()).with_reloads(1, 900);
+    ///
+    /// let mut plan = VehicleRoutePlan::new("test", vec![depot], visits, vec![vehicle]);
+    /// plan.add_reload_stops();
+    /// plan.add_reload_stops();
+    /// assert_eq!(plan.visits.len(), 3);
+    ///
+    /// // Capacity is checked per trip: 20, reload, 20
+    /// plan.vehicles[0].visits = vec![0, 2, 1];
+    /// assert_eq!(plan.peak_load(&plan.vehicles[0]), 20);
+    /// assert_eq!(plan.load_profile(&plan.vehicles[0]), vec![0, 20, 0]);
+    /// ```
+    pub fn add_reload_stops(&mut self) {
+        for v_pos in 0..self.vehicles.len() {
+            let vehicle = &self.vehicles[v_pos];
+            let existing = self
+                .visits
+                .iter()
+                .filter(|v| v.is_reload() && v.allowed_vehicles == [vehicle.id])
+                .count();
+            for _ in existing..vehicle.max_reloads {
+                let reload = Visit::reload(self.visits.len(), &self.vehicles[v_pos]);
+                self.visits.push(reload);
+            }
+        }
+    }
+
+    /// Peak load for an arbitrary demand quantity.
+    fn peak_load_by(&self, vehicle: &Vehicle, demand: impl Fn(&Visit) -> i32) -> i32 {
+        let start = self.start_load_by(vehicle, &demand);
+        self.load_profile_by(vehicle, demand)
+            .into_iter()
+            .fold(start, i32::max)
+    }
+
+    /// Maps each visit index to its `(vehicle position, route position)`.
+    ///
+    /// Unassigned visits map to `None`.
+    pub fn visit_positions(&self) -> Vec<Option<(usize, usize)>> {
+        let mut positions = vec![None; self.visits.len()];
+        for (v_pos, vehicle) in self.vehicles.iter().enumerate() {
+            for (pos, &visit_idx) in vehicle.visits.iter().enumerate() {
+                if let Some(slot) = positions.get_mut(visit_idx) {
+                    *slot = Some((v_pos, pos));
+                }
+            }
+        }
+        positions
+    }
+
+    /// Returns the indices of visits not on any vehicle's route.
+    pub fn unassigned_visits(&self) -> Vec<usize> {
+        self.visit_positions()
+            .iter()
+            .enumerate()
+            .filter(|(_, pos)| pos.is_none())
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    /// Returns the visit together with its pair partner (if any), pickup first.
+    ///
+    /// Used to insert or remove both halves of a pickup-and-delivery pair
+    /// at once.
+    pub fn visit_group(&self, visit_idx: usize) -> Vec<usize> {
+        let Some(visit) = self.visits.get(visit_idx) else {
+            return Vec::new();
+        };
+        match visit.paired_visit {
+            Some(pair) if pair < self.visits.len() && pair != visit_idx => {
+                if visit.kind == VisitKind::Pickup {
+                    vec![visit_idx, pair]
+                } else {
+                    vec![pair, visit_idx]
+                }
+            }
+            _ => vec![visit_idx],
+        }
+    }
+
+    /// Returns `(pickup, delivery)` index pairs declared in the plan.
+    pub fn pickup_delivery_pairs(&self) -> Vec<(usize, usize)> {
+        self.visits
+            .iter()
+            .filter(|v| v.kind == VisitKind::Pickup)
+            .filter_map(|v| v.paired_visit.map(|d| (v.index, d)))
+            .collect()
+    }
+
+    /// Calculates the time the vehicle arrives at its end location.
+    ///
+    /// Open routes end when service at the last visit finishes.
+    /// Returns the departure time for an empty route.
+    pub fn route_end_time(&self, vehicle: &Vehicle) -> i64 {
+        let timings = self.calculate_route_times(vehicle);
+        match timings.last() {
+            Some(last) => {
+                let last_loc = self.visits[last.visit_idx].location.index;
+                let leg = vehicle
+                    .end_location_index()
+                    .map_or(0, |end| self.travel_time_at(last_loc, end, last.departure));
+                last.departure + leg
+            }
+            None => vehicle.departure_time,
+        }
+    }
+
+    /// Location indices a vehicle passes through: start, visits, end.
+    ///
+    /// The end is omitted for open routes. An empty route yields only the start.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use vehicle_routing::domain::{Location, Visit, Vehicle, VehicleRoutePlan};
+    ///
+    /// let home = Location::new(0, 39.95, -75.17);
+    /// let hub = Location::new(1, 39.98, -75.12);
+    /// let customer = Location::new(2, 39.96, -75.15);
+    ///
+    /// let visits = vec![Visit::new(0, "A", customer.clone())];
+    /// let mut to_hub = Vehicle::new(0, "To hub", 10, home.clone()).with_end_location(hub.clone());
+    /// to_hub.visits = vec![0];
+    /// let mut open = Vehicle::new(1, "Open", 10, home.clone()).with_open_route();
+    /// open.visits = vec![0];
+    ///
+    /// let mut plan = VehicleRoutePlan::new("test", vec![home, hub, customer], visits, vec![to_hub, open]);
+    /// plan.finalize();
+    ///
+    /// assert_eq!(plan.route_location_indices(&plan.vehicles[0]), vec![0, 2, 1]);
+    /// assert_eq!(plan.route_location_indices(&plan.vehicles[1]), vec![0, 2]);
+    /// assert_eq!(plan.total_driving_time(&plan.vehicles[1]), plan.travel_time(0, 2));
+    /// ```
+    pub fn route_location_indices(&self, vehicle: &Vehicle) -> Vec<usize> {
+        let mut route = vec![vehicle.home_location.index];
+        route.extend(
+            vehicle
+                .visits
+                .iter()
+                .filter_map(|&idx| self.visits.get(idx))
+                .map(|visit| visit.location.index),
+        );
+        if !vehicle.visits.is_empty() {
+            route.extend(vehicle.end_location_index());
+        }
+        route
+    }
+
+    /// Calculates total distance for a vehicle's route in meters.
+    ///
+    /// Includes travel from depot, between visits, and on to the end
+    /// location (none for open routes).
+    pub fn total_distance_meters(&self, vehicle: &Vehicle) -> f64 {
+        if vehicle.visits.is_empty() {
+            return 0.0;
+        }
+
+        let mut total = 0.0;
+        let mut current_loc = vehicle.home_location.index;
+
+        for &visit_idx in &vehicle.visits {
+            if let Some(visit) = self.visits.get(visit_idx) {
+                total += self.distance_meters(current_loc, visit.location.index);
+                current_loc = visit.location.index;
+            }
+        }
+
+        total
+            + vehicle
+                .end_location_index()
+                .map_or(0.0, |end| self.distance_meters(current_loc, end))
+    }
+
+    /// Calculates the cost of operating a vehicle on its route.
+    ///
+    /// An empty route costs nothing. Otherwise the fixed cost is charged,
+    /// plus `cost_per_km` for the distance driven and `cost_per_hour` for
+    /// the time between departure and return to depot.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use vehicle_routing::domain::{Location, Visit, Vehicle, VehicleRoutePlan};
+    ///
+    /// let depot = Location::new(0, 0.0, 0.0);
+    /// let locations = vec![depot.clone()];
+    /// let visits = vec![Visit::new(0, "A", depot.clone()).with_service_duration(3600)];
+    /// let mut used = Vehicle::new(0, "Used", 10, depot.clone()).with_costs(500, 10, 100);
+    /// used.visits = vec![0];
+    /// let idle = Vehicle::new(1, "Idle", 10, depot).with_costs(500, 10, 100);
+    ///
+    /// let mut plan = VehicleRoutePlan::new("test", locations, visits, vec![used, idle]);
+    /// plan.finalize();
+    ///
+    /// // Fixed 500 + 0 km + 1 hour of service at 100/hour
+    /// assert_eq!(plan.vehicle_cost(&plan.vehicles[0]), 600);
+    /// assert_eq!(plan.vehicle_cost(&plan.vehicles[1]), 0);
+    /// assert_eq!(plan.total_cost(), 600);
+    /// ```
+    pub fn vehicle_cost(&self, vehicle: &Vehicle) -> i64 {
+        if !self.is_vehicle_used(vehicle) {
+            return 0;
+        }
+
+        let km = self.total_distance_meters(vehicle) / 1000.0;
+        let hours = (self.route_end_time(vehicle) - vehicle.departure_time) as f64 / 3600.0;
+
+        vehicle.fixed_cost
+            + (vehicle.cost_per_km as f64 * km).round() as i64
+            + (vehicle.cost_per_hour as f64 * hours).round() as i64
+    }
+
+    /// Calculates total cost across all vehicles.
+    pub fn total_cost(&self) -> i64 {
+        self.vehicles.iter().map(|v| self.vehicle_cost(v)).sum()
+    }
+
+    /// Estimates the CO2 emitted on a vehicle's route in kg.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use vehicle_routing::domain::{Location, Visit, Vehicle, VehicleRoutePlan};
+    ///
+    /// let depot = Location::new(0, 0.0, 0.0);
+    /// let customer = Location::new(1, 0.0, 0.45); // ~50 km east
+    /// let visits = vec![Visit::new(0, "A", customer.clone())];
+    /// // Diesel van: 10 l/100km, 2.68 kg CO2 per liter
+    /// let mut van = Vehicle::new(0, "Van", 10, depot.clone()).with_emission_factors(10.0, 2.68);
+    /// van.visits = vec![0];
+    ///
+    /// let mut plan = VehicleRoutePlan::new("test", vec![depot, customer], visits, vec![van]);
+    /// plan.finalize();
+    ///
+    /// // ~100 km round trip -> ~10 l -> ~26.8 kg
+    /// let kg = plan.vehicle_emissions_kg(&plan.vehicles[0]);
+    /// assert!(kg > 26.0 && kg < 27.5);
+    /// ```
+    pub fn vehicle_emissions_kg(&self, vehicle: &Vehicle) -> f64 {
+        let km = self.total_distance_meters(vehicle) / 1000.0;
+        km * vehicle.fuel_per_100km.0 / 100.0 * vehicle.co2_kg_per_fuel_unit.0
+    }
+
+    /// Estimates total CO2 emissions across all vehicles in kg.
+    pub fn total_emissions_kg(&self) -> f64 {
+        self.vehicles.iter().map(|v| self.vehicle_emissions_kg(v)).sum()
+    }
+
+    /// Calculates total distance across all vehicles in meters.
+    pub fn total_distance_meters_all(&self) -> f64 {
+        self.vehicles.iter().map(|v| self.total_distance_meters(v)).sum()
+    }
+
+    /// Returns true if the vehicle serves at least one visit other than a
+    /// reload stop.
+    pub fn is_vehicle_used(&self, vehicle: &Vehicle) -> bool {
+        self.route_visits(vehicle).iter().any(|v| !v.is_reload())
+    }
+
+    /// Returns the number of vehicles serving at least one non-reload visit.
+    pub fn vehicles_used(&self) -> usize {
+        self.vehicles.iter().filter(|v| self.is_vehicle_used(v)).count()
+    }
 }
@@ -0,0 +1,415 @@
+//! Export of solved routes to GeoJSON, GPX and KML.
+//!
+//! Every format is built from the same stored road geometries as the
+//! encoded polylines in [`geometry`](crate::geometry) (straight lines where
+//! no road geometry is known), plus the visits with their planned times.
+
+use crate::domain::{Vehicle, VehicleRoutePlan};
+use crate::geometry::get_route_coords;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fmt::Write;
+use utoipa::ToSchema;
+
+/// Export file format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// GeoJSON FeatureCollection: route LineStrings and stop Points.
+    GeoJson,
+    /// GPX 1.1: one track per vehicle, stops as waypoints.
+    Gpx,
+    /// KML 2.2: one folder per vehicle.
+    Kml,
+}
+
+impl ExportFormat {
+    /// MIME type of the exported document.
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ExportFormat::GeoJson => "application/geo+json",
+            ExportFormat::Gpx => "application/gpx+xml",
+            ExportFormat::Kml => "application/vnd.google-earth.kml+xml",
+        }
+    }
+
+    /// File extension, without the dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::GeoJson => "geojson",
+            ExportFormat::Gpx => "gpx",
+            ExportFormat::Kml => "kml",
+        }
+    }
+}
+
+/// Exports the routes of all vehicles, or only of `vehicle_id`.
+///
+/// # Examples
+///
+/// ```
+/// use vehicle_routing::domain::{Location, Visit, Vehicle, VehicleRoutePlan};
+/// use vehicle_routing::export::{export, ExportFormat};
+///
+/// let depot = Location::new(0, 39.95, -75.16);
+/// let customer = Location::new(1, 39.96, -75.17);
+/// let mut vehicle = Vehicle::new(0, "Alpha", 10, depot.clone());
+/// vehicle.visits = vec![0];
+/// let mut plan = VehicleRoutePlan::new(
+///     "test",
+///     vec![depot, customer.clone()],
+///     vec![Visit::new(0, "Bakery & Co", customer)],
+///     vec![vehicle],
+/// );
+/// plan.finalize();
+///
+/// let gpx = export(&plan, ExportFormat::Gpx, None);
+/// assert!(gpx.contains("<trkpt lat=\"39.960000\" lon=\"-75.170000\"/>"));
+/// assert!(gpx.contains("Bakery &amp; Co"));
+///
+/// let kml = export(&plan, ExportFormat::Kml, Some(0));
+/// assert!(kml.contains("<coordinates>-75.160000,39.950000 -75.170000,39.960000 -75.160000,39.950000</coordinates>"));
+/// ```
+pub fn export(plan: &VehicleRoutePlan, format: ExportFormat, vehicle_id: Option<usize>) -> String {
+    match format {
+        ExportFormat::GeoJson => to_geojson(plan, vehicle_id).to_string(),
+        ExportFormat::Gpx => to_gpx(plan, vehicle_id),
+        ExportFormat::Kml => to_kml(plan, vehicle_id),
+    }
+}
+
+/// A visit on an exported route.
+struct Stop<'a> {
+    visit_idx: usize,
+    name: &'a str,
+    kind: &'static str,
+    lat: f64,
+    lng: f64,
+    arrival: i64,
+    service_start: i64,
+    departure: i64,
+}
+
+/// A vehicle's route: geometry and stops in order.
+struct ExportedRoute<'a> {
+    vehicle: &'a Vehicle,
+    coords: Vec<(f64, f64)>,
+    stops: Vec<Stop<'a>>,
+}
+
+/// Non-empty routes of all vehicles, or only of `vehicle_id`.
+fn routes(plan: &VehicleRoutePlan, vehicle_id: Option<usize>) -> Vec<ExportedRoute<'_>> {
+    plan.vehicles
+        .iter()
+        .filter(|v| !v.visits.is_empty() && vehicle_id.is_none_or(|id| v.id == id))
+        .map(|vehicle| {
+            let stops = plan
+                .calculate_route_times(vehicle)
+                .into_iter()
+                .filter_map(|timing| {
+                    let visit = plan.get_visit(timing.visit_idx)?;
+                    Some(Stop {
+                        visit_idx: visit.index,
+                        name: &visit.name,
+                        kind: if visit.is_reload() { "reload" } else { "visit" },
+                        lat: visit.location.latitude,
+                        lng: visit.location.longitude,
+                        arrival: timing.arrival,
+                        service_start: timing.service_start,
+                        departure: timing.departure,
+                    })
+                })
+                .collect();
+            ExportedRoute {
+                vehicle,
+                coords: get_route_coords(plan, vehicle),
+                stops,
+            }
+        })
+        .collect()
+}
+
+/// Formats a time offset as an ISO datetime.
+fn iso(plan: &VehicleRoutePlan, offset: i64) -> String {
+    plan.datetime_at(offset).format("%Y-%m-%dT%H:%M:%S").to_string()
+}
+
+/// Escapes text for XML (and HTML) content and attributes.
+pub(crate) fn xml_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+// ============================================================================
+// GeoJSON
+// ============================================================================
+
+/// Builds a GeoJSON FeatureCollection.
+///
+/// Each route is a LineString feature (`kind: "route"`) with vehicle and
+/// totals; each stop is a Point feature (`kind: "visit"` or `"reload"`) with
+/// its sequence number and arrival, service start and departure times. When
+/// exporting all vehicles, unassigned visits are included with a null
+/// `vehicleId`. Coordinates are `[longitude, latitude]` as GeoJSON requires.
+pub fn to_geojson(plan: &VehicleRoutePlan, vehicle_id: Option<usize>) -> Value {
+    let mut features = Vec::new();
+
+    for route in routes(plan, vehicle_id) {
+        let vehicle = route.vehicle;
+        let coordinates: Vec<[f64; 2]> = route.coords.iter().map(|&(lat, lng)| [lng, lat]).collect();
+        features.push(json!({
+            "type": "Feature",
+            "geometry": { "type": "LineString", "coordinates": coordinates },
+            "properties": {
+                "kind": "route",
+                "vehicleId": vehicle.id.to_string(),
+                "vehicleName": vehicle.name,
+                "departureTime": iso(plan, vehicle.departure_time),
+                "arrivalTime": iso(plan, plan.route_end_time(vehicle)),
+                "drivingTimeSeconds": plan.total_driving_time(vehicle),
+                "distanceMeters": plan.total_distance_meters(vehicle).round() as i64,
+                "visitCount": route.stops.len(),
+            },
+        }));
+
+        for (pos, stop) in route.stops.iter().enumerate() {
+            features.push(json!({
+                "type": "Feature",
+                "geometry": { "type": "Point", "coordinates": [stop.lng, stop.lat] },
+                "properties": {
+                    "kind": stop.kind,
+                    "id": format!("v{}", stop.visit_idx),
+                    "name": stop.name,
+                    "vehicleId": vehicle.id.to_string(),
+                    "sequence": pos + 1,
+                    "arrivalTime": iso(plan, stop.arrival),
+                    "serviceStartTime": iso(plan, stop.service_start),
+                    "departureTime": iso(plan, stop.departure),
+                },
+            }));
+        }
+    }
+
+    if vehicle_id.is_none() {
+        for idx in plan.unassigned_visits() {
+            let visit = &plan.visits[idx];
+            features.push(json!({
+                "type": "Feature",
+                "geometry": {
+                    "type": "Point",
+                    "coordinates": [visit.location.longitude, visit.location.latitude],
+                },
+                "properties": {
+                    "kind": "visit",
+                    "id": format!("v{}", idx),
+                    "name": visit.name,
+                    "vehicleId": null,
+                },
+            }));
+        }
+    }
+
+    json!({
+        "type": "FeatureCollection",
+        "name": plan.name,
+        "features": features,
+    })
+}
+
+// ============================================================================
+// GPX
+// ============================================================================
+
+/// Builds a GPX 1.1 document with one track per vehicle.
+///
+/// Stops become waypoints named `<vehicle> #<sequence>: <visit>`, timed at
+/// their arrival, so navigation devices list them in driving order.
+pub fn to_gpx(plan: &VehicleRoutePlan, vehicle_id: Option<usize>) -> String {
+    let routes = routes(plan, vehicle_id);
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str(
+        "<gpx version=\"1.1\" creator=\"SolverForge vehicle-routing\" xmlns=\"http://www.topografix.com/GPX/1/1\">\n",
+    );
+    let _ = writeln!(out, "  <metadata><name>{}</name></metadata>", xml_escape(&plan.name));
+
+    // GPX requires all waypoints before the tracks
+    for route in &routes {
+        for (pos, stop) in route.stops.iter().enumerate() {
+            let _ = writeln!(out, "  <wpt lat=\"{:.6}\" lon=\"{:.6}\">", stop.lat, stop.lng);
+            let _ = writeln!(out, "    <time>{}</time>", iso(plan, stop.arrival));
+            let _ = writeln!(
+                out,
+                "    <name>{}</name>",
+                xml_escape(&format!("{} #{}: {}", route.vehicle.name, pos + 1, stop.name))
+            );
+            let _ = writeln!(
+                out,
+                "    <desc>Arrival {}, service {}, departure {}</desc>",
+                iso(plan, stop.arrival),
+                iso(plan, stop.service_start),
+                iso(plan, stop.departure)
+            );
+            let _ = writeln!(out, "    <type>{}</type>", stop.kind);
+            out.push_str("  </wpt>\n");
+        }
+    }
+
+    for route in &routes {
+        out.push_str("  <trk>\n");
+        let _ = writeln!(out, "    <name>{}</name>", xml_escape(&route.vehicle.name));
+        out.push_str("    <trkseg>\n");
+        for &(lat, lng) in &route.coords {
+            let _ = writeln!(out, "      <trkpt lat=\"{:.6}\" lon=\"{:.6}\"/>", lat, lng);
+        }
+        out.push_str("    </trkseg>\n  </trk>\n");
+    }
+
+    out.push_str("</gpx>\n");
+    out
+}
+
+// ============================================================================
+// KML
+// ============================================================================
+
+/// Builds a KML 2.2 document with one folder per vehicle, holding the route
+/// line and a placemark per stop spanning its arrival to departure.
+pub fn to_kml(plan: &VehicleRoutePlan, vehicle_id: Option<usize>) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<kml xmlns=\"http://www.opengis.net/kml/2.2\">\n<Document>\n");
+    let _ = writeln!(out, "  <name>{}</name>", xml_escape(&plan.name));
+
+    for route in routes(plan, vehicle_id) {
+        let name = xml_escape(&route.vehicle.name);
+        out.push_str("  <Folder>\n");
+        let _ = writeln!(out, "    <name>{}</name>", name);
+
+        let coordinates: Vec<String> = route
+            .coords
+            .iter()
+            .map(|&(lat, lng)| format!("{:.6},{:.6}", lng, lat))
+            .collect();
+        out.push_str("    <Placemark>\n");
+        let _ = writeln!(out, "      <name>{} route</name>", name);
+        let _ = writeln!(
+            out,
+            "      <LineString><tessellate>1</tessellate><coordinates>{}</coordinates></LineString>",
+            coordinates.join(" ")
+        );
+        out.push_str("    </Placemark>\n");
+
+        for (pos, stop) in route.stops.iter().enumerate() {
+            out.push_str("    <Placemark>\n");
+            let _ = writeln!(out, "      <name>{}. {}</name>", pos + 1, xml_escape(stop.name));
+            let _ = writeln!(
+                out,
+                "      <description>Arrival {}, service {}, departure {}</description>",
+                iso(plan, stop.arrival),
+                iso(plan, stop.service_start),
+                iso(plan, stop.departure)
+            );
+            let _ = writeln!(
+                out,
+                "      <TimeSpan><begin>{}</begin><end>{}</end></TimeSpan>",
+                iso(plan, stop.arrival),
+                iso(plan, stop.departure)
+            );
+            let _ = writeln!(
+                out,
+                "      <Point><coordinates>{:.6},{:.6}</coordinates></Point>",
+                stop.lng, stop.lat
+            );
+            out.push_str("    </Placemark>\n");
+        }
+        out.push_str("  </Folder>\n");
+    }
+
+    out.push_str("</Document>\n</kml>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{Location, Visit};
+
+    fn sample_plan() -> VehicleRoutePlan {
+        let depot = Location::new(0, 39.95, -75.16);
+        let a = Location::new(1, 39.96, -75.17);
+        let b = Location::new(2, 39.97, -75.15);
+        let mut vehicle = Vehicle::new(0, "Alpha", 10, depot.clone()).with_departure_time(8 * 3600);
+        vehicle.visits = vec![1, 0];
+        let idle = Vehicle::new(1, "Bravo", 10, depot.clone());
+        let visits = vec![
+            Visit::new(0, "A", a.clone()).with_service_duration(600),
+            Visit::new(1, "B", b.clone()),
+            Visit::new(2, "Dropped", b.clone()),
+        ];
+        let mut plan = VehicleRoutePlan::new("test", vec![depot, a, b], visits, vec![vehicle, idle]);
+        plan.finalize();
+        plan
+    }
+
+    #[test]
+    fn test_geojson_features() {
+        let plan = sample_plan();
+        let doc = to_geojson(&plan, None);
+        let features = doc["features"].as_array().unwrap();
+        // One route, two stops, one unassigned visit; the idle vehicle is skipped
+        assert_eq!(features.len(), 4);
+
+        let route = &features[0];
+        assert_eq!(route["geometry"]["type"], "LineString");
+        assert_eq!(route["geometry"]["coordinates"][0], json!([-75.16, 39.95]));
+        assert_eq!(route["properties"]["vehicleId"], "0");
+
+        let first = &features[1]["properties"];
+        assert_eq!(first["id"], "v1");
+        assert_eq!(first["sequence"], 1);
+        assert!(first["arrivalTime"].as_str().unwrap().starts_with("2025-01-05T08:"));
+
+        let second = &features[2]["properties"];
+        assert_eq!(second["id"], "v0");
+        let timing = &plan.calculate_route_times(&plan.vehicles[0])[1];
+        assert_eq!(second["departureTime"], iso(&plan, timing.departure));
+
+        assert_eq!(features[3]["properties"]["id"], "v2");
+        assert!(features[3]["properties"]["vehicleId"].is_null());
+    }
+
+    #[test]
+    fn test_vehicle_filter() {
+        let plan = sample_plan();
+        let doc = to_geojson(&plan, Some(1));
+        assert!(doc["features"].as_array().unwrap().is_empty());
+        assert!(!to_gpx(&plan, Some(1)).contains("<trk>"));
+    }
+
+    #[test]
+    fn test_gpx_waypoints_precede_tracks() {
+        let plan = sample_plan();
+        let gpx = to_gpx(&plan, None);
+        let last_wpt = gpx.rfind("<wpt").unwrap();
+        let first_trk = gpx.find("<trk>").unwrap();
+        assert!(last_wpt < first_trk);
+        assert_eq!(gpx.matches("<wpt").count(), 2);
+        assert!(gpx.contains("<name>Alpha #1: B</name>"));
+    }
+
+    #[test]
+    fn test_xml_escape() {
+        assert_eq!(xml_escape("A & B <\"x\">"), "A &amp; B &lt;&quot;x&quot;&gt;");
+    }
+}
@@ -1,6 +1,7 @@
 //! Geometry utilities for route visualization.
 //!
-//! Implements Google Polyline encoding for efficient route transmission.
+//! Implements Google Polyline encoding for efficient route transmission,
+//! and Douglas–Peucker simplification so maps can load coarse shapes first.
 //! See: <https://developers.google.com/maps/documentation/utilities/polylinealgorithm>
 
 use crate::domain::{Vehicle, VehicleRoutePlan};
@@ -144,6 +145,74 @@ fn decode_value(bytes: &[u8]) -> (i64, usize) {
     (result, consumed)
 }
 
+/// Simplifies a line with the Douglas–Peucker algorithm.
+///
+/// Drops points closer than `tolerance_m` meters to the simplified line;
+/// the first and last points are always kept. A tolerance of zero or less
+/// returns the line unchanged.
+///
+/// # Examples
+///
+/// ```
+/// use vehicle_routing::geometry::simplify;
+///
+/// // The middle point is ~1 m off the straight line
+/// let line = vec![(39.95, -75.16), (39.95001, -75.155), (39.95, -75.15)];
+/// assert_eq!(simplify(&line, 5.0), vec![(39.95, -75.16), (39.95, -75.15)]);
+/// assert_eq!(simplify(&line, 0.5), line);
+/// assert_eq!(simplify(&line, 0.0), line);
+/// ```
+pub fn simplify(coords: &[(f64, f64)], tolerance_m: f64) -> Vec<(f64, f64)> {
+    if tolerance_m <= 0.0 || coords.len() < 3 {
+        return coords.to_vec();
+    }
+
+    // Project to local meters (equirectangular around the first point)
+    let (lat0, _) = coords[0];
+    let meters_per_deg_lat = 111_320.0;
+    let meters_per_deg_lng = meters_per_deg_lat * lat0.to_radians().cos();
+    let points: Vec<(f64, f64)> = coords
+        .iter()
+        .map(|&(lat, lng)| (lng * meters_per_deg_lng, lat * meters_per_deg_lat))
+        .collect();
+
+    let mut keep = vec![false; coords.len()];
+    keep[0] = true;
+    keep[coords.len() - 1] = true;
+    let mut stack = vec![(0, coords.len() - 1)];
+    while let Some((first, last)) = stack.pop() {
+        let farthest = (first + 1..last)
+            .map(|i| (i, distance_to_segment(points[i], points[first], points[last])))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((i, distance)) = farthest {
+            if distance > tolerance_m {
+                keep[i] = true;
+                stack.push((first, i));
+                stack.push((i, last));
+            }
+        }
+    }
+
+    coords
+        .iter()
+        .zip(keep)
+        .filter_map(|(&c, k)| k.then_some(c))
+        .collect()
+}
+
+/// Distance from `p` to the segment `a`-`b`, in planar coordinates.
+fn distance_to_segment(p: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let len_sq = dx * dx + dy * dy;
+    let t = if len_sq > 0.0 {
+        (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / len_sq).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let (x, y) = (a.0 + t * dx, a.1 + t * dy);
+    ((p.0 - x).powi(2) + (p.1 - y).powi(2)).sqrt()
+}
+
 /// Encoded route segment for a vehicle's route.
 #[derive(Debug, Clone, serde::Serialize, ToSchema)]
 pub struct EncodedSegment {
@@ -193,11 +262,17 @@ pub struct EncodedSegment {
 /// assert_eq!(segments[0].point_count, 4);  // depot -> A -> B -> depot
 /// ```
 pub fn encode_routes(plan: &VehicleRoutePlan) -> Vec<EncodedSegment> {
+    encode_routes_simplified(plan, 0.0)
+}
+
+/// Generates encoded polylines for all vehicle routes, simplified to
+/// `tolerance_m` meters (see [`simplify`]).
+pub fn encode_routes_simplified(plan: &VehicleRoutePlan, tolerance_m: f64) -> Vec<EncodedSegment> {
     plan.vehicles
         .iter()
         .filter(|v| !v.visits.is_empty())
         .map(|vehicle| {
-            let coords = get_route_coords(plan, vehicle);
+            let coords = simplify(&get_route_coords(plan, vehicle), tolerance_m);
             let polyline = encode_polyline(&coords);
             EncodedSegment {
                 vehicle_idx: vehicle.id,
@@ -209,49 +284,102 @@ pub fn encode_routes(plan: &VehicleRoutePlan) -> Vec<EncodedSegment> {
         .collect()
 }
 
-/// Gets coordinates for a vehicle's complete route (depot -> visits -> depot).
-///
-/// Uses stored route geometries from road network routing.
-/// Returns empty if route geometries are not initialized.
-fn get_route_coords(plan: &VehicleRoutePlan, vehicle: &Vehicle) -> Vec<(f64, f64)> {
-    let mut coords = Vec::new();
-    let depot_idx = vehicle.home_location.index;
-
-    // Build the sequence of location indices: depot -> visits -> depot
-    let visit_loc_indices: Vec<usize> = vehicle
-        .visits
-        .iter()
-        .filter_map(|&v| plan.get_visit(v).map(|visit| visit.location.index))
-        .collect();
-
-    let route: Vec<usize> = std::iter::once(depot_idx)
-        .chain(visit_loc_indices)
-        .chain(std::iter::once(depot_idx))
-        .collect();
+/// Encoded geometry of one leg of a vehicle's route.
+#[derive(Debug, Clone, serde::Serialize, ToSchema)]
+pub struct EncodedLeg {
+    /// Vehicle index.
+    pub vehicle_idx: usize,
+    /// Position of the leg in the route (0 = leaving the start location).
+    pub leg_idx: usize,
+    /// Location index the leg starts at.
+    pub from_location: usize,
+    /// Location index the leg ends at.
+    pub to_location: usize,
+    /// Driving time in seconds, at the leg's planned departure.
+    pub travel_time_seconds: i64,
+    /// Road distance in meters.
+    pub distance_meters: f64,
+    /// Encoded polyline string (Google format).
+    pub polyline: String,
+    /// Number of points in the leg.
+    pub point_count: usize,
+}
 
-    // Process each leg
-    for i in 0..route.len().saturating_sub(1) {
-        let from_idx = route[i];
-        let to_idx = route[i + 1];
-
-        if let Some(geometry) = plan.route_geometry(from_idx, to_idx) {
-            // Use stored road geometry
-            // Skip first point of subsequent segments to avoid duplicates
-            let skip = if coords.is_empty() { 0 } else { 1 };
-            coords.extend(geometry.iter().skip(skip).copied());
-        } else {
-            // Fallback: use direct lat/lng when road geometry unavailable
-            if coords.is_empty() {
-                if let Some(from_loc) = plan.get_location(from_idx) {
-                    coords.push((from_loc.latitude, from_loc.longitude));
-                }
-            }
-            if let Some(to_loc) = plan.get_location(to_idx) {
-                coords.push((to_loc.latitude, to_loc.longitude));
-            }
+/// Generates encoded polylines per route leg, with each leg's travel time
+/// and distance, simplified to `tolerance_m` meters.
+///
+/// # Examples
+///
+/// ```
+/// use vehicle_routing::domain::{Location, Visit, Vehicle, VehicleRoutePlan};
+/// use vehicle_routing::geometry::encode_legs;
+///
+/// let depot = Location::new(0, 39.95, -75.16);
+/// let loc_a = Location::new(1, 39.96, -75.17);
+/// let mut vehicle = Vehicle::new(0, "Alpha", 100, depot.clone());
+/// vehicle.visits = vec![0];
+/// let mut plan = VehicleRoutePlan::new(
+///     "test",
+///     vec![depot, loc_a.clone()],
+///     vec![Visit::new(0, "A", loc_a)],
+///     vec![vehicle],
+/// );
+/// plan.finalize();
+///
+/// let legs = encode_legs(&plan, 0.0);
+/// assert_eq!(legs.len(), 2); // depot -> A -> depot
+/// assert_eq!((legs[0].from_location, legs[0].to_location), (0, 1));
+/// assert_eq!(legs[0].travel_time_seconds, plan.travel_time(0, 1));
+/// assert_eq!(legs[1].point_count, 2); // straight line without road geometry
+/// ```
+pub fn encode_legs(plan: &VehicleRoutePlan, tolerance_m: f64) -> Vec<EncodedLeg> {
+    let mut legs = Vec::new();
+    for vehicle in plan.vehicles.iter().filter(|v| !v.visits.is_empty()) {
+        let route = plan.route_location_indices(vehicle);
+        let departures = std::iter::once(vehicle.departure_time)
+            .chain(plan.calculate_route_times(vehicle).into_iter().map(|t| t.departure));
+
+        for (leg_idx, (pair, departure)) in route.windows(2).zip(departures).enumerate() {
+            let (from, to) = (pair[0], pair[1]);
+            let coords = simplify(&leg_coords(plan, from, to), tolerance_m);
+            legs.push(EncodedLeg {
+                vehicle_idx: vehicle.id,
+                leg_idx,
+                from_location: from,
+                to_location: to,
+                travel_time_seconds: plan.travel_time_at(from, to, departure),
+                distance_meters: plan.distance_meters(from, to),
+                polyline: encode_polyline(&coords),
+                point_count: coords.len(),
+            });
         }
     }
+    legs
+}
+
+/// Gets coordinates of one leg: the stored road geometry, or a straight
+/// line when road geometry is unavailable.
+fn leg_coords(plan: &VehicleRoutePlan, from_idx: usize, to_idx: usize) -> Vec<(f64, f64)> {
+    match plan.route_geometry(from_idx, to_idx) {
+        Some(geometry) => geometry.to_vec(),
+        None => [from_idx, to_idx]
+            .iter()
+            .filter_map(|&idx| plan.get_location(idx).map(|l| (l.latitude, l.longitude)))
+            .collect(),
+    }
+}
 
+/// Gets coordinates for a vehicle's complete route (start -> visits -> end).
+///
+/// Open routes stop at the last visit. Uses stored route geometries from
+/// road network routing, with straight lines for legs without one.
+pub(crate) fn get_route_coords(plan: &VehicleRoutePlan, vehicle: &Vehicle) -> Vec<(f64, f64)> {
+    let mut coords: Vec<(f64, f64)> = Vec::new();
+    for pair in plan.route_location_indices(vehicle).windows(2) {
+        // Skip the first point of subsequent legs to avoid duplicates
+        let skip = usize::from(!coords.is_empty());
+        coords.extend(leg_coords(plan, pair[0], pair[1]).into_iter().skip(skip));
+    }
     coords
 }
 
@@ -305,4 +433,45 @@ mod tests {
         assert!((decoded[0].0).abs() < 0.00001);
         assert!((decoded[0].1).abs() < 0.00001);
     }
+
+    #[test]
+    fn test_simplify_zigzag() {
+        // A line along the equator with points ~11 m and ~55 m off to the side
+        let line = vec![(0.0, 0.0), (0.0001, 0.001), (0.0, 0.002), (0.0005, 0.003), (0.0, 0.004)];
+        assert_eq!(simplify(&line, 5.0), line);
+        assert_eq!(simplify(&line, 20.0), vec![(0.0, 0.0), (0.0, 0.002), (0.0005, 0.003), (0.0, 0.004)]);
+        assert_eq!(simplify(&line, 40.0), vec![(0.0, 0.0), (0.0005, 0.003), (0.0, 0.004)]);
+        assert_eq!(simplify(&line, 100.0), vec![(0.0, 0.0), (0.0, 0.004)]);
+        assert_eq!(simplify(&line[..2], 100.0), line[..2]);
+    }
+
+    #[test]
+    fn test_legs_use_stored_geometry() {
+        use crate::domain::{Location, Visit};
+
+        let depot = Location::new(0, 39.95, -75.16);
+        let loc_a = Location::new(1, 39.96, -75.17);
+        let mut vehicle = Vehicle::new(0, "Alpha", 100, depot.clone());
+        vehicle.visits = vec![0];
+        let mut plan = VehicleRoutePlan::new(
+            "test",
+            vec![depot, loc_a.clone()],
+            vec![Visit::new(0, "A", loc_a).with_service_duration(600)],
+            vec![vehicle],
+        );
+        plan.finalize();
+        let road = vec![(39.95, -75.16), (39.955, -75.16), (39.96, -75.165), (39.96, -75.17)];
+        plan.route_geometries.insert((0, 1), road.clone());
+
+        let legs = encode_legs(&plan, 0.0);
+        assert_eq!(legs.len(), 2);
+        assert_eq!(decode_polyline(&legs[0].polyline), road);
+        assert_eq!(legs[0].point_count, 4);
+        assert_eq!(legs[1].leg_idx, 1);
+        assert_eq!(legs[1].distance_meters, plan.distance_meters(1, 0));
+
+        // The whole route joins the legs without repeating the shared point
+        let coords = get_route_coords(&plan, &plan.vehicles[0]);
+        assert_eq!(coords.len(), 5);
+    }
 }
@@ -0,0 +1,420 @@
+//! Durable storage for solve jobs.
+//!
+//! A [`JobRepository`] keeps each job's problem, best solution, score,
+//! solver configuration and status, so route plans survive a restart.
+//! [`FileJobRepository`] stores one directory per job under `.jobs/`:
+//!
+//! - `job.json`: id, status, score and solver configuration
+//! - `problem.json`: the plan as submitted
+//! - `routing.json`: travel matrices and route geometries, shared by both plans
+//! - `solution.json`: the best solution so far, once there is one
+//!
+//! Files are replaced atomically. [`MemoryJobRepository`] keeps nothing
+//! across restarts.
+
+use crate::domain::VehicleRoutePlan;
+use crate::road_cache::write_atomic;
+use crate::solver::{SolverConfig, SolverStatus};
+use crate::traffic::TimeDependentTimes;
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use solverforge::prelude::HardSoftScore;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::warn;
+
+/// Default job directory.
+pub const JOBS_DIR: &str = ".jobs";
+
+/// Environment variable overriding the job directory.
+pub const JOBS_DIR_ENV: &str = "JOBS_DIR";
+
+/// Environment variable selecting the backend: `file` (default) or `memory`.
+pub const JOB_STORE_ENV: &str = "JOB_STORE";
+
+/// Stored state of a job, apart from its plans.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobRecord {
+    /// Job identifier.
+    pub id: String,
+    /// Solver status when last saved.
+    pub status: SolverStatus,
+    /// The service stopped while the job was solving; it can be resumed.
+    #[serde(default)]
+    pub interrupted: bool,
+    /// Score of the best solution.
+    pub score: Option<HardSoftScore>,
+    /// Termination configuration.
+    pub config: SolverConfig,
+    /// Time of the last save.
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A job as loaded from a repository.
+#[derive(Clone)]
+pub struct StoredJob {
+    /// Status, score and configuration.
+    pub record: JobRecord,
+    /// The plan as submitted, with its travel matrices.
+    pub problem: VehicleRoutePlan,
+    /// Best solution so far, with its travel matrices.
+    pub solution: Option<VehicleRoutePlan>,
+}
+
+impl StoredJob {
+    /// The best solution, or the problem if solving never got far enough
+    /// to save one.
+    pub fn best_plan(&self) -> &VehicleRoutePlan {
+        self.solution.as_ref().unwrap_or(&self.problem)
+    }
+}
+
+/// Storage for solve jobs.
+pub trait JobRepository: Send + Sync {
+    /// Stores a new job with its problem.
+    fn create(&self, record: &JobRecord, problem: &VehicleRoutePlan) -> io::Result<()>;
+
+    /// Stores a job's state and, if given, its new best solution.
+    ///
+    /// Jobs that were removed are not recreated.
+    fn update(&self, record: &JobRecord, solution: Option<&VehicleRoutePlan>) -> io::Result<()>;
+
+    /// Deletes a job.
+    fn remove(&self, id: &str) -> io::Result<()>;
+
+    /// Loads every stored job.
+    fn load_all(&self) -> io::Result<Vec<StoredJob>>;
+}
+
+/// Creates the repository selected by [`JOB_STORE_ENV`] and [`JOBS_DIR_ENV`].
+pub fn from_env() -> Arc<dyn JobRepository> {
+    match std::env::var(JOB_STORE_ENV).as_deref() {
+        Ok("memory") => Arc::new(MemoryJobRepository::default()),
+        Ok(other) if other != "file" => {
+            warn!("Unknown {} {:?}, storing jobs on disk", JOB_STORE_ENV, other);
+            Arc::new(FileJobRepository::new(jobs_dir()))
+        }
+        _ => Arc::new(FileJobRepository::new(jobs_dir())),
+    }
+}
+
+fn jobs_dir() -> PathBuf {
+    std::env::var_os(JOBS_DIR_ENV)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(JOBS_DIR))
+}
+
+// ============================================================================
+// In-Memory Backend
+// ============================================================================
+
+/// Keeps jobs in memory only.
+#[derive(Default)]
+pub struct MemoryJobRepository {
+    jobs: Mutex<HashMap<String, StoredJob>>,
+}
+
+impl JobRepository for MemoryJobRepository {
+    fn create(&self, record: &JobRecord, problem: &VehicleRoutePlan) -> io::Result<()> {
+        let job = StoredJob {
+            record: record.clone(),
+            problem: problem.clone(),
+            solution: None,
+        };
+        self.jobs.lock().insert(record.id.clone(), job);
+        Ok(())
+    }
+
+    fn update(&self, record: &JobRecord, solution: Option<&VehicleRoutePlan>) -> io::Result<()> {
+        if let Some(job) = self.jobs.lock().get_mut(&record.id) {
+            job.record = record.clone();
+            if let Some(solution) = solution {
+                job.solution = Some(solution.clone());
+            }
+        }
+        Ok(())
+    }
+
+    fn remove(&self, id: &str) -> io::Result<()> {
+        self.jobs.lock().remove(id);
+        Ok(())
+    }
+
+    fn load_all(&self) -> io::Result<Vec<StoredJob>> {
+        Ok(self.jobs.lock().values().cloned().collect())
+    }
+}
+
+// ============================================================================
+// File Backend
+// ============================================================================
+
+/// Stores jobs as JSON files, one directory per job.
+pub struct FileJobRepository {
+    dir: PathBuf,
+    /// Serializes writes and removals, so a save racing a delete cannot
+    /// recreate the job directory after it was removed.
+    lock: Mutex<()>,
+}
+
+/// Travel data skipped by the plan's own serialization.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RoutingData<'a> {
+    travel_time_matrix: Cow<'a, [Vec<i64>]>,
+    distance_matrix: Cow<'a, [Vec<i64>]>,
+    time_dependent_times: Cow<'a, Option<TimeDependentTimes>>,
+    route_geometries: Vec<StoredGeometry<'a>>,
+}
+
+/// Route geometry between two locations.
+#[derive(Serialize, Deserialize)]
+struct StoredGeometry<'a> {
+    from: usize,
+    to: usize,
+    coords: Cow<'a, [(f64, f64)]>,
+}
+
+impl<'a> RoutingData<'a> {
+    fn of(plan: &'a VehicleRoutePlan) -> Self {
+        Self {
+            travel_time_matrix: Cow::Borrowed(&plan.travel_time_matrix),
+            distance_matrix: Cow::Borrowed(&plan.distance_matrix),
+            time_dependent_times: Cow::Borrowed(&plan.time_dependent_times),
+            route_geometries: plan
+                .route_geometries
+                .iter()
+                .map(|(&(from, to), coords)| StoredGeometry {
+                    from,
+                    to,
+                    coords: Cow::Borrowed(coords.as_slice()),
+                })
+                .collect(),
+        }
+    }
+
+    fn apply_to(&self, plan: &mut VehicleRoutePlan) {
+        plan.travel_time_matrix = self.travel_time_matrix.to_vec();
+        plan.distance_matrix = self.distance_matrix.to_vec();
+        plan.time_dependent_times = self.time_dependent_times.as_ref().clone();
+        plan.route_geometries = self
+            .route_geometries
+            .iter()
+            .map(|g| ((g.from, g.to), g.coords.to_vec()))
+            .collect();
+    }
+}
+
+impl FileJobRepository {
+    /// Creates a repository storing jobs under `dir`.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Directory of one job. Ids are generated UUIDs; anything that could
+    /// escape the job directory is rejected.
+    fn job_dir(&self, id: &str) -> io::Result<PathBuf> {
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("invalid job id {:?}", id)));
+        }
+        Ok(self.dir.join(id))
+    }
+
+    fn load(&self, dir: &Path) -> io::Result<StoredJob> {
+        let record: JobRecord = read_json(&dir.join("job.json"))?;
+        let routing: RoutingData = read_json(&dir.join("routing.json"))?;
+        let mut problem: VehicleRoutePlan = read_json(&dir.join("problem.json"))?;
+        routing.apply_to(&mut problem);
+
+        let solution_path = dir.join("solution.json");
+        let solution = if solution_path.exists() {
+            let mut solution: VehicleRoutePlan = read_json(&solution_path)?;
+            routing.apply_to(&mut solution);
+            Some(solution)
+        } else {
+            None
+        };
+        Ok(StoredJob {
+            record,
+            problem,
+            solution,
+        })
+    }
+}
+
+impl JobRepository for FileJobRepository {
+    fn create(&self, record: &JobRecord, problem: &VehicleRoutePlan) -> io::Result<()> {
+        let dir = self.job_dir(&record.id)?;
+        let routing = serde_json::to_vec(&RoutingData::of(problem))?;
+        let problem = serde_json::to_vec(problem)?;
+        let record = serde_json::to_vec_pretty(record)?;
+
+        let _guard = self.lock.lock();
+        write_atomic(&dir.join("routing.json"), &routing)?;
+        write_atomic(&dir.join("problem.json"), &problem)?;
+        // Written last: directories without a record are ignored on load
+        write_atomic(&dir.join("job.json"), &record)
+    }
+
+    fn update(&self, record: &JobRecord, solution: Option<&VehicleRoutePlan>) -> io::Result<()> {
+        let dir = self.job_dir(&record.id)?;
+        let solution = solution.map(serde_json::to_vec).transpose()?;
+        let record = serde_json::to_vec_pretty(record)?;
+
+        // The existence check and the writes must not interleave with remove
+        let _guard = self.lock.lock();
+        if !dir.join("job.json").exists() {
+            return Ok(());
+        }
+        if let Some(solution) = solution {
+            write_atomic(&dir.join("solution.json"), &solution)?;
+        }
+        write_atomic(&dir.join("job.json"), &record)
+    }
+
+    fn remove(&self, id: &str) -> io::Result<()> {
+        let dir = self.job_dir(id)?;
+        let _guard = self.lock.lock();
+        match fs::remove_dir_all(dir) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        }
+    }
+
+    fn load_all(&self) -> io::Result<Vec<StoredJob>> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        let mut jobs = Vec::new();
+        for entry in entries.flatten() {
+            let dir = entry.path();
+            if !dir.join("job.json").is_file() {
+                continue;
+            }
+            match self.load(&dir) {
+                Ok(job) => jobs.push(job),
+                Err(e) => warn!("Skipping unreadable job {:?}: {}", dir, e),
+            }
+        }
+        Ok(jobs)
+    }
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> io::Result<T> {
+    Ok(serde_json::from_slice(&fs::read(path)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{Location, Vehicle, Visit};
+    use std::time::Duration;
+
+    fn sample_plan() -> VehicleRoutePlan {
+        let depot = Location::new(0, 39.95, -75.16);
+        let customer = Location::new(1, 39.96, -75.17);
+        let visits = vec![Visit::new(0, "Customer", customer.clone())];
+        let vehicles = vec![Vehicle::new(0, "Truck", 10, depot.clone())];
+        let mut plan = VehicleRoutePlan::new("test", vec![depot, customer], visits, vehicles);
+        plan.finalize();
+        plan.route_geometries
+            .insert((0, 1), vec![(39.95, -75.16), (39.955, -75.165), (39.96, -75.17)]);
+        plan
+    }
+
+    fn record(id: &str, status: SolverStatus) -> JobRecord {
+        JobRecord {
+            id: id.to_string(),
+            status,
+            interrupted: false,
+            score: None,
+            config: SolverConfig {
+                unimproved_time_limit: Some(Duration::from_secs(5)),
+                ..SolverConfig::default_config()
+            },
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("vrp-jobs-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_file_round_trip() {
+        let dir = temp_dir("round-trip");
+        let repo = FileJobRepository::new(&dir);
+        let plan = sample_plan();
+        let mut rec = record("job-1", SolverStatus::Solving);
+        repo.create(&rec, &plan).unwrap();
+
+        let loaded = repo.load_all().unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].record, rec);
+        assert!(loaded[0].solution.is_none());
+        assert_eq!(loaded[0].problem.travel_time_matrix, plan.travel_time_matrix);
+        assert_eq!(loaded[0].problem.route_geometries, plan.route_geometries);
+
+        let mut solution = plan.clone();
+        solution.vehicles[0].visits = vec![0];
+        rec.status = SolverStatus::NotSolving;
+        rec.score = Some(HardSoftScore::of(0, -42));
+        repo.update(&rec, Some(&solution)).unwrap();
+
+        let loaded = repo.load_all().unwrap();
+        assert_eq!(loaded[0].record, rec);
+        let best = loaded[0].best_plan();
+        assert_eq!(best.vehicles[0].visits, vec![0]);
+        assert_eq!(best.distance_matrix, plan.distance_matrix);
+        assert!(loaded[0].problem.vehicles[0].visits.is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_removed_jobs_stay_removed() {
+        let dir = temp_dir("remove");
+        let repo = FileJobRepository::new(&dir);
+        let rec = record("job-2", SolverStatus::Solving);
+        repo.create(&rec, &sample_plan()).unwrap();
+        repo.remove("job-2").unwrap();
+        repo.remove("job-2").unwrap();
+
+        // A solver thread saving after removal must not bring the job back
+        repo.update(&rec, Some(&sample_plan())).unwrap();
+        assert!(repo.load_all().unwrap().is_empty());
+        assert!(!dir.join("job-2").exists());
+        assert!(repo.remove("../escape").is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_unreadable_jobs_skipped() {
+        let dir = temp_dir("corrupt");
+        let repo = FileJobRepository::new(&dir);
+        repo.create(&record("good", SolverStatus::NotSolving), &sample_plan()).unwrap();
+        repo.create(&record("bad", SolverStatus::NotSolving), &sample_plan()).unwrap();
+        fs::write(dir.join("bad").join("problem.json"), b"{").unwrap();
+
+        let loaded = repo.load_all().unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].record.id, "good");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
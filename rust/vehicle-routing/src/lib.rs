@@ -7,20 +7,34 @@
 //!
 //! - [`Location`](domain::Location): Geographic point with haversine distance
 //! - [`Visit`](domain::Visit): Customer to visit with time window and demand
-//! - [`Vehicle`](domain::Vehicle): Delivery vehicle with capacity and route
+//! - [`Vehicle`](domain::Vehicle): Delivery vehicle with capacity, costs and route
 //! - [`VehicleRoutePlan`](domain::VehicleRoutePlan): Complete planning solution
 //!
 //! # Constraints
 //!
-//! - **Vehicle capacity** (hard): Total demand must not exceed vehicle capacity
+//! - **Vehicle capacity** (hard): On-board load must not exceed vehicle
+//!   capacity, checked per trip when vehicles reload at their depot
 //! - **Time windows** (hard): Service must finish before max end time
 //! - **Travel time** (soft): Minimize total driving time
+//! - **Vehicle cost** (soft): Alternatively minimize fixed plus distance/time costs
+//! - **Vehicles used** (soft): Optionally minimize fleet size
+//! - **Unassigned visits** (soft): Optional visits may be dropped at a cost
+//!   set by their priority
 
 pub mod api;
 pub mod console;
 pub mod constraints;
 pub mod demo_data;
 pub mod domain;
+pub mod export;
 pub mod geometry;
+pub mod job_store;
+pub mod manifest;
+pub mod matrix;
+pub mod metrics;
+mod osm_import;
+pub mod recommendation;
+pub mod road_cache;
 pub mod routing;
 pub mod solver;
+pub mod traffic;
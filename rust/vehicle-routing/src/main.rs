@@ -10,6 +10,8 @@ use tower_http::cors::{Any, CorsLayer};
 use tower_http::services::ServeDir;
 use tracing_subscriber::EnvFilter;
 use vehicle_routing::console;
+use vehicle_routing::road_cache;
+use vehicle_routing::routing::{self, RoadNetworkSource};
 
 #[tokio::main]
 async fn main() {
@@ -21,9 +23,25 @@ async fn main() {
         )
         .init();
 
+    // `vehicle-routing cache list|purge [key]` manages the road network cache
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("cache") {
+        run_cache_command(&args[1..]).await;
+        return;
+    }
+
     // Print colorful banner
     console::print_banner();
 
+    // Report offline road network source, if configured
+    if let RoadNetworkSource::Extract(path) = routing::network_source() {
+        println!(
+            "{} Reading road networks from {}",
+            "▸".bright_green(),
+            path.display().bright_cyan()
+        );
+    }
+
     // CORS for development
     let cors = CorsLayer::new()
         .allow_origin(Any)
@@ -58,3 +76,32 @@ async fn main() {
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
     axum::serve(listener, app).await.unwrap();
 }
+
+/// Lists or purges cached road networks, then exits.
+async fn run_cache_command(args: &[String]) {
+    match args.first().map(String::as_str) {
+        Some("list") => {
+            let networks = road_cache::list().await;
+            if networks.is_empty() {
+                println!("{} No cached road networks", "▸".bright_green());
+            }
+            for n in networks {
+                println!(
+                    "{} {}  {}  {} nodes  {} edges  {:.1} MB  last used {}",
+                    "▸".bright_green(),
+                    n.key.bright_cyan(),
+                    n.source,
+                    n.node_count,
+                    n.edge_count,
+                    n.size_bytes as f64 / (1024.0 * 1024.0),
+                    n.last_used.unwrap_or_default()
+                );
+            }
+        }
+        Some("purge") => {
+            let removed = road_cache::purge(args.get(1).map(String::as_str)).await.files;
+            println!("{} Removed {} cached road network file(s)", "▸".bright_green(), removed);
+        }
+        _ => eprintln!("usage: vehicle-routing cache list | cache purge [key]"),
+    }
+}
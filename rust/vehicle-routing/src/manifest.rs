@@ -0,0 +1,328 @@
+//! Driver manifests (run sheets).
+//!
+//! A [`Manifest`] lists one vehicle's stops in driving order with planned
+//! arrival, waiting, service start and end, demand and the load on board
+//! after each stop. Manifests are served as JSON or rendered as a
+//! print-friendly HTML page with one sheet per vehicle.
+
+use crate::domain::{Vehicle, VehicleRoutePlan, VisitKind};
+use crate::export::xml_escape;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use utoipa::ToSchema;
+
+/// Manifest output format.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ManifestFormat {
+    /// Manifests as JSON.
+    #[default]
+    Json,
+    /// Print-friendly HTML, one page per vehicle.
+    Html,
+}
+
+/// One stop on a driver manifest.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestStop {
+    /// Position in the route, starting at 1.
+    pub sequence: usize,
+    /// Visit ID.
+    pub visit_id: String,
+    /// Customer name.
+    pub name: String,
+    /// Whether goods are delivered, picked up or reloaded.
+    pub kind: VisitKind,
+    /// Address coordinates as `[latitude, longitude]`.
+    pub location: [f64; 2],
+    /// Driving time from the previous stop in seconds.
+    pub travel_time_seconds: i64,
+    /// Road distance from the previous stop in meters.
+    pub distance_meters: f64,
+    /// Planned arrival (ISO datetime).
+    pub arrival_time: String,
+    /// Time waiting for the time window to open, in seconds.
+    pub waiting_seconds: i64,
+    /// Service start (ISO datetime).
+    pub service_start_time: String,
+    /// Service end and departure (ISO datetime).
+    pub service_end_time: String,
+    /// Earliest service start allowed (ISO datetime).
+    pub time_window_start: String,
+    /// Latest service end allowed (ISO datetime).
+    pub time_window_end: String,
+    /// Units delivered, picked up or reloaded.
+    pub demand: i32,
+    /// Load on board after the stop.
+    pub load_after: i32,
+}
+
+/// Run sheet for one vehicle.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest {
+    /// Vehicle ID.
+    pub vehicle_id: String,
+    /// Vehicle name.
+    pub vehicle_name: String,
+    /// Vehicle capacity.
+    pub capacity: i32,
+    /// Start location as `[latitude, longitude]`.
+    pub start_location: [f64; 2],
+    /// End location as `[latitude, longitude]`; absent for open routes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_location: Option<[f64; 2]>,
+    /// Departure from the start location (ISO datetime).
+    pub departure_time: String,
+    /// Arrival at the end location, or departure from the last stop for
+    /// open routes (ISO datetime).
+    pub return_time: String,
+    /// Load on board when leaving the start location.
+    pub start_load: i32,
+    /// Total road distance in meters.
+    pub total_distance_meters: f64,
+    /// Total driving time in seconds.
+    pub total_driving_time_seconds: i64,
+    /// Stops in driving order.
+    pub stops: Vec<ManifestStop>,
+}
+
+/// Builds the manifest of one vehicle.
+///
+/// # Examples
+///
+/// ```
+/// use vehicle_routing::domain::{Location, Visit, Vehicle, VehicleRoutePlan};
+/// use vehicle_routing::manifest::build_manifest;
+///
+/// let depot = Location::new(0, 39.95, -75.16);
+/// let customer = Location::new(1, 39.96, -75.17);
+/// let mut vehicle = Vehicle::new(0, "Alpha", 10, depot.clone()).with_departure_time(8 * 3600);
+/// vehicle.visits = vec![0];
+/// let visit = Visit::new(0, "Bakery", customer)
+///     .with_demand(4)
+///     .with_time_window(10 * 3600, 12 * 3600)
+///     .with_service_duration(600);
+/// let mut plan = VehicleRoutePlan::new("test", vec![depot, visit.location.clone()], vec![visit], vec![vehicle]);
+/// plan.finalize();
+///
+/// let manifest = build_manifest(&plan, &plan.vehicles[0]);
+/// assert_eq!(manifest.start_load, 4);
+/// let stop = &manifest.stops[0];
+/// assert_eq!(stop.service_start_time, "2025-01-05T10:00:00");
+/// assert_eq!(stop.service_end_time, "2025-01-05T10:10:00");
+/// assert!(stop.waiting_seconds > 0); // arrives before the window opens
+/// assert_eq!(stop.load_after, 0);
+/// ```
+pub fn build_manifest(plan: &VehicleRoutePlan, vehicle: &Vehicle) -> Manifest {
+    let iso = |offset: i64| plan.datetime_at(offset).format("%Y-%m-%dT%H:%M:%S").to_string();
+    let coords = |idx: usize| plan.get_location(idx).map(|l| [l.latitude, l.longitude]);
+
+    let loads = plan.load_profile(vehicle);
+    let mut prev_location = vehicle.home_location.index;
+    let mut prev_departure = vehicle.departure_time;
+    let mut stops = Vec::with_capacity(vehicle.visits.len());
+
+    for (pos, (timing, load_after)) in plan.calculate_route_times(vehicle).into_iter().zip(loads).enumerate() {
+        let visit = &plan.visits[timing.visit_idx];
+        let location = visit.location.index;
+        stops.push(ManifestStop {
+            sequence: pos + 1,
+            visit_id: format!("v{}", visit.index),
+            name: visit.name.clone(),
+            kind: visit.kind,
+            location: [visit.location.latitude, visit.location.longitude],
+            travel_time_seconds: timing.arrival - prev_departure,
+            distance_meters: plan.distance_meters(prev_location, location),
+            arrival_time: iso(timing.arrival),
+            waiting_seconds: timing.waiting(),
+            service_start_time: iso(timing.service_start),
+            service_end_time: iso(timing.departure),
+            time_window_start: iso(visit.min_start_time),
+            time_window_end: iso(visit.max_end_time),
+            demand: visit.demand,
+            load_after,
+        });
+        prev_location = location;
+        prev_departure = timing.departure;
+    }
+
+    Manifest {
+        vehicle_id: vehicle.id.to_string(),
+        vehicle_name: vehicle.name.clone(),
+        capacity: vehicle.capacity,
+        start_location: [vehicle.home_location.latitude, vehicle.home_location.longitude],
+        end_location: vehicle.end_location_index().and_then(coords),
+        departure_time: iso(vehicle.departure_time),
+        return_time: iso(plan.route_end_time(vehicle)),
+        start_load: plan.start_load(vehicle),
+        total_distance_meters: plan.total_distance_meters(vehicle),
+        total_driving_time_seconds: plan.total_driving_time(vehicle),
+        stops,
+    }
+}
+
+/// Builds manifests for all vehicles with stops, or only for `vehicle_id`.
+pub fn build_manifests(plan: &VehicleRoutePlan, vehicle_id: Option<usize>) -> Vec<Manifest> {
+    plan.vehicles
+        .iter()
+        .filter(|v| match vehicle_id {
+            Some(id) => v.id == id,
+            None => !v.visits.is_empty(),
+        })
+        .map(|v| build_manifest(plan, v))
+        .collect()
+}
+
+/// Print stylesheet: one sheet per vehicle, compact tables.
+const STYLE: &str = "\
+body { font-family: sans-serif; font-size: 11pt; margin: 1.5em; }
+section { page-break-after: always; }
+section:last-child { page-break-after: auto; }
+h1 { font-size: 16pt; margin-bottom: 0.2em; }
+.summary { color: #444; margin-bottom: 0.8em; }
+table { border-collapse: collapse; width: 100%; }
+th, td { border: 1px solid #999; padding: 3px 6px; text-align: left; }
+th { background: #eee; }
+td.num { text-align: right; }
+@media print { body { margin: 0; } }
+";
+
+/// Renders manifests as a standalone HTML page, one printed sheet each.
+///
+/// Times show as `HH:MM`; the date is in each sheet's summary line.
+pub fn render_html(title: &str, manifests: &[Manifest]) -> String {
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    let _ = writeln!(out, "<title>{}</title>", xml_escape(title));
+    let _ = writeln!(out, "<style>\n{}</style>\n</head>\n<body>", STYLE);
+
+    for manifest in manifests {
+        out.push_str("<section>\n");
+        let _ = writeln!(out, "<h1>{}</h1>", xml_escape(&manifest.vehicle_name));
+        let _ = writeln!(
+            out,
+            "<div class=\"summary\">{} &middot; depart {} &middot; return {} &middot; {} stops &middot; {:.1} km &middot; {} driving &middot; start load {}/{}</div>",
+            date(&manifest.departure_time),
+            clock(&manifest.departure_time),
+            clock(&manifest.return_time),
+            manifest.stops.len(),
+            manifest.total_distance_meters / 1000.0,
+            duration(manifest.total_driving_time_seconds),
+            manifest.start_load,
+            manifest.capacity
+        );
+        out.push_str(
+            "<table>\n<tr><th>#</th><th>Stop</th><th>Coordinates</th><th>Window</th><th>Arrival</th>\
+             <th>Wait</th><th>Service</th><th>Demand</th><th>Load after</th></tr>\n",
+        );
+        for stop in &manifest.stops {
+            let demand = match stop.kind {
+                VisitKind::Delivery => format!("&minus;{}", stop.demand),
+                VisitKind::Pickup => format!("+{}", stop.demand),
+                VisitKind::Reload => "reload".to_string(),
+            };
+            let _ = writeln!(
+                out,
+                "<tr><td class=\"num\">{}</td><td>{}</td><td>{:.5}, {:.5}</td><td>{}&ndash;{}</td><td>{}</td>\
+                 <td class=\"num\">{}</td><td>{}&ndash;{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>",
+                stop.sequence,
+                xml_escape(&stop.name),
+                stop.location[0],
+                stop.location[1],
+                clock(&stop.time_window_start),
+                clock(&stop.time_window_end),
+                clock(&stop.arrival_time),
+                duration(stop.waiting_seconds),
+                clock(&stop.service_start_time),
+                clock(&stop.service_end_time),
+                demand,
+                stop.load_after
+            );
+        }
+        out.push_str("</table>\n</section>\n");
+    }
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+/// Date part of an ISO datetime.
+fn date(iso: &str) -> &str {
+    iso.split('T').next().unwrap_or(iso)
+}
+
+/// `HH:MM` part of an ISO datetime.
+fn clock(iso: &str) -> &str {
+    iso.split('T').nth(1).and_then(|t| t.get(..5)).unwrap_or(iso)
+}
+
+/// Formats seconds as `H:MM`.
+fn duration(seconds: i64) -> String {
+    let minutes = (seconds.max(0) + 30) / 60;
+    format!("{}:{:02}", minutes / 60, minutes % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{Location, Visit};
+
+    fn sample_plan() -> VehicleRoutePlan {
+        let depot = Location::new(0, 39.95, -75.16);
+        let a = Location::new(1, 39.96, -75.17);
+        let b = Location::new(2, 39.97, -75.15);
+        let mut vehicle = Vehicle::new(0, "Alpha", 10, depot.clone()).with_departure_time(8 * 3600);
+        vehicle.visits = vec![0, 1, 2];
+        let visits = vec![
+            Visit::new(0, "Pickup <A>", a.clone()).with_demand(3).with_pickup_for(1),
+            Visit::new(1, "Delivery B", b.clone()).with_demand(3).with_delivery_for(0),
+            Visit::new(2, "Drop C", a.clone()).with_demand(2).with_service_duration(300),
+        ];
+        let idle = Vehicle::new(1, "Bravo", 10, depot.clone());
+        let mut plan = VehicleRoutePlan::new("test", vec![depot, a, b], visits, vec![vehicle, idle]);
+        plan.finalize();
+        plan
+    }
+
+    #[test]
+    fn test_manifest_stops() {
+        let plan = sample_plan();
+        let manifests = build_manifests(&plan, None);
+        assert_eq!(manifests.len(), 1); // idle vehicle skipped
+
+        let manifest = &manifests[0];
+        assert_eq!(manifest.start_load, 2);
+        assert_eq!(manifest.end_location, Some([39.95, -75.16]));
+        let loads: Vec<i32> = manifest.stops.iter().map(|s| s.load_after).collect();
+        assert_eq!(loads, vec![5, 2, 0]);
+        assert_eq!(manifest.stops[0].travel_time_seconds, plan.travel_time(0, 1));
+        assert_eq!(manifest.stops[2].distance_meters, plan.distance_meters(2, 1));
+        assert_eq!(manifest.stops[2].waiting_seconds, 0);
+
+        // Driving time adds up, including the way back
+        let driven: i64 = manifest.stops.iter().map(|s| s.travel_time_seconds).sum();
+        assert_eq!(driven + plan.travel_time(1, 0), manifest.total_driving_time_seconds);
+    }
+
+    #[test]
+    fn test_vehicle_filter_includes_empty_routes() {
+        let plan = sample_plan();
+        let manifests = build_manifests(&plan, Some(1));
+        assert_eq!(manifests.len(), 1);
+        assert!(manifests[0].stops.is_empty());
+    }
+
+    #[test]
+    fn test_render_html() {
+        let plan = sample_plan();
+        let html = render_html("test", &build_manifests(&plan, None));
+        assert!(html.contains("<h1>Alpha</h1>"));
+        assert!(html.contains("Pickup &lt;A&gt;"));
+        assert!(html.contains("<td class=\"num\">+3</td>"));
+        assert_eq!(html.matches("<section>").count(), 1);
+        assert_eq!(duration(3 * 3600 + 20 * 60 + 40), "3:21");
+        assert_eq!(clock("2025-01-05T08:05:00"), "08:05");
+    }
+}
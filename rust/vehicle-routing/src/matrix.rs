@@ -0,0 +1,529 @@
+//! Travel matrix providers.
+//!
+//! A [`MatrixProvider`] turns the plan's locations into travel time and
+//! distance matrices (and optionally road geometries). Plans can be solved
+//! against straight-line estimates, the local OSM road graph, a precomputed
+//! matrix file, or an OSRM/Valhalla-compatible HTTP table service. With a
+//! [`SpeedProfile`], providers also return one travel time matrix per
+//! time-of-day bucket.
+
+use crate::domain::Location;
+pub use crate::routing::Geometries;
+use crate::routing::{BoundingBox, RoadNetwork, RoutingError, TravelMatrices};
+use crate::traffic::{SpeedProfile, TimeDependentTimes};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use tracing::info;
+use utoipa::ToSchema;
+
+/// Future returned by [`MatrixProvider::fetch`].
+pub type ProviderFuture<'a> = Pin<Box<dyn Future<Output = Result<RoutingData, RoutingError>> + Send + 'a>>;
+
+/// Everything a provider knows about travel between locations.
+#[derive(Debug, Clone, Default)]
+pub struct RoutingData {
+    /// Travel times and distances, in location order.
+    pub matrices: TravelMatrices,
+    /// Road geometries for visualization (empty if the provider has none).
+    pub geometries: Geometries,
+    /// Travel times per time-of-day bucket, if a speed profile was given.
+    pub time_dependent: Option<TimeDependentTimes>,
+}
+
+/// Source of travel times and distances between locations.
+pub trait MatrixProvider: Send + Sync {
+    /// Short name used in logs.
+    fn name(&self) -> &'static str;
+
+    /// Computes matrices for `locations`, which all lie inside `bbox`.
+    fn fetch<'a>(&'a self, locations: &'a [Location], bbox: BoundingBox) -> ProviderFuture<'a>;
+
+    /// Computes matrices plus travel times for each bucket of `profile`.
+    ///
+    /// The default scales the static times by each bucket's default factor;
+    /// providers that know road classes override this.
+    fn fetch_with_profile<'a>(
+        &'a self,
+        locations: &'a [Location],
+        bbox: BoundingBox,
+        profile: &'a SpeedProfile,
+    ) -> ProviderFuture<'a> {
+        Box::pin(async move {
+            let mut data = self.fetch(locations, bbox).await?;
+            data.time_dependent = Some(TimeDependentTimes::scaled(&data.matrices.times, profile));
+            Ok(data)
+        })
+    }
+}
+
+/// Straight-line distances at a constant average speed.
+///
+/// # Examples
+///
+/// ```
+/// use vehicle_routing::domain::Location;
+/// use vehicle_routing::matrix::haversine_matrices;
+///
+/// let locations = vec![Location::new(0, 39.95, -75.16), Location::new(1, 39.96, -75.17)];
+/// let matrices = haversine_matrices(&locations);
+/// assert_eq!(matrices.times[0][1], locations[0].travel_time_seconds(&locations[1]));
+/// assert_eq!(matrices.distances[1][1], 0);
+/// ```
+pub fn haversine_matrices(locations: &[Location]) -> TravelMatrices {
+    let n = locations.len();
+    let mut times = vec![vec![0; n]; n];
+    let mut distances = vec![vec![0; n]; n];
+
+    for i in 0..n {
+        for j in 0..n {
+            if i != j {
+                times[i][j] = locations[i].travel_time_seconds(&locations[j]);
+                distances[i][j] = locations[i].distance_meters(&locations[j]).round() as i64;
+            }
+        }
+    }
+
+    TravelMatrices {
+        times,
+        distances,
+        unsnapped: Vec::new(),
+    }
+}
+
+/// Straight-line provider (no network access, no geometries).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HaversineProvider;
+
+impl MatrixProvider for HaversineProvider {
+    fn name(&self) -> &'static str {
+        "haversine"
+    }
+
+    fn fetch<'a>(&'a self, locations: &'a [Location], _bbox: BoundingBox) -> ProviderFuture<'a> {
+        Box::pin(async move {
+            Ok(RoutingData {
+                matrices: haversine_matrices(locations),
+                geometries: Geometries::new(),
+                time_dependent: None,
+            })
+        })
+    }
+}
+
+/// Local OSM road graph (Overpass download or extract, see
+/// [`network_source`](crate::routing::network_source)).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OsmProvider;
+
+impl MatrixProvider for OsmProvider {
+    fn name(&self) -> &'static str {
+        "osm"
+    }
+
+    fn fetch<'a>(&'a self, locations: &'a [Location], bbox: BoundingBox) -> ProviderFuture<'a> {
+        Box::pin(async move {
+            let network = RoadNetwork::load_or_fetch(&bbox.expand(0.05)).await?;
+            let coords = coords(locations);
+            let (matrices, geometries) = network.compute_routes(&coords);
+            Ok(RoutingData {
+                matrices,
+                geometries,
+                time_dependent: None,
+            })
+        })
+    }
+
+    /// Runs one all-pairs search per bucket with speeds scaled by road class.
+    fn fetch_with_profile<'a>(
+        &'a self,
+        locations: &'a [Location],
+        bbox: BoundingBox,
+        profile: &'a SpeedProfile,
+    ) -> ProviderFuture<'a> {
+        Box::pin(async move {
+            let network = RoadNetwork::load_or_fetch(&bbox.expand(0.05)).await?;
+            let coords = coords(locations);
+            let (matrices, geometries) = network.compute_routes(&coords);
+            let times = (0..profile.buckets.len())
+                .map(|b| {
+                    network
+                        .compute_matrices_with_speed_factors(&coords, &profile.class_factors(b))
+                        .times
+                })
+                .collect();
+            Ok(RoutingData {
+                matrices,
+                geometries,
+                time_dependent: Some(TimeDependentTimes {
+                    bucket_starts: profile.bucket_starts(),
+                    times,
+                }),
+            })
+        })
+    }
+}
+
+/// Precomputed matrices read from a JSON file.
+///
+/// The file holds `{"times": [[...]], "distances": [[...]]}` in location
+/// order (depots first, then visits). Times are seconds, distances meters;
+/// `distances` may be omitted, in which case straight-line distances are used.
+#[derive(Debug, Clone)]
+pub struct MatrixFileProvider {
+    path: PathBuf,
+}
+
+impl MatrixFileProvider {
+    /// Creates a provider reading the given file.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct MatrixFile {
+    times: Vec<Vec<i64>>,
+    #[serde(default)]
+    distances: Option<Vec<Vec<i64>>>,
+}
+
+impl MatrixProvider for MatrixFileProvider {
+    fn name(&self) -> &'static str {
+        "matrix-file"
+    }
+
+    fn fetch<'a>(&'a self, locations: &'a [Location], _bbox: BoundingBox) -> ProviderFuture<'a> {
+        Box::pin(async move {
+            let data = tokio::fs::read_to_string(&self.path).await?;
+            let file: MatrixFile =
+                serde_json::from_str(&data).map_err(|e| RoutingError::Parse(e.to_string()))?;
+
+            let n = locations.len();
+            check_square(&file.times, n, "times")?;
+            let distances = match file.distances {
+                Some(distances) => {
+                    check_square(&distances, n, "distances")?;
+                    distances
+                }
+                None => haversine_matrices(locations).distances,
+            };
+
+            info!("Read {}x{} matrix from {:?}", n, n, self.path);
+            Ok(RoutingData {
+                matrices: TravelMatrices {
+                    times: file.times,
+                    distances,
+                    unsnapped: Vec::new(),
+                },
+                geometries: Geometries::new(),
+                time_dependent: None,
+            })
+        })
+    }
+}
+
+/// Flavor of HTTP table API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TableApi {
+    /// OSRM `GET /table/v1/{profile}/{coordinates}`.
+    #[default]
+    Osrm,
+    /// Valhalla `POST /sources_to_targets`.
+    Valhalla,
+}
+
+/// Matrices from an OSRM- or Valhalla-compatible HTTP table service.
+///
+/// Pairs the service cannot route fall back to straight-line estimates.
+#[derive(Debug, Clone)]
+pub struct HttpTableProvider {
+    base_url: String,
+    api: TableApi,
+    profile: String,
+}
+
+impl HttpTableProvider {
+    /// Creates a provider for the service at `base_url`.
+    ///
+    /// `profile` is the OSRM profile (default "driving") or Valhalla costing
+    /// model (default "auto").
+    pub fn new(base_url: impl Into<String>, api: TableApi, profile: Option<String>) -> Self {
+        let profile = profile.unwrap_or_else(|| match api {
+            TableApi::Osrm => "driving".to_string(),
+            TableApi::Valhalla => "auto".to_string(),
+        });
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            api,
+            profile,
+        }
+    }
+
+    async fn fetch_osrm(&self, client: &reqwest::Client, locations: &[Location]) -> Result<TravelMatrices, RoutingError> {
+        let coordinates: Vec<String> = locations
+            .iter()
+            .map(|l| format!("{},{}", l.longitude, l.latitude))
+            .collect();
+        let url = format!(
+            "{}/table/v1/{}/{}?annotations=duration,distance",
+            self.base_url,
+            self.profile,
+            coordinates.join(";")
+        );
+
+        let response: OsrmTableResponse = client
+            .get(&url)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| RoutingError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| RoutingError::Parse(e.to_string()))?;
+
+        if response.code != "Ok" {
+            return Err(RoutingError::Network(format!(
+                "table service returned {}",
+                response.code
+            )));
+        }
+
+        let n = locations.len();
+        let durations = response.durations.unwrap_or_default();
+        let distances = response.distances.unwrap_or_default();
+        check_square(&durations, n, "durations")?;
+
+        Ok(fill_matrices(locations, |i, j| {
+            let time = durations[i][j]?;
+            let distance = distances.get(i).and_then(|row| row.get(j)).copied().flatten();
+            Some((time, distance))
+        }))
+    }
+
+    async fn fetch_valhalla(&self, client: &reqwest::Client, locations: &[Location]) -> Result<TravelMatrices, RoutingError> {
+        let points: Vec<serde_json::Value> = locations
+            .iter()
+            .map(|l| serde_json::json!({ "lat": l.latitude, "lon": l.longitude }))
+            .collect();
+        let body = serde_json::json!({
+            "sources": points,
+            "targets": points,
+            "costing": self.profile,
+        });
+
+        let response: ValhallaMatrixResponse = client
+            .post(format!("{}/sources_to_targets", self.base_url))
+            .json(&body)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| RoutingError::Network(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| RoutingError::Parse(e.to_string()))?;
+
+        let n = locations.len();
+        let rows = response.sources_to_targets;
+        check_square(&rows, n, "sources_to_targets")?;
+
+        // Valhalla reports distances in kilometers
+        Ok(fill_matrices(locations, |i, j| {
+            let cell = &rows[i][j];
+            Some((cell.time?, cell.distance.map(|km| km * 1000.0)))
+        }))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct OsrmTableResponse {
+    code: String,
+    durations: Option<Vec<Vec<Option<f64>>>>,
+    distances: Option<Vec<Vec<Option<f64>>>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ValhallaMatrixResponse {
+    sources_to_targets: Vec<Vec<ValhallaCell>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ValhallaCell {
+    time: Option<f64>,
+    distance: Option<f64>,
+}
+
+impl MatrixProvider for HttpTableProvider {
+    fn name(&self) -> &'static str {
+        match self.api {
+            TableApi::Osrm => "osrm",
+            TableApi::Valhalla => "valhalla",
+        }
+    }
+
+    fn fetch<'a>(&'a self, locations: &'a [Location], _bbox: BoundingBox) -> ProviderFuture<'a> {
+        Box::pin(async move {
+            let client = reqwest::Client::builder()
+                .connect_timeout(std::time::Duration::from_secs(30))
+                .timeout(std::time::Duration::from_secs(180))
+                .user_agent("SolverForge/0.4.0")
+                .build()
+                .map_err(|e| RoutingError::Network(e.to_string()))?;
+
+            let matrices = match self.api {
+                TableApi::Osrm => self.fetch_osrm(&client, locations).await?,
+                TableApi::Valhalla => self.fetch_valhalla(&client, locations).await?,
+            };
+
+            Ok(RoutingData {
+                matrices,
+                geometries: Geometries::new(),
+                time_dependent: None,
+            })
+        })
+    }
+}
+
+/// Builds matrices from `(seconds, meters)` per pair, falling back to
+/// straight-line estimates where the pair is unknown.
+fn fill_matrices<F>(locations: &[Location], pair: F) -> TravelMatrices
+where
+    F: Fn(usize, usize) -> Option<(f64, Option<f64>)>,
+{
+    let mut matrices = haversine_matrices(locations);
+    let n = locations.len();
+    for i in 0..n {
+        for j in 0..n {
+            if i == j {
+                continue;
+            }
+            if let Some((time, distance)) = pair(i, j) {
+                matrices.times[i][j] = time.round() as i64;
+                if let Some(distance) = distance {
+                    matrices.distances[i][j] = distance.round() as i64;
+                }
+            }
+        }
+    }
+    matrices
+}
+
+/// Rejects matrices that are not `n` x `n`.
+fn check_square<T>(matrix: &[Vec<T>], n: usize, what: &str) -> Result<(), RoutingError> {
+    if matrix.len() != n || matrix.iter().any(|row| row.len() != n) {
+        return Err(RoutingError::Parse(format!(
+            "{} matrix must be {}x{} to match the plan's locations",
+            what, n, n
+        )));
+    }
+    Ok(())
+}
+
+fn coords(locations: &[Location]) -> Vec<(f64, f64)> {
+    locations.iter().map(|l| (l.latitude, l.longitude)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::{get, post};
+    use axum::{Json, Router};
+
+    fn locations() -> Vec<Location> {
+        vec![
+            Location::new(0, 39.95, -75.16),
+            Location::new(1, 39.96, -75.17),
+            Location::new(2, 39.94, -75.15),
+        ]
+    }
+
+    fn bbox() -> BoundingBox {
+        BoundingBox::new(39.94, -75.17, 39.96, -75.15)
+    }
+
+    /// Serves `router` on an ephemeral local port and returns its base URL.
+    async fn serve(router: Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, router).await.unwrap();
+        });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_haversine_provider() {
+        let locs = locations();
+        let data = HaversineProvider.fetch(&locs, bbox()).await.unwrap();
+        assert_eq!(data.matrices.times, haversine_matrices(&locs).times);
+        assert!(data.geometries.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_matrix_file_provider() {
+        let path = std::env::temp_dir().join(format!("vrp-matrix-{}.json", std::process::id()));
+        std::fs::write(&path, r#"{"times": [[0, 10, 20], [11, 0, 30], [21, 31, 0]]}"#).unwrap();
+
+        let locs = locations();
+        let data = MatrixFileProvider::new(&path).fetch(&locs, bbox()).await.unwrap();
+        assert_eq!(data.matrices.times[1][2], 30);
+        assert_eq!(data.matrices.distances, haversine_matrices(&locs).distances);
+
+        // Wrong dimensions are rejected
+        let data = MatrixFileProvider::new(&path).fetch(&locs[..2], bbox()).await;
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(data, Err(RoutingError::Parse(_))));
+    }
+
+    #[tokio::test]
+    async fn test_osrm_table_provider() {
+        let router = Router::new().route(
+            "/table/v1/driving/{coords}",
+            get(|| async {
+                Json(serde_json::json!({
+                    "code": "Ok",
+                    "durations": [[0, 100, 200], [110, 0, null], [210, 310, 0]],
+                    "distances": [[0, 1000, 2000], [1100, 0, null], [2100, 3100, 0]],
+                }))
+            }),
+        );
+        let base_url = serve(router).await;
+
+        let locs = locations();
+        let provider = HttpTableProvider::new(base_url, TableApi::Osrm, None);
+        let data = provider.fetch(&locs, bbox()).await.unwrap();
+        assert_eq!(data.matrices.times[0][1], 100);
+        assert_eq!(data.matrices.distances[2][1], 3100);
+
+        // Unroutable pair falls back to straight line
+        assert_eq!(data.matrices.times[1][2], locs[1].travel_time_seconds(&locs[2]));
+    }
+
+    #[tokio::test]
+    async fn test_valhalla_table_provider() {
+        let router = Router::new().route(
+            "/sources_to_targets",
+            post(|Json(body): Json<serde_json::Value>| async move {
+                assert_eq!(body["costing"], "auto");
+                let n = body["sources"].as_array().unwrap().len();
+                let rows: Vec<Vec<serde_json::Value>> = (0..n)
+                    .map(|i| {
+                        (0..n)
+                            .map(|j| serde_json::json!({ "time": (i * 10 + j) as f64, "distance": 1.5 }))
+                            .collect()
+                    })
+                    .collect();
+                Json(serde_json::json!({ "sources_to_targets": rows }))
+            }),
+        );
+        let base_url = serve(router).await;
+
+        let locs = locations();
+        let provider = HttpTableProvider::new(format!("{}/", base_url), TableApi::Valhalla, None);
+        let data = provider.fetch(&locs, bbox()).await.unwrap();
+        assert_eq!(data.matrices.times[2][1], 21);
+        assert_eq!(data.matrices.distances[0][2], 1500);
+    }
+}
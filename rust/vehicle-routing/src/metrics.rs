@@ -0,0 +1,321 @@
+//! Prometheus metrics.
+//!
+//! Process-wide counters and histograms live in a global [`Metrics`]
+//! registry, fed by the solver, the road network cache, matrix providers
+//! and an HTTP middleware. Per-job gauges are read from the
+//! [`SolverService`] when scraped. [`render`] writes everything in the
+//! Prometheus text exposition format.
+
+use crate::solver::{SolverService, SolverStatus};
+use axum::extract::{MatchedPath, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use parking_lot::Mutex;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// Content type of the text exposition format.
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Upper bounds of the HTTP latency buckets, in seconds.
+const HTTP_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Upper bounds of the matrix computation buckets, in seconds.
+const MATRIX_BUCKETS: &[f64] = &[0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0, 30.0, 60.0, 300.0];
+
+/// Where a road network lookup was answered from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheLookup {
+    /// In-memory cache hit.
+    Memory,
+    /// File cache hit.
+    Disk,
+    /// Downloaded or read from the extract.
+    Miss,
+}
+
+/// Process-wide metrics.
+#[derive(Default)]
+pub struct Metrics {
+    solves_started: AtomicU64,
+    solves_completed: AtomicU64,
+    moves_evaluated: AtomicU64,
+    steps_accepted: AtomicU64,
+    cache_memory_hits: AtomicU64,
+    cache_disk_hits: AtomicU64,
+    cache_misses: AtomicU64,
+    /// Matrix computation time per provider.
+    matrix: Mutex<BTreeMap<&'static str, Histogram>>,
+    /// Request latency per method, route and status.
+    http: Mutex<BTreeMap<(String, String, u16), Histogram>>,
+}
+
+/// The global registry.
+pub fn global() -> &'static Metrics {
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+    METRICS.get_or_init(Metrics::default)
+}
+
+impl Metrics {
+    /// Counts a job that started solving.
+    pub fn record_solve_started(&self) {
+        self.solves_started.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts a job that stopped solving.
+    pub fn record_solve_completed(&self) {
+        self.solves_completed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Adds solver progress since the last call for the same job.
+    pub fn record_progress(&self, moves_evaluated: u64, steps_accepted: u64) {
+        self.moves_evaluated.fetch_add(moves_evaluated, Ordering::Relaxed);
+        self.steps_accepted.fetch_add(steps_accepted, Ordering::Relaxed);
+    }
+
+    /// Counts a road network lookup.
+    pub fn record_cache_lookup(&self, lookup: CacheLookup) {
+        let counter = match lookup {
+            CacheLookup::Memory => &self.cache_memory_hits,
+            CacheLookup::Disk => &self.cache_disk_hits,
+            CacheLookup::Miss => &self.cache_misses,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records how long a provider took to compute travel matrices.
+    pub fn record_matrix(&self, provider: &'static str, duration: Duration) {
+        self.matrix
+            .lock()
+            .entry(provider)
+            .or_insert_with(|| Histogram::new(MATRIX_BUCKETS))
+            .observe(duration.as_secs_f64());
+    }
+
+    /// Records the latency of an HTTP request.
+    pub fn record_http(&self, method: &str, route: &str, status: u16, duration: Duration) {
+        self.http
+            .lock()
+            .entry((method.to_string(), route.to_string(), status))
+            .or_insert_with(|| Histogram::new(HTTP_BUCKETS))
+            .observe(duration.as_secs_f64());
+    }
+
+    fn write(&self, out: &mut String) {
+        let counter = |out: &mut String, name: &str, help: &str, value: &AtomicU64| {
+            header(out, name, help, "counter");
+            let _ = writeln!(out, "{} {}", name, value.load(Ordering::Relaxed));
+        };
+        counter(out, "vrp_solves_started_total", "Jobs that started solving.", &self.solves_started);
+        counter(out, "vrp_solves_completed_total", "Jobs that stopped solving.", &self.solves_completed);
+        counter(out, "vrp_moves_evaluated_total", "Moves evaluated by all solvers.", &self.moves_evaluated);
+        counter(out, "vrp_steps_accepted_total", "Steps accepted by all solvers.", &self.steps_accepted);
+
+        header(out, "vrp_road_network_lookups_total", "Road network lookups by result.", "counter");
+        for (result, value) in [
+            ("memory_hit", &self.cache_memory_hits),
+            ("disk_hit", &self.cache_disk_hits),
+            ("miss", &self.cache_misses),
+        ] {
+            let _ = writeln!(
+                out,
+                "vrp_road_network_lookups_total{{result=\"{}\"}} {}",
+                result,
+                value.load(Ordering::Relaxed)
+            );
+        }
+
+        header(out, "vrp_matrix_computation_seconds", "Travel matrix computation time.", "histogram");
+        for (provider, histogram) in self.matrix.lock().iter() {
+            histogram.write(out, "vrp_matrix_computation_seconds", &format!("provider=\"{}\"", escape(provider)));
+        }
+
+        header(out, "vrp_http_request_duration_seconds", "HTTP request latency.", "histogram");
+        for ((method, route, status), histogram) in self.http.lock().iter() {
+            let labels = format!(
+                "method=\"{}\",route=\"{}\",status=\"{}\"",
+                escape(method),
+                escape(route),
+                status
+            );
+            histogram.write(out, "vrp_http_request_duration_seconds", &labels);
+        }
+    }
+}
+
+/// Cumulative histogram with fixed buckets.
+#[derive(Debug, Clone)]
+struct Histogram {
+    bounds: &'static [f64],
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            counts: vec![0; bounds.len()],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    fn observe(&mut self, value: f64) {
+        for (bound, count) in self.bounds.iter().zip(&mut self.counts) {
+            if value <= *bound {
+                *count += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+
+    fn write(&self, out: &mut String, name: &str, labels: &str) {
+        for (bound, count) in self.bounds.iter().zip(&self.counts) {
+            let _ = writeln!(out, "{}_bucket{{{},le=\"{}\"}} {}", name, labels, bound, count);
+        }
+        let _ = writeln!(out, "{}_bucket{{{},le=\"+Inf\"}} {}", name, labels, self.count);
+        let _ = writeln!(out, "{}_sum{{{}}} {}", name, labels, self.sum);
+        let _ = writeln!(out, "{}_count{{{}}} {}", name, labels, self.count);
+    }
+}
+
+/// Renders the global metrics and the per-job gauges of `service`.
+///
+/// # Examples
+///
+/// ```
+/// use vehicle_routing::demo_data::generate_philadelphia;
+/// use vehicle_routing::metrics::render;
+/// use vehicle_routing::solver::SolverService;
+///
+/// let service = SolverService::new();
+/// service.create_job("job-1".to_string(), generate_philadelphia());
+///
+/// let text = render(&service);
+/// assert!(text.contains("vrp_jobs{status=\"NOT_SOLVING\"} 1"));
+/// assert!(text.contains("vrp_job_steps_accepted{job=\"job-1\"} 0"));
+/// ```
+pub fn render(service: &SolverService) -> String {
+    let mut out = String::new();
+    global().write(&mut out);
+
+    let mut ids = service.list_jobs();
+    ids.sort();
+    let jobs: Vec<_> = ids
+        .iter()
+        .filter_map(|id| service.get_job(id).map(|job| (id, job)))
+        .collect();
+
+    header(&mut out, "vrp_jobs", "Jobs by solver status.", "gauge");
+    for status in [SolverStatus::NotSolving, SolverStatus::SolvingScheduled, SolverStatus::Solving] {
+        let count = jobs.iter().filter(|(_, job)| job.read().status == status).count();
+        let _ = writeln!(out, "vrp_jobs{{status=\"{}\"}} {}", status.as_str(), count);
+    }
+
+    type Gauge = (&'static str, &'static str, fn(&crate::solver::SolveJob) -> f64);
+    let gauges: [Gauge; 5] = [
+        ("vrp_job_moves_per_second", "Moves evaluated per second of solving.", |job| {
+            job.stats.moves_per_second()
+        }),
+        ("vrp_job_steps_accepted", "Steps accepted by the job's solver.", |job| {
+            job.stats.steps_accepted as f64
+        }),
+        ("vrp_job_moves_evaluated", "Moves evaluated by the job's solver.", |job| {
+            job.stats.moves_evaluated as f64
+        }),
+        ("vrp_job_best_hard_score", "Hard part of the job's best score.", |job| {
+            job.stats.best_score.or(job.plan.score).map_or(0.0, |s| s.hard() as f64)
+        }),
+        ("vrp_job_best_soft_score", "Soft part of the job's best score.", |job| {
+            job.stats.best_score.or(job.plan.score).map_or(0.0, |s| s.soft() as f64)
+        }),
+    ];
+    for (name, help, value) in gauges {
+        header(&mut out, name, help, "gauge");
+        for (id, job) in &jobs {
+            let _ = writeln!(out, "{}{{job=\"{}\"}} {}", name, escape(id), value(&job.read()));
+        }
+    }
+    out
+}
+
+/// Middleware recording the latency of routed requests, labelled with the
+/// route template so ids don't multiply the series.
+pub async fn track_http(request: Request, next: Next) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or_else(|| "unmatched".to_string(), |p| p.as_str().to_string());
+    let method = request.method().to_string();
+    let start = Instant::now();
+    let response = next.run(request).await;
+    global().record_http(&method, &route, response.status().as_u16(), start.elapsed());
+    response
+}
+
+fn header(out: &mut String, name: &str, help: &str, kind: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+/// Escapes a label value.
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_is_cumulative() {
+        let mut histogram = Histogram::new(&[0.1, 1.0]);
+        histogram.observe(0.0625);
+        histogram.observe(0.5);
+        histogram.observe(4.0);
+
+        let mut out = String::new();
+        histogram.write(&mut out, "latency", "route=\"/x\"");
+        assert!(out.contains("latency_bucket{route=\"/x\",le=\"0.1\"} 1\n"));
+        assert!(out.contains("latency_bucket{route=\"/x\",le=\"1\"} 2\n"));
+        assert!(out.contains("latency_bucket{route=\"/x\",le=\"+Inf\"} 3\n"));
+        assert!(out.contains("latency_sum{route=\"/x\"} 4.5625\n"));
+        assert!(out.contains("latency_count{route=\"/x\"} 3\n"));
+    }
+
+    #[test]
+    fn test_labels_escaped() {
+        let metrics = Metrics::default();
+        metrics.record_http("GET", "/a\"b", 200, Duration::from_millis(3));
+        let mut out = String::new();
+        metrics.write(&mut out);
+        assert!(out.contains("route=\"/a\\\"b\",status=\"200\",le=\"0.005\"} 1"));
+    }
+
+    #[test]
+    fn test_every_sample_has_a_type() {
+        let metrics = Metrics::default();
+        metrics.record_cache_lookup(CacheLookup::Disk);
+        metrics.record_matrix("osm", Duration::from_secs(2));
+        let mut out = String::new();
+        metrics.write(&mut out);
+
+        assert!(out.contains("vrp_road_network_lookups_total{result=\"disk_hit\"} 1"));
+        assert!(out.contains("vrp_matrix_computation_seconds_count{provider=\"osm\"} 1"));
+        for line in out.lines().filter(|l| !l.starts_with('#')) {
+            let name = line.split(['{', ' ']).next().unwrap();
+            let family = ["_bucket", "_sum", "_count"]
+                .iter()
+                .find_map(|suffix| name.strip_suffix(suffix))
+                .filter(|base| out.contains(&format!("# TYPE {} histogram", base)))
+                .unwrap_or(name);
+            assert!(out.contains(&format!("# TYPE {} ", family)), "{}", line);
+        }
+    }
+}
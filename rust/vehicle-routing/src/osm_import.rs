@@ -0,0 +1,798 @@
+//! Offline import of OpenStreetMap extracts.
+//!
+//! Reads `.osm.pbf` and `.osm` XML files into the same element list the
+//! Overpass API returns, so [`RoadNetwork`](crate::routing::RoadNetwork)
+//! builds identical graphs from either source. Only nodes inside the
+//! bounding box, ways tagged with a routable highway type and turn
+//! restriction relations are kept.
+//!
+//! The PBF reader understands the subset of the format needed for routing:
+//! raw and zlib-compressed blobs, plain and dense nodes, ways and relations.
+//! See: <https://wiki.openstreetmap.org/wiki/PBF_Format>
+
+use crate::routing::{BoundingBox, OsmElement, OsmMember, OsmTags, OverpassResponse, RoutingError, ROUTABLE_HIGHWAYS};
+use flate2::read::ZlibDecoder;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufReader, ErrorKind, Read};
+use std::path::Path;
+
+/// Reads an OSM extract, clipped to `bbox`.
+///
+/// The format is chosen by file extension: `.pbf` for protocol buffers,
+/// `.osm` or `.xml` for XML.
+pub(crate) fn read_extract(path: &Path, bbox: &BoundingBox) -> Result<OverpassResponse, RoutingError> {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+
+    let mut extract = Extract::new(*bbox);
+    if name.ends_with(".pbf") {
+        read_pbf(path, &mut extract)?;
+    } else if name.ends_with(".osm") || name.ends_with(".xml") {
+        read_xml(path, &mut extract)?;
+    } else {
+        return Err(RoutingError::Parse(format!(
+            "unsupported OSM extract {:?} (expected .osm.pbf or .osm)",
+            path
+        )));
+    }
+
+    Ok(extract.finish())
+}
+
+/// Elements collected while reading an extract.
+struct Extract {
+    bbox: BoundingBox,
+    nodes: Vec<OsmElement>,
+    ways: Vec<OsmElement>,
+    relations: Vec<OsmElement>,
+}
+
+impl Extract {
+    fn new(bbox: BoundingBox) -> Self {
+        Self {
+            bbox,
+            nodes: Vec::new(),
+            ways: Vec::new(),
+            relations: Vec::new(),
+        }
+    }
+
+    fn add_node(&mut self, id: i64, lat: f64, lon: f64) {
+        if self.bbox.contains(lat, lon) {
+            self.nodes.push(OsmElement {
+                elem_type: "node".to_string(),
+                id,
+                lat: Some(lat),
+                lon: Some(lon),
+                nodes: None,
+                members: None,
+                tags: None,
+            });
+        }
+    }
+
+    fn add_way(&mut self, id: i64, refs: Vec<i64>, tags: OsmTags) {
+        let routable = tags
+            .highway
+            .as_deref()
+            .is_some_and(|h| ROUTABLE_HIGHWAYS.contains(&h));
+        if routable && refs.len() >= 2 {
+            self.ways.push(OsmElement {
+                elem_type: "way".to_string(),
+                id,
+                lat: None,
+                lon: None,
+                nodes: Some(refs),
+                members: None,
+                tags: Some(tags),
+            });
+        }
+    }
+
+    fn add_relation(&mut self, id: i64, members: Vec<OsmMember>, tags: OsmTags) {
+        if tags.relation_type.as_deref() == Some("restriction") {
+            self.relations.push(OsmElement {
+                elem_type: "relation".to_string(),
+                id,
+                lat: None,
+                lon: None,
+                nodes: None,
+                members: Some(members),
+                tags: Some(tags),
+            });
+        }
+    }
+
+    /// Drops ways that never enter the bounding box and restrictions whose
+    /// members aren't all kept.
+    fn finish(mut self) -> OverpassResponse {
+        let kept: HashSet<i64> = self.nodes.iter().map(|n| n.id).collect();
+        self.ways.retain(|w| {
+            w.nodes
+                .as_ref()
+                .is_some_and(|refs| refs.iter().any(|id| kept.contains(id)))
+        });
+
+        let kept_ways: HashSet<i64> = self.ways.iter().map(|w| w.id).collect();
+        self.relations.retain(|r| {
+            r.members.as_ref().is_some_and(|members| {
+                members.iter().all(|m| match m.member_type.as_str() {
+                    "node" => kept.contains(&m.member_ref),
+                    "way" => kept_ways.contains(&m.member_ref),
+                    _ => false,
+                })
+            })
+        });
+
+        let mut elements = self.nodes;
+        elements.append(&mut self.ways);
+        elements.append(&mut self.relations);
+        OverpassResponse { elements }
+    }
+}
+
+/// Sets the routing-relevant tag on `tags`.
+fn set_tag(tags: &mut OsmTags, key: &str, value: &str) {
+    match key {
+        "highway" => tags.highway = Some(value.to_string()),
+        "oneway" => tags.oneway = Some(value.to_string()),
+        "junction" => tags.junction = Some(value.to_string()),
+        "maxspeed" => tags.maxspeed = Some(value.to_string()),
+        "access" => tags.access = Some(value.to_string()),
+        "vehicle" => tags.vehicle = Some(value.to_string()),
+        "motor_vehicle" => tags.motor_vehicle = Some(value.to_string()),
+        "motorcar" => tags.motorcar = Some(value.to_string()),
+        "type" => tags.relation_type = Some(value.to_string()),
+        "restriction" => tags.restriction = Some(value.to_string()),
+        _ => {}
+    }
+}
+
+// ============================================================================
+// XML
+// ============================================================================
+
+fn read_xml(path: &Path, extract: &mut Extract) -> Result<(), RoutingError> {
+    let mut reader = Reader::from_reader(BufReader::new(File::open(path)?));
+    let mut buf = Vec::new();
+    // Way being read: (id, node refs, tags)
+    let mut way: Option<(i64, Vec<i64>, OsmTags)> = None;
+    // Relation being read: (id, members, tags)
+    let mut relation: Option<(i64, Vec<OsmMember>, OsmTags)> = None;
+
+    loop {
+        let event = reader
+            .read_event_into(&mut buf)
+            .map_err(|e| RoutingError::Parse(e.to_string()))?;
+        match event {
+            Event::Start(ref e) | Event::Empty(ref e) => {
+                let is_empty = matches!(event, Event::Empty(_));
+                match e.name().as_ref() {
+                    b"node" => {
+                        let id = parse_attr::<i64>(e, b"id");
+                        let lat = parse_attr::<f64>(e, b"lat");
+                        let lon = parse_attr::<f64>(e, b"lon");
+                        if let (Some(id), Some(lat), Some(lon)) = (id, lat, lon) {
+                            extract.add_node(id, lat, lon);
+                        }
+                    }
+                    b"way" if !is_empty => {
+                        if let Some(id) = parse_attr::<i64>(e, b"id") {
+                            way = Some((id, Vec::new(), OsmTags::default()));
+                        }
+                    }
+                    b"relation" if !is_empty => {
+                        if let Some(id) = parse_attr::<i64>(e, b"id") {
+                            relation = Some((id, Vec::new(), OsmTags::default()));
+                        }
+                    }
+                    b"member" => {
+                        let member = (attr(e, b"type"), parse_attr::<i64>(e, b"ref"), attr(e, b"role"));
+                        if let (Some((_, members, _)), (Some(member_type), Some(member_ref), Some(role))) =
+                            (relation.as_mut(), member)
+                        {
+                            members.push(OsmMember {
+                                member_type,
+                                member_ref,
+                                role,
+                            });
+                        }
+                    }
+                    b"nd" => {
+                        if let (Some((_, refs, _)), Some(id)) = (way.as_mut(), parse_attr::<i64>(e, b"ref")) {
+                            refs.push(id);
+                        }
+                    }
+                    b"tag" => {
+                        let tags = way
+                            .as_mut()
+                            .map(|(_, _, tags)| tags)
+                            .or(relation.as_mut().map(|(_, _, tags)| tags));
+                        if let (Some(tags), Some(k), Some(v)) = (tags, attr(e, b"k"), attr(e, b"v")) {
+                            set_tag(tags, &k, &v);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Event::End(ref e) if e.name().as_ref() == b"way" => {
+                if let Some((id, refs, tags)) = way.take() {
+                    extract.add_way(id, refs, tags);
+                }
+            }
+            Event::End(ref e) if e.name().as_ref() == b"relation" => {
+                if let Some((id, members, tags)) = relation.take() {
+                    extract.add_relation(id, members, tags);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    Ok(())
+}
+
+/// Returns the unescaped value of an attribute.
+fn attr(e: &BytesStart, name: &[u8]) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|a| a.key.as_ref() == name)
+        .and_then(|a| a.unescape_value().ok().map(|v| v.into_owned()))
+}
+
+fn parse_attr<T: std::str::FromStr>(e: &BytesStart, name: &[u8]) -> Option<T> {
+    attr(e, name).and_then(|v| v.parse().ok())
+}
+
+// ============================================================================
+// PBF
+// ============================================================================
+
+fn read_pbf(path: &Path, extract: &mut Extract) -> Result<(), RoutingError> {
+    let mut file = BufReader::new(File::open(path)?);
+    let mut len_buf = [0u8; 4];
+
+    loop {
+        match file.read_exact(&mut len_buf) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e.into()),
+        }
+
+        let header = read_exact_vec(&mut file, u32::from_be_bytes(len_buf) as usize)?;
+        let (blob_type, data_size) = parse_blob_header(&header)?;
+        let blob = read_exact_vec(&mut file, data_size)?;
+
+        // OSMHeader blocks only carry metadata
+        if blob_type == "OSMData" {
+            let block = decode_blob(&blob)?;
+            parse_primitive_block(&block, extract)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn read_exact_vec(reader: &mut impl Read, len: usize) -> Result<Vec<u8>, RoutingError> {
+    let mut buf = vec![0u8; len];
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+/// Returns `(type, datasize)` of a `BlobHeader`.
+fn parse_blob_header(buf: &[u8]) -> Result<(String, usize), RoutingError> {
+    let mut blob_type = String::new();
+    let mut data_size = 0;
+    let mut reader = ProtoReader::new(buf);
+    while let Some((field, value)) = reader.next_field()? {
+        match (field, value) {
+            (1, Value::Bytes(b)) => blob_type = String::from_utf8_lossy(b).into_owned(),
+            (3, Value::Varint(v)) => data_size = v as usize,
+            _ => {}
+        }
+    }
+    Ok((blob_type, data_size))
+}
+
+/// Returns the uncompressed contents of a `Blob`.
+fn decode_blob(buf: &[u8]) -> Result<Vec<u8>, RoutingError> {
+    let mut raw_size = 0;
+    let mut reader = ProtoReader::new(buf);
+    while let Some((field, value)) = reader.next_field()? {
+        match (field, value) {
+            (1, Value::Bytes(raw)) => return Ok(raw.to_vec()),
+            (2, Value::Varint(v)) => raw_size = v as usize,
+            (3, Value::Bytes(zlib)) => {
+                let mut data = Vec::with_capacity(raw_size);
+                ZlibDecoder::new(zlib).read_to_end(&mut data)?;
+                return Ok(data);
+            }
+            (4..=7, _) => {
+                return Err(RoutingError::Parse(
+                    "unsupported PBF blob compression (only raw and zlib are supported)".into(),
+                ))
+            }
+            _ => {}
+        }
+    }
+    Err(RoutingError::Parse("PBF blob has no data".into()))
+}
+
+/// Coordinate encoding shared by all groups of a `PrimitiveBlock`.
+struct BlockContext<'a> {
+    strings: Vec<&'a [u8]>,
+    granularity: i64,
+    lat_offset: i64,
+    lon_offset: i64,
+}
+
+impl BlockContext<'_> {
+    fn lat(&self, raw: i64) -> f64 {
+        1e-9 * (self.lat_offset + self.granularity * raw) as f64
+    }
+
+    fn lon(&self, raw: i64) -> f64 {
+        1e-9 * (self.lon_offset + self.granularity * raw) as f64
+    }
+
+    fn string(&self, idx: u64) -> &str {
+        self.strings
+            .get(idx as usize)
+            .and_then(|s| std::str::from_utf8(s).ok())
+            .unwrap_or("")
+    }
+}
+
+fn parse_primitive_block(buf: &[u8], extract: &mut Extract) -> Result<(), RoutingError> {
+    let mut ctx = BlockContext {
+        strings: Vec::new(),
+        granularity: 100,
+        lat_offset: 0,
+        lon_offset: 0,
+    };
+    let mut groups = Vec::new();
+
+    let mut reader = ProtoReader::new(buf);
+    while let Some((field, value)) = reader.next_field()? {
+        match (field, value) {
+            (1, Value::Bytes(table)) => {
+                let mut table = ProtoReader::new(table);
+                while let Some((field, value)) = table.next_field()? {
+                    if let (1, Value::Bytes(s)) = (field, value) {
+                        ctx.strings.push(s);
+                    }
+                }
+            }
+            (2, Value::Bytes(group)) => groups.push(group),
+            (17, Value::Varint(v)) => ctx.granularity = v as i64,
+            (19, Value::Varint(v)) => ctx.lat_offset = v as i64,
+            (20, Value::Varint(v)) => ctx.lon_offset = v as i64,
+            _ => {}
+        }
+    }
+
+    for group in groups {
+        let mut reader = ProtoReader::new(group);
+        while let Some((field, value)) = reader.next_field()? {
+            match (field, value) {
+                (1, Value::Bytes(node)) => parse_node(node, &ctx, extract)?,
+                (2, Value::Bytes(dense)) => parse_dense_nodes(dense, &ctx, extract)?,
+                (3, Value::Bytes(way)) => parse_way(way, &ctx, extract)?,
+                (4, Value::Bytes(relation)) => parse_relation(relation, &ctx, extract)?,
+                _ => {}
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_node(buf: &[u8], ctx: &BlockContext, extract: &mut Extract) -> Result<(), RoutingError> {
+    let (mut id, mut lat, mut lon) = (0, 0, 0);
+    let mut reader = ProtoReader::new(buf);
+    while let Some((field, value)) = reader.next_field()? {
+        match (field, value) {
+            (1, Value::Varint(v)) => id = zigzag(v),
+            (8, Value::Varint(v)) => lat = zigzag(v),
+            (9, Value::Varint(v)) => lon = zigzag(v),
+            _ => {}
+        }
+    }
+    extract.add_node(id, ctx.lat(lat), ctx.lon(lon));
+    Ok(())
+}
+
+fn parse_dense_nodes(buf: &[u8], ctx: &BlockContext, extract: &mut Extract) -> Result<(), RoutingError> {
+    let (mut ids, mut lats, mut lons) = (Vec::new(), Vec::new(), Vec::new());
+    let mut reader = ProtoReader::new(buf);
+    while let Some((field, value)) = reader.next_field()? {
+        match field {
+            1 => value.read_varints(&mut ids)?,
+            8 => value.read_varints(&mut lats)?,
+            9 => value.read_varints(&mut lons)?,
+            _ => {}
+        }
+    }
+
+    // All three columns are delta coded
+    let (mut id, mut lat, mut lon) = (0, 0, 0);
+    for ((&d_id, &d_lat), &d_lon) in ids.iter().zip(&lats).zip(&lons) {
+        id += zigzag(d_id);
+        lat += zigzag(d_lat);
+        lon += zigzag(d_lon);
+        extract.add_node(id, ctx.lat(lat), ctx.lon(lon));
+    }
+    Ok(())
+}
+
+fn parse_way(buf: &[u8], ctx: &BlockContext, extract: &mut Extract) -> Result<(), RoutingError> {
+    let mut id = 0;
+    let (mut keys, mut vals, mut deltas) = (Vec::new(), Vec::new(), Vec::new());
+    let mut reader = ProtoReader::new(buf);
+    while let Some((field, value)) = reader.next_field()? {
+        match (field, value) {
+            (1, Value::Varint(v)) => id = v as i64,
+            (2, value) => value.read_varints(&mut keys)?,
+            (3, value) => value.read_varints(&mut vals)?,
+            (8, value) => value.read_varints(&mut deltas)?,
+            _ => {}
+        }
+    }
+
+    let mut tags = OsmTags::default();
+    for (&k, &v) in keys.iter().zip(&vals) {
+        set_tag(&mut tags, ctx.string(k), ctx.string(v));
+    }
+
+    let mut node_id = 0;
+    let refs = deltas
+        .iter()
+        .map(|&d| {
+            node_id += zigzag(d);
+            node_id
+        })
+        .collect();
+
+    extract.add_way(id, refs, tags);
+    Ok(())
+}
+
+fn parse_relation(buf: &[u8], ctx: &BlockContext, extract: &mut Extract) -> Result<(), RoutingError> {
+    let mut id = 0;
+    let (mut keys, mut vals) = (Vec::new(), Vec::new());
+    let (mut roles, mut deltas, mut types) = (Vec::new(), Vec::new(), Vec::new());
+    let mut reader = ProtoReader::new(buf);
+    while let Some((field, value)) = reader.next_field()? {
+        match (field, value) {
+            (1, Value::Varint(v)) => id = v as i64,
+            (2, value) => value.read_varints(&mut keys)?,
+            (3, value) => value.read_varints(&mut vals)?,
+            (8, value) => value.read_varints(&mut roles)?,
+            (9, value) => value.read_varints(&mut deltas)?,
+            (10, value) => value.read_varints(&mut types)?,
+            _ => {}
+        }
+    }
+
+    let mut tags = OsmTags::default();
+    for (&k, &v) in keys.iter().zip(&vals) {
+        set_tag(&mut tags, ctx.string(k), ctx.string(v));
+    }
+
+    let mut member_ref = 0;
+    let members = roles
+        .iter()
+        .zip(&deltas)
+        .zip(&types)
+        .map(|((&role, &delta), &member_type)| {
+            member_ref += zigzag(delta);
+            OsmMember {
+                member_type: match member_type {
+                    0 => "node",
+                    1 => "way",
+                    _ => "relation",
+                }
+                .to_string(),
+                member_ref,
+                role: ctx.string(role).to_string(),
+            }
+        })
+        .collect();
+
+    extract.add_relation(id, members, tags);
+    Ok(())
+}
+
+/// Decodes a zigzag-encoded `sint64`.
+fn zigzag(v: u64) -> i64 {
+    ((v >> 1) as i64) ^ -((v & 1) as i64)
+}
+
+/// A protobuf field value (fixed-width values are skipped).
+enum Value<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed,
+}
+
+impl Value<'_> {
+    /// Appends a repeated varint field, packed or not.
+    fn read_varints(self, out: &mut Vec<u64>) -> Result<(), RoutingError> {
+        match self {
+            Value::Varint(v) => out.push(v),
+            Value::Bytes(packed) => {
+                let mut reader = ProtoReader::new(packed);
+                while !reader.is_empty() {
+                    out.push(reader.varint()?);
+                }
+            }
+            Value::Fixed => {}
+        }
+        Ok(())
+    }
+}
+
+/// Minimal protobuf wire-format reader.
+struct ProtoReader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ProtoReader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.buf.len()
+    }
+
+    fn varint(&mut self) -> Result<u64, RoutingError> {
+        let mut result = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = *self
+                .buf
+                .get(self.pos)
+                .ok_or_else(|| RoutingError::Parse("truncated PBF varint".into()))?;
+            self.pos += 1;
+            result |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(result);
+            }
+        }
+        Err(RoutingError::Parse("PBF varint too long".into()))
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], RoutingError> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.buf.len())
+            .ok_or_else(|| RoutingError::Parse("truncated PBF field".into()))?;
+        let bytes = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    /// Returns the next `(field number, value)`, or `None` at the end.
+    fn next_field(&mut self) -> Result<Option<(u32, Value<'a>)>, RoutingError> {
+        if self.is_empty() {
+            return Ok(None);
+        }
+        let key = self.varint()?;
+        let value = match key & 7 {
+            0 => Value::Varint(self.varint()?),
+            1 => {
+                self.take(8)?;
+                Value::Fixed
+            }
+            2 => {
+                let len = self.varint()? as usize;
+                Value::Bytes(self.take(len)?)
+            }
+            5 => {
+                self.take(4)?;
+                Value::Fixed
+            }
+            wire_type => {
+                return Err(RoutingError::Parse(format!(
+                    "unsupported PBF wire type {}",
+                    wire_type
+                )))
+            }
+        };
+        Ok(Some(((key >> 3) as u32, value)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routing::RoadNetwork;
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    fn bbox() -> BoundingBox {
+        BoundingBox::new(39.9, -75.2, 40.0, -75.1)
+    }
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("vrp-osm-import-{}-{}", std::process::id(), name))
+    }
+
+    // --- tiny protobuf writer for building test files ---
+
+    fn put_varint(out: &mut Vec<u8>, mut v: u64) {
+        while v >= 0x80 {
+            out.push((v as u8) | 0x80);
+            v >>= 7;
+        }
+        out.push(v as u8);
+    }
+
+    fn put_uint(out: &mut Vec<u8>, field: u64, v: u64) {
+        put_varint(out, field << 3);
+        put_varint(out, v);
+    }
+
+    fn put_bytes(out: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+        put_varint(out, (field << 3) | 2);
+        put_varint(out, bytes.len() as u64);
+        out.extend_from_slice(bytes);
+    }
+
+    fn packed(values: &[u64]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for &v in values {
+            put_varint(&mut out, v);
+        }
+        out
+    }
+
+    fn zz(v: i64) -> u64 {
+        ((v << 1) ^ (v >> 63)) as u64
+    }
+
+    fn write_blob(file: &mut Vec<u8>, blob_type: &str, data: &[u8]) {
+        let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+        zlib.write_all(data).unwrap();
+        let mut blob = Vec::new();
+        put_uint(&mut blob, 2, data.len() as u64);
+        put_bytes(&mut blob, 3, &zlib.finish().unwrap());
+
+        let mut header = Vec::new();
+        put_bytes(&mut header, 1, blob_type.as_bytes());
+        put_uint(&mut header, 3, blob.len() as u64);
+
+        file.extend_from_slice(&(header.len() as u32).to_be_bytes());
+        file.extend_from_slice(&header);
+        file.extend_from_slice(&blob);
+    }
+
+    #[test]
+    fn test_read_pbf_extract() {
+        // Nodes 1 and 2 inside the bbox, node 3 outside
+        let coords = [(1, 39.95, -75.16), (2, 39.96, -75.17), (3, 41.0, -75.17)];
+        let (mut ids, mut lats, mut lons) = (Vec::new(), Vec::new(), Vec::new());
+        let (mut prev_id, mut prev_lat, mut prev_lon) = (0, 0, 0);
+        for &(id, lat, lon) in &coords {
+            let (lat, lon) = ((lat * 1e7) as i64, (lon * 1e7) as i64);
+            ids.push(zz(id - prev_id));
+            lats.push(zz(lat - prev_lat));
+            lons.push(zz(lon - prev_lon));
+            (prev_id, prev_lat, prev_lon) = (id, lat, lon);
+        }
+        let mut dense = Vec::new();
+        put_bytes(&mut dense, 1, &packed(&ids));
+        put_bytes(&mut dense, 8, &packed(&lats));
+        put_bytes(&mut dense, 9, &packed(&lons));
+
+        // Strings: 0 = "", 1 = highway, 2 = residential, 3 = footway
+        let mut table = Vec::new();
+        for s in ["", "highway", "residential", "footway"] {
+            put_bytes(&mut table, 1, s.as_bytes());
+        }
+        let way = |id: u64, value: u64| {
+            let mut way = Vec::new();
+            put_uint(&mut way, 1, id);
+            put_bytes(&mut way, 2, &packed(&[1]));
+            put_bytes(&mut way, 3, &packed(&[value]));
+            put_bytes(&mut way, 8, &packed(&[zz(1), zz(1), zz(1)]));
+            way
+        };
+
+        let mut group = Vec::new();
+        put_bytes(&mut group, 2, &dense);
+        let mut ways = Vec::new();
+        put_bytes(&mut ways, 3, &way(10, 2));
+        put_bytes(&mut ways, 3, &way(11, 3));
+
+        let mut block = Vec::new();
+        put_bytes(&mut block, 1, &table);
+        put_bytes(&mut block, 2, &group);
+        put_bytes(&mut block, 2, &ways);
+
+        let mut file = Vec::new();
+        write_blob(&mut file, "OSMHeader", &[]);
+        write_blob(&mut file, "OSMData", &block);
+        let path = temp_path("test.osm.pbf");
+        std::fs::write(&path, file).unwrap();
+
+        let osm = read_extract(&path, &bbox()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let nodes: Vec<_> = osm.elements.iter().filter(|e| e.elem_type == "node").collect();
+        let ways: Vec<_> = osm.elements.iter().filter(|e| e.elem_type == "way").collect();
+        assert_eq!(nodes.len(), 2);
+        assert!((nodes[1].lat.unwrap() - 39.96).abs() < 1e-7);
+        assert!((nodes[1].lon.unwrap() + 75.17).abs() < 1e-7);
+        assert_eq!(ways.len(), 1); // footway dropped
+        assert_eq!(ways[0].nodes.as_deref(), Some(&[1, 2, 3][..]));
+
+        let network = RoadNetwork::build_from_osm(&osm).unwrap();
+        assert_eq!(network.node_count(), 2);
+        assert_eq!(network.edge_count(), 2); // two-way segment 1-2
+    }
+
+    #[test]
+    fn test_read_xml_extract() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<osm version="0.6">
+  <node id="1" lat="39.95" lon="-75.16"/>
+  <node id="2" lat="39.96" lon="-75.17"/>
+  <node id="3" lat="39.97" lon="-75.18"><tag k="amenity" v="cafe"/></node>
+  <node id="4" lat="41.00" lon="-75.18"/>
+  <way id="10">
+    <nd ref="1"/><nd ref="2"/><nd ref="3"/>
+    <tag k="highway" v="primary"/>
+    <tag k="oneway" v="yes"/>
+  </way>
+  <way id="11">
+    <nd ref="3"/><nd ref="4"/>
+    <tag k="highway" v="footway"/>
+  </way>
+  <relation id="20">
+    <member type="way" ref="10" role="from"/>
+    <member type="node" ref="3" role="via"/>
+    <member type="way" ref="10" role="to"/>
+    <tag k="type" v="restriction"/>
+    <tag k="restriction" v="no_u_turn"/>
+  </relation>
+  <relation id="21">
+    <member type="way" ref="10" role="from"/>
+    <member type="node" ref="3" role="via"/>
+    <member type="way" ref="11" role="to"/>
+    <tag k="type" v="restriction"/>
+    <tag k="restriction" v="no_right_turn"/>
+  </relation>
+</osm>"#;
+        let path = temp_path("test.osm");
+        std::fs::write(&path, xml).unwrap();
+
+        let osm = read_extract(&path, &bbox()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // The restriction into the dropped footway goes with it
+        let relations: Vec<_> = osm.elements.iter().filter(|e| e.elem_type == "relation").collect();
+        assert_eq!(relations.len(), 1);
+        assert_eq!(relations[0].tags.as_ref().unwrap().restriction.as_deref(), Some("no_u_turn"));
+
+        let network = RoadNetwork::build_from_osm(&osm).unwrap();
+        assert_eq!(network.node_count(), 3);
+        assert_eq!(network.edge_count(), 2); // oneway
+    }
+
+    #[test]
+    fn test_unsupported_extension() {
+        let result = read_extract(Path::new("roads.geojson"), &bbox());
+        assert!(matches!(result, Err(RoutingError::Parse(_))));
+    }
+}
@@ -0,0 +1,236 @@
+//! Insertion recommendations for visits.
+//!
+//! Candidate insertions are scored incrementally: only the receiving route
+//! is re-evaluated, on the plan's own travel matrices, together with the
+//! visit's unassigned and pickup-and-delivery terms. Each candidate costs
+//! one pass over a single route instead of a full score calculation.
+
+use crate::constraints::{unassigned_penalty, vehicle_penalties, PAIR_VIOLATION_PENALTY};
+use crate::domain::{Vehicle, VehicleRoutePlan, Visit, VisitKind};
+use rayon::prelude::*;
+use solverforge::prelude::HardSoftScore;
+
+/// A candidate position for a visit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Insertion {
+    /// Visit index.
+    pub visit_idx: usize,
+    /// Vehicle position in `plan.vehicles`.
+    pub vehicle_idx: usize,
+    /// Position in the vehicle's route (with the visit removed from it).
+    pub position: usize,
+    /// Score change compared to leaving the visit unassigned.
+    pub score_diff: HardSoftScore,
+}
+
+/// Ranked insertions for one visit.
+#[derive(Debug, Clone)]
+pub struct VisitInsertions {
+    /// Visit index.
+    pub visit_idx: usize,
+    /// Best insertions first.
+    pub insertions: Vec<Insertion>,
+}
+
+/// Ranks every insertion of a visit into a compatible vehicle, best first.
+///
+/// A visit already on a route is first taken off it, so the result also
+/// answers "where should this visit move to".
+///
+/// # Examples
+///
+/// ```
+/// use vehicle_routing::domain::{Location, Visit, Vehicle, VehicleRoutePlan};
+/// use vehicle_routing::recommendation::rank_insertions;
+///
+/// let west = Location::new(0, 0.0, 0.0);
+/// let east = Location::new(1, 0.0, 0.05);
+/// let customer = Location::new(2, 0.0, 0.045);
+/// let vehicles = vec![Vehicle::new(0, "West", 10, west.clone()), Vehicle::new(1, "East", 10, east.clone())];
+///
+/// let visits = vec![Visit::new(0, "Customer", customer.clone())];
+/// let mut plan = VehicleRoutePlan::new("test", vec![west, east, customer], visits, vehicles);
+/// plan.finalize();
+///
+/// let ranked = rank_insertions(&plan, 0);
+/// assert_eq!(ranked.len(), 2);
+/// // Best first: the vehicle based next to the customer
+/// assert_eq!(ranked[0].vehicle_idx, 1);
+/// assert!(ranked[0].score_diff > ranked[1].score_diff);
+/// ```
+pub fn rank_insertions(plan: &VehicleRoutePlan, visit_idx: usize) -> Vec<Insertion> {
+    let Some(visit) = plan.visits.get(visit_idx) else {
+        return Vec::new();
+    };
+
+    // Pair term before insertion: violated if the partner is on a route
+    let partner_assigned = visit
+        .paired_visit
+        .is_some_and(|partner| plan.vehicles.iter().any(|v| v.visits.contains(&partner)));
+    let (unassigned_hard, unassigned_soft) = unassigned_penalty(visit);
+    let pair_before = if partner_assigned { PAIR_VIOLATION_PENALTY } else { 0 };
+
+    let mut insertions = Vec::new();
+    for (vehicle_idx, vehicle) in plan.vehicles.iter().enumerate() {
+        if !visit.is_compatible_with(vehicle) {
+            continue;
+        }
+
+        let mut route = vehicle.clone();
+        route.visits.retain(|&v| v != visit_idx);
+        let (base_hard, base_soft) = vehicle_penalties(plan, &route);
+
+        for position in 0..=route.visits.len() {
+            let mut trial = route.clone();
+            trial.visits.insert(position, visit_idx);
+            let (hard, soft) = vehicle_penalties(plan, &trial);
+            let pair_after = if pair_violated(visit, &trial, position) {
+                PAIR_VIOLATION_PENALTY
+            } else {
+                0
+            };
+
+            insertions.push(Insertion {
+                visit_idx,
+                vehicle_idx,
+                position,
+                score_diff: HardSoftScore::of(
+                    hard - base_hard + unassigned_hard + pair_before - pair_after,
+                    soft - base_soft + unassigned_soft,
+                ),
+            });
+        }
+    }
+
+    insertions.sort_by(|a, b| {
+        b.score_diff
+            .cmp(&a.score_diff)
+            .then((a.vehicle_idx, a.position).cmp(&(b.vehicle_idx, b.position)))
+    });
+    insertions
+}
+
+/// Ranks insertions for several visits at once, keeping the best `limit`
+/// per visit.
+///
+/// Every visit is evaluated against the current plan independently, in
+/// parallel. Visits are ordered by their best insertion, so the visit that
+/// is cheapest to place comes first; visits without any compatible vehicle
+/// come last with no insertions.
+pub fn rank_batch(plan: &VehicleRoutePlan, visit_indices: &[usize], limit: usize) -> Vec<VisitInsertions> {
+    let mut ranked: Vec<VisitInsertions> = visit_indices
+        .par_iter()
+        .map(|&visit_idx| {
+            let mut insertions = rank_insertions(plan, visit_idx);
+            insertions.truncate(limit);
+            VisitInsertions { visit_idx, insertions }
+        })
+        .collect();
+
+    ranked.sort_by(|a, b| match (a.insertions.first(), b.insertions.first()) {
+        (Some(x), Some(y)) => y.score_diff.cmp(&x.score_diff),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    });
+    ranked
+}
+
+/// Whether a visit at `position` of `trial` breaks its pickup-and-delivery
+/// pair: the partner is elsewhere (or unassigned), or on the wrong side.
+fn pair_violated(visit: &Visit, trial: &Vehicle, position: usize) -> bool {
+    let Some(partner) = visit.paired_visit else {
+        return false;
+    };
+    match trial.visits.iter().position(|&v| v == partner) {
+        Some(partner_pos) if visit.kind == VisitKind::Pickup => position > partner_pos,
+        Some(partner_pos) => position < partner_pos,
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constraints::calculate_score;
+    use crate::domain::Location;
+
+    fn sample_plan() -> VehicleRoutePlan {
+        let depot = Location::new(0, 39.95, -75.16);
+        let locations: Vec<Location> = (1..=5)
+            .map(|i| Location::new(i, 39.95 + 0.01 * i as f64, -75.16 + 0.007 * (i % 3) as f64))
+            .collect();
+        let visits = vec![
+            Visit::new(0, "Pickup", locations[0].clone()).with_demand(4).with_pickup_for(1),
+            Visit::new(1, "Delivery", locations[1].clone()).with_demand(4).with_delivery_for(0),
+            Visit::new(2, "Tight", locations[2].clone()).with_time_window(0, 8 * 3600 + 600),
+            Visit::new(3, "Optional", locations[3].clone()).with_optional(3),
+            Visit::new(4, "Frozen", locations[4].clone())
+                .with_demand(5)
+                .with_required_capability("refrigerated"),
+        ];
+        let mut alpha = Vehicle::new(0, "Alpha", 8, depot.clone()).with_departure_time(8 * 3600);
+        alpha.visits = vec![0, 2];
+        let mut bravo = Vehicle::new(1, "Bravo", 10, depot.clone())
+            .with_departure_time(8 * 3600)
+            .with_capability("refrigerated");
+        bravo.visits = vec![4];
+
+        let mut all = vec![depot];
+        all.extend(locations);
+        let mut plan = VehicleRoutePlan::new("test", all, visits, vec![alpha, bravo]);
+        plan.finalize();
+        plan
+    }
+
+    /// Score change of an insertion, computed the slow way.
+    fn full_diff(plan: &VehicleRoutePlan, insertion: &Insertion) -> HardSoftScore {
+        let mut without = plan.clone();
+        for vehicle in &mut without.vehicles {
+            vehicle.visits.retain(|&v| v != insertion.visit_idx);
+        }
+        let mut with = without.clone();
+        with.vehicles[insertion.vehicle_idx]
+            .visits
+            .insert(insertion.position, insertion.visit_idx);
+        calculate_score(&with) - calculate_score(&without)
+    }
+
+    #[test]
+    fn test_incremental_matches_full_score() {
+        let plan = sample_plan();
+        for visit_idx in 0..plan.visits.len() {
+            let ranked = rank_insertions(&plan, visit_idx);
+            assert!(!ranked.is_empty());
+            for insertion in &ranked {
+                assert_eq!(insertion.score_diff, full_diff(&plan, insertion), "{:?}", insertion);
+            }
+            assert!(ranked.windows(2).all(|w| w[0].score_diff >= w[1].score_diff));
+        }
+    }
+
+    #[test]
+    fn test_incompatible_vehicles_skipped() {
+        let plan = sample_plan();
+        assert!(rank_insertions(&plan, 4).iter().all(|i| i.vehicle_idx == 1));
+        assert!(rank_insertions(&plan, 99).is_empty());
+    }
+
+    #[test]
+    fn test_delivery_goes_after_its_pickup() {
+        let plan = sample_plan();
+        let best = rank_insertions(&plan, 1)[0];
+        assert_eq!(best.vehicle_idx, 0);
+        assert!(best.position >= 1);
+    }
+
+    #[test]
+    fn test_batch_orders_by_best_insertion() {
+        let plan = sample_plan();
+        let batch = rank_batch(&plan, &[3, 1], 2);
+        assert_eq!(batch.len(), 2);
+        assert!(batch.iter().all(|v| v.insertions.len() == 2));
+        // The mandatory delivery gains the hard unassigned penalty, so it comes first
+        assert_eq!(batch[0].visit_idx, 1);
+    }
+}
@@ -0,0 +1,775 @@
+//! Road network cache, in memory and in `.osm_cache/`.
+//!
+//! Networks are stored in a compact binary format: an uncompressed header
+//! (magic, format version, source, bounding box, counts, CRC-32) followed by
+//! the zlib-compressed node, edge, turn restriction and edge shape arrays.
+//! Lookups reuse any cached network
+//! from the same source whose bounding box contains the requested one, and
+//! the directory is kept under a size limit by evicting the least recently
+//! used files.
+
+use crate::routing::{BoundingBox, RoadNetwork, RoutingError};
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::{Compression, Crc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::SystemTime;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+/// Cache directory for road networks.
+pub const CACHE_DIR: &str = ".osm_cache";
+
+/// Environment variable limiting the cache directory size, in megabytes.
+pub const CACHE_MAX_MB_ENV: &str = "OSM_CACHE_MAX_MB";
+
+/// Default cache directory size limit in megabytes.
+const DEFAULT_CACHE_MAX_MB: u64 = 1024;
+
+/// Maximum number of networks kept in memory.
+const MAX_IN_MEMORY: usize = 8;
+
+/// Cache file extension.
+const EXTENSION: &str = "rnc";
+
+/// File signature.
+const MAGIC: &[u8; 4] = b"VRNC";
+
+/// Cache format version. Bump this when changing the cache structure.
+const CACHE_VERSION: u32 = 7;
+
+// ============================================================================
+// Cache Data Structures
+// ============================================================================
+
+/// Node, edge and turn restriction arrays of a road network.
+///
+/// Edge shape points are stored after the restrictions, in edge order; each
+/// edge record holds its shape point count.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct CachedNetwork {
+    pub(crate) nodes: Vec<CachedNode>,
+    pub(crate) edges: Vec<CachedEdge>,
+    pub(crate) restrictions: Vec<CachedRestriction>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct CachedNode {
+    pub(crate) lat: f64,
+    pub(crate) lng: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CachedEdge {
+    pub(crate) from: usize,
+    pub(crate) to: usize,
+    pub(crate) travel_time_s: f64,
+    pub(crate) distance_m: f64,
+    pub(crate) way_id: i64,
+    pub(crate) road_class: u8,
+    /// Shape points between the endpoints.
+    pub(crate) shape: Vec<(f64, f64)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct CachedRestriction {
+    pub(crate) from_way: i64,
+    pub(crate) via: usize,
+    pub(crate) to_way: i64,
+    pub(crate) only: bool,
+}
+
+/// Encoded sizes of the payload records.
+const NODE_BYTES: u64 = 16;
+const EDGE_BYTES: u64 = 37;
+const RESTRICTION_BYTES: u64 = 21;
+const SHAPE_POINT_BYTES: u64 = 16;
+
+/// Uncompressed file header.
+#[derive(Debug, Clone)]
+struct Header {
+    source: String,
+    bbox: BoundingBox,
+    node_count: u64,
+    edge_count: u64,
+    restriction_count: u64,
+    shape_point_count: u64,
+    payload_len: u64,
+    /// CRC-32 of the header fields before it and the compressed payload.
+    checksum: u32,
+}
+
+/// A cached road network, as listed by the cache endpoints.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CachedNetworkInfo {
+    /// Cache key (file name without extension).
+    pub key: String,
+    /// Where the network came from ("overpass" or "extract:<file>").
+    pub source: String,
+    /// South-west corner of the covered area as `[latitude, longitude]`.
+    pub south_west_corner: [f64; 2],
+    /// North-east corner of the covered area as `[latitude, longitude]`.
+    pub north_east_corner: [f64; 2],
+    /// Number of road graph nodes.
+    pub node_count: u64,
+    /// Number of road graph edges.
+    pub edge_count: u64,
+    /// File size in bytes.
+    pub size_bytes: u64,
+    /// Last time the network was loaded or saved (RFC 3339).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_used: Option<String>,
+    /// Whether the network is also held in memory.
+    pub in_memory: bool,
+}
+
+// ============================================================================
+// In-Memory Cache
+// ============================================================================
+
+static MEMORY_CACHE: OnceLock<RwLock<MemoryCache>> = OnceLock::new();
+
+/// Process-wide in-memory cache.
+pub(crate) fn memory_cache() -> &'static RwLock<MemoryCache> {
+    MEMORY_CACHE.get_or_init(|| RwLock::new(MemoryCache::default()))
+}
+
+struct MemoryEntry {
+    source: String,
+    bbox: BoundingBox,
+    network: Arc<RoadNetwork>,
+    last_used: AtomicU64,
+}
+
+/// Road networks held in memory, keyed by cache key, with LRU eviction.
+#[derive(Default)]
+pub(crate) struct MemoryCache {
+    entries: HashMap<String, MemoryEntry>,
+    clock: AtomicU64,
+}
+
+impl MemoryCache {
+    /// Returns the smallest cached network from `source` covering `bbox`.
+    pub(crate) fn find(&self, source: &str, bbox: &BoundingBox) -> Option<(String, Arc<RoadNetwork>)> {
+        let (key, entry) = self
+            .entries
+            .iter()
+            .filter(|(_, e)| e.source == source && e.bbox.contains_box(bbox))
+            .min_by(|a, b| a.1.bbox.area().total_cmp(&b.1.bbox.area()))?;
+        entry
+            .last_used
+            .store(self.clock.fetch_add(1, Ordering::Relaxed), Ordering::Relaxed);
+        Some((key.clone(), Arc::clone(&entry.network)))
+    }
+
+    /// Adds a network, evicting the least recently used beyond the limit.
+    pub(crate) fn insert(&mut self, key: String, source: &str, bbox: BoundingBox, network: Arc<RoadNetwork>) {
+        let now = self.clock.fetch_add(1, Ordering::Relaxed);
+        self.entries.insert(
+            key,
+            MemoryEntry {
+                source: source.to_string(),
+                bbox,
+                network,
+                last_used: AtomicU64::new(now),
+            },
+        );
+        while self.entries.len() > MAX_IN_MEMORY {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, e)| e.last_used.load(Ordering::Relaxed))
+                .map(|(k, _)| k.clone());
+            if let Some(key) = oldest {
+                info!("Evicting road network {} from memory", key);
+                self.entries.remove(&key);
+            }
+        }
+    }
+
+    fn contains_key(&self, key: &str) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    /// Removes the network with `key`, or all networks. Returns the number removed.
+    fn remove(&mut self, key: Option<&str>) -> usize {
+        match key {
+            Some(key) => self.entries.remove(key).map_or(0, |_| 1),
+            None => {
+                let removed = self.entries.len();
+                self.entries.clear();
+                removed
+            }
+        }
+    }
+}
+
+// ============================================================================
+// Disk Cache
+// ============================================================================
+
+/// Loads the smallest cached network from `source` whose bounding box
+/// contains `bbox`. Returns its key, covered area and network.
+///
+/// Corrupt or outdated files are deleted and skipped.
+pub(crate) async fn load_from_disk(
+    source: &str,
+    bbox: &BoundingBox,
+) -> Option<(String, BoundingBox, RoadNetwork)> {
+    let source = source.to_string();
+    let bbox = *bbox;
+    tokio::task::spawn_blocking(move || {
+        let mut candidates = scan(Path::new(CACHE_DIR))
+            .into_iter()
+            .filter(|(_, h)| h.source == source && h.bbox.contains_box(&bbox))
+            .collect::<Vec<_>>();
+        candidates.sort_by(|a, b| a.1.bbox.area().total_cmp(&b.1.bbox.area()));
+
+        for (path, header) in candidates {
+            info!("Loading road network from file cache: {:?}", path);
+            match read_file(&path) {
+                Ok((_, cached)) => {
+                    touch(&path);
+                    let key = file_key(&path)?;
+                    return Some((key, header.bbox, RoadNetwork::from_cached(&cached)));
+                }
+                Err(e) => {
+                    info!("File cache invalid ({}), removing {:?}", e, path);
+                    let _ = fs::remove_file(&path);
+                }
+            }
+        }
+        None
+    })
+    .await
+    .ok()
+    .flatten()
+}
+
+/// Saves a network under `key` and enforces the cache size limit.
+pub(crate) async fn save_to_disk(
+    key: &str,
+    source: &str,
+    bbox: &BoundingBox,
+    network: &RoadNetwork,
+) -> Result<(), RoutingError> {
+    let path = Path::new(CACHE_DIR).join(format!("{}.{}", key, EXTENSION));
+    let bytes = encode(source, bbox, &network.to_cached())?;
+    tokio::task::spawn_blocking(move || {
+        write_atomic(&path, &bytes)?;
+        info!("Saved road network to file cache: {:?}", path);
+        enforce_limit(Path::new(CACHE_DIR), max_cache_bytes(), &path);
+        Ok::<_, RoutingError>(())
+    })
+    .await
+    .map_err(|e| RoutingError::Io(io::Error::other(e)))?
+}
+
+/// Lists cached networks, most recently used first.
+pub async fn list() -> Vec<CachedNetworkInfo> {
+    let mut infos = tokio::task::spawn_blocking(|| list_dir(Path::new(CACHE_DIR)))
+        .await
+        .unwrap_or_default();
+    let memory = memory_cache().read().await;
+    for info in &mut infos {
+        info.in_memory = memory.contains_key(&info.key);
+    }
+    infos
+}
+
+/// What [`purge`] removed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Purged {
+    /// Networks dropped from memory.
+    pub in_memory: usize,
+    /// Cache files deleted.
+    pub files: usize,
+}
+
+/// Removes the cached network with `key`, or all cached networks (including
+/// legacy JSON caches) if `key` is `None`, from memory and disk.
+pub async fn purge(key: Option<&str>) -> Purged {
+    let in_memory = memory_cache().write().await.remove(key);
+    let key = key.map(str::to_string);
+    let files = tokio::task::spawn_blocking(move || purge_dir(Path::new(CACHE_DIR), key.as_deref()))
+        .await
+        .unwrap_or(0);
+    Purged { in_memory, files }
+}
+
+/// Cache directory size limit in bytes.
+fn max_cache_bytes() -> u64 {
+    std::env::var(CACHE_MAX_MB_ENV)
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_CACHE_MAX_MB)
+        * 1024
+        * 1024
+}
+
+/// Cache files in `dir` with their headers (unreadable files are skipped).
+fn scan(dir: &Path) -> Vec<(PathBuf, Header)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == EXTENSION))
+        .filter_map(|p| {
+            let header = File::open(&p).ok().and_then(|mut f| read_header(&mut f).ok())?;
+            Some((p, header))
+        })
+        .collect()
+}
+
+fn list_dir(dir: &Path) -> Vec<CachedNetworkInfo> {
+    let mut infos: Vec<(SystemTime, CachedNetworkInfo)> = scan(dir)
+        .into_iter()
+        .filter_map(|(path, h)| {
+            let meta = fs::metadata(&path).ok()?;
+            let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            Some((
+                modified,
+                CachedNetworkInfo {
+                    key: file_key(&path)?,
+                    source: h.source,
+                    south_west_corner: [h.bbox.min_lat, h.bbox.min_lng],
+                    north_east_corner: [h.bbox.max_lat, h.bbox.max_lng],
+                    node_count: h.node_count,
+                    edge_count: h.edge_count,
+                    size_bytes: meta.len(),
+                    last_used: Some(chrono::DateTime::<chrono::Utc>::from(modified).to_rfc3339()),
+                    in_memory: false,
+                },
+            ))
+        })
+        .collect();
+    infos.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    infos.into_iter().map(|(_, info)| info).collect()
+}
+
+fn purge_dir(dir: &Path, key: Option<&str>) -> usize {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            let is_cache = p
+                .extension()
+                .is_some_and(|ext| ext == EXTENSION || (key.is_none() && ext == "json"));
+            is_cache && key.is_none_or(|k| file_key(p).as_deref() == Some(k))
+        })
+        .filter(|p| fs::remove_file(p).is_ok())
+        .count()
+}
+
+/// Deletes least recently used cache files until the directory fits in
+/// `max_bytes`. The file at `keep` is never deleted.
+fn enforce_limit(dir: &Path, max_bytes: u64, keep: &Path) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut files: Vec<(SystemTime, u64, PathBuf)> = entries
+        .flatten()
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == EXTENSION))
+        .filter_map(|e| {
+            let meta = e.metadata().ok()?;
+            Some((meta.modified().unwrap_or(SystemTime::UNIX_EPOCH), meta.len(), e.path()))
+        })
+        .collect();
+    files.sort();
+
+    let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+    for (_, len, path) in files {
+        if total <= max_bytes {
+            break;
+        }
+        if path == keep {
+            continue;
+        }
+        if fs::remove_file(&path).is_ok() {
+            warn!("Road network cache over limit, evicted {:?}", path);
+            total -= len;
+        }
+    }
+}
+
+/// Marks a cache file as recently used.
+fn touch(path: &Path) {
+    if let Ok(file) = File::options().write(true).open(path) {
+        let _ = file.set_modified(SystemTime::now());
+    }
+}
+
+fn file_key(path: &Path) -> Option<String> {
+    path.file_stem().map(|s| s.to_string_lossy().into_owned())
+}
+
+/// Writes via a temporary file so readers never see partial files.
+pub(crate) fn write_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, bytes)?;
+    fs::rename(&tmp, path)
+}
+
+// ============================================================================
+// Binary Format
+// ============================================================================
+
+fn encode(source: &str, bbox: &BoundingBox, network: &CachedNetwork) -> Result<Vec<u8>, RoutingError> {
+    let shape_point_count: usize = network.edges.iter().map(|e| e.shape.len()).sum();
+    let mut payload = Vec::with_capacity(
+        network.nodes.len() * NODE_BYTES as usize
+            + network.edges.len() * EDGE_BYTES as usize
+            + network.restrictions.len() * RESTRICTION_BYTES as usize
+            + shape_point_count * SHAPE_POINT_BYTES as usize,
+    );
+    for node in &network.nodes {
+        payload.extend_from_slice(&node.lat.to_le_bytes());
+        payload.extend_from_slice(&node.lng.to_le_bytes());
+    }
+    for edge in &network.edges {
+        payload.extend_from_slice(&(edge.from as u32).to_le_bytes());
+        payload.extend_from_slice(&(edge.to as u32).to_le_bytes());
+        payload.extend_from_slice(&edge.travel_time_s.to_le_bytes());
+        payload.extend_from_slice(&edge.distance_m.to_le_bytes());
+        payload.extend_from_slice(&edge.way_id.to_le_bytes());
+        payload.push(edge.road_class);
+        payload.extend_from_slice(&(edge.shape.len() as u32).to_le_bytes());
+    }
+    for r in &network.restrictions {
+        payload.extend_from_slice(&r.from_way.to_le_bytes());
+        payload.extend_from_slice(&(r.via as u32).to_le_bytes());
+        payload.extend_from_slice(&r.to_way.to_le_bytes());
+        payload.push(r.only as u8);
+    }
+    for &(lat, lng) in network.edges.iter().flat_map(|e| &e.shape) {
+        payload.extend_from_slice(&lat.to_le_bytes());
+        payload.extend_from_slice(&lng.to_le_bytes());
+    }
+
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&payload)?;
+    let compressed = encoder.finish()?;
+
+    let source = source.as_bytes();
+    let mut out = Vec::with_capacity(compressed.len() + 80 + source.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&CACHE_VERSION.to_le_bytes());
+    out.extend_from_slice(&(source.len() as u16).to_le_bytes());
+    out.extend_from_slice(source);
+    for v in [bbox.min_lat, bbox.min_lng, bbox.max_lat, bbox.max_lng] {
+        out.extend_from_slice(&v.to_le_bytes());
+    }
+    out.extend_from_slice(&(network.nodes.len() as u64).to_le_bytes());
+    out.extend_from_slice(&(network.edges.len() as u64).to_le_bytes());
+    out.extend_from_slice(&(network.restrictions.len() as u64).to_le_bytes());
+    out.extend_from_slice(&(shape_point_count as u64).to_le_bytes());
+    out.extend_from_slice(&(compressed.len() as u64).to_le_bytes());
+    let mut crc = Crc::new();
+    crc.update(&out);
+    crc.update(&compressed);
+    out.extend_from_slice(&crc.sum().to_le_bytes());
+    out.extend_from_slice(&compressed);
+    Ok(out)
+}
+
+fn read_header(r: &mut impl Read) -> Result<Header, RoutingError> {
+    let mut magic = [0u8; 4];
+    r.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(RoutingError::Parse("not a road network cache file".into()));
+    }
+    let version = read_u32(r)?;
+    if version != CACHE_VERSION {
+        return Err(RoutingError::Parse(format!(
+            "cache version mismatch (got {}, need {})",
+            version, CACHE_VERSION
+        )));
+    }
+    let mut source = vec![0u8; read_u16(r)? as usize];
+    r.read_exact(&mut source)?;
+    let bbox = BoundingBox::new(read_f64(r)?, read_f64(r)?, read_f64(r)?, read_f64(r)?);
+    Ok(Header {
+        source: String::from_utf8_lossy(&source).into_owned(),
+        bbox,
+        node_count: read_u64(r)?,
+        edge_count: read_u64(r)?,
+        restriction_count: read_u64(r)?,
+        shape_point_count: read_u64(r)?,
+        payload_len: read_u64(r)?,
+        checksum: read_u32(r)?,
+    })
+}
+
+fn decode(bytes: &[u8]) -> Result<(Header, CachedNetwork), RoutingError> {
+    let mut r = bytes;
+    let header = read_header(&mut r)?;
+    if r.len() as u64 != header.payload_len {
+        return Err(RoutingError::Parse("truncated cache file".into()));
+    }
+    // Everything but the checksum field itself
+    let header_len = bytes.len() - r.len() - 4;
+    let mut crc = Crc::new();
+    crc.update(&bytes[..header_len]);
+    crc.update(r);
+    if crc.sum() != header.checksum {
+        return Err(RoutingError::Parse("cache checksum mismatch".into()));
+    }
+
+    let expected = [
+        (header.node_count, NODE_BYTES),
+        (header.edge_count, EDGE_BYTES),
+        (header.restriction_count, RESTRICTION_BYTES),
+        (header.shape_point_count, SHAPE_POINT_BYTES),
+    ]
+    .into_iter()
+    .try_fold(0u64, |total, (count, size)| count.checked_mul(size)?.checked_add(total))
+    .ok_or_else(|| RoutingError::Parse("cache record counts overflow".into()))?;
+    let mut payload = Vec::new();
+    ZlibDecoder::new(r).read_to_end(&mut payload)?;
+    if payload.len() as u64 != expected {
+        return Err(RoutingError::Parse("cache payload size mismatch".into()));
+    }
+
+    let mut p = payload.as_slice();
+    let nodes = (0..header.node_count)
+        .map(|_| {
+            Ok(CachedNode {
+                lat: read_f64(&mut p)?,
+                lng: read_f64(&mut p)?,
+            })
+        })
+        .collect::<Result<Vec<_>, RoutingError>>()?;
+    let mut shape_lens = Vec::with_capacity(header.edge_count as usize);
+    let mut edges = (0..header.edge_count)
+        .map(|_| {
+            let edge = CachedEdge {
+                from: read_u32(&mut p)? as usize,
+                to: read_u32(&mut p)? as usize,
+                travel_time_s: read_f64(&mut p)?,
+                distance_m: read_f64(&mut p)?,
+                way_id: read_u64(&mut p)? as i64,
+                road_class: read_u8(&mut p)?,
+                shape: Vec::new(),
+            };
+            if edge.from >= nodes.len() || edge.to >= nodes.len() {
+                return Err(RoutingError::Parse("cache edge refers to unknown node".into()));
+            }
+            shape_lens.push(read_u32(&mut p)? as u64);
+            Ok(edge)
+        })
+        .collect::<Result<Vec<_>, RoutingError>>()?;
+    if shape_lens.iter().sum::<u64>() != header.shape_point_count {
+        return Err(RoutingError::Parse("cache shape point count mismatch".into()));
+    }
+    let restrictions = (0..header.restriction_count)
+        .map(|_| {
+            let restriction = CachedRestriction {
+                from_way: read_u64(&mut p)? as i64,
+                via: read_u32(&mut p)? as usize,
+                to_way: read_u64(&mut p)? as i64,
+                only: read_u8(&mut p)? != 0,
+            };
+            if restriction.via >= nodes.len() {
+                return Err(RoutingError::Parse("cache restriction refers to unknown node".into()));
+            }
+            Ok(restriction)
+        })
+        .collect::<Result<Vec<_>, RoutingError>>()?;
+    for (edge, len) in edges.iter_mut().zip(shape_lens) {
+        edge.shape = (0..len)
+            .map(|_| Ok((read_f64(&mut p)?, read_f64(&mut p)?)))
+            .collect::<io::Result<Vec<_>>>()?;
+    }
+
+    Ok((
+        header,
+        CachedNetwork {
+            nodes,
+            edges,
+            restrictions,
+        },
+    ))
+}
+
+fn read_file(path: &Path) -> Result<(Header, CachedNetwork), RoutingError> {
+    decode(&fs::read(path)?)
+}
+
+fn read_u8(r: &mut impl Read) -> io::Result<u8> {
+    let mut buf = [0u8; 1];
+    r.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+fn read_u16(r: &mut impl Read) -> io::Result<u16> {
+    let mut buf = [0u8; 2];
+    r.read_exact(&mut buf)?;
+    Ok(u16::from_le_bytes(buf))
+}
+
+fn read_u32(r: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(r: &mut impl Read) -> io::Result<u64> {
+    let mut buf = [0u8; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_f64(r: &mut impl Read) -> io::Result<f64> {
+    Ok(f64::from_bits(read_u64(r)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> CachedNetwork {
+        CachedNetwork {
+            nodes: vec![
+                CachedNode { lat: 39.95, lng: -75.16 },
+                CachedNode { lat: 39.96, lng: -75.17 },
+            ],
+            edges: vec![CachedEdge {
+                from: 0,
+                to: 1,
+                travel_time_s: 42.5,
+                distance_m: 1_234.0,
+                way_id: 1_000_000_000_123,
+                road_class: 5,
+                shape: vec![(39.953, -75.162), (39.957, -75.168)],
+            }],
+            restrictions: vec![CachedRestriction {
+                from_way: 7,
+                via: 1,
+                to_way: -8,
+                only: true,
+            }],
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("vrp-road-cache-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let bbox = BoundingBox::new(39.9, -75.2, 40.0, -75.1);
+        let bytes = encode("overpass", &bbox, &sample()).unwrap();
+        let (header, network) = decode(&bytes).unwrap();
+        assert_eq!(header.source, "overpass");
+        assert_eq!(header.bbox.max_lng, -75.1);
+        assert_eq!(network, sample());
+    }
+
+    #[test]
+    fn test_corruption_detected() {
+        let bbox = BoundingBox::new(39.9, -75.2, 40.0, -75.1);
+        let mut bytes = encode("overpass", &bbox, &sample()).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        assert!(matches!(decode(&bytes), Err(RoutingError::Parse(_))));
+        assert!(decode(&bytes[..bytes.len() - 3]).is_err());
+
+        // Header fields are covered too: flip a bit of the bounding box
+        let mut bytes = encode("overpass", &bbox, &sample()).unwrap();
+        let bbox_offset = MAGIC.len() + 4 + 2 + "overpass".len();
+        bytes[bbox_offset] ^= 0x01;
+        assert!(matches!(decode(&bytes), Err(RoutingError::Parse(_))));
+    }
+
+    #[test]
+    fn test_record_count_overflow() {
+        let bbox = BoundingBox::new(39.9, -75.2, 40.0, -75.1);
+        let mut bytes = encode("overpass", &bbox, &sample()).unwrap();
+        let node_count_offset = MAGIC.len() + 4 + 2 + "overpass".len() + 4 * 8;
+        bytes[node_count_offset..node_count_offset + 8].copy_from_slice(&(u64::MAX / 2).to_le_bytes());
+
+        // Fix up the checksum so the count check is what rejects the file
+        let checksum_offset = node_count_offset + 5 * 8;
+        let mut crc = Crc::new();
+        crc.update(&bytes[..checksum_offset]);
+        crc.update(&bytes[checksum_offset + 4..]);
+        bytes[checksum_offset..checksum_offset + 4].copy_from_slice(&crc.sum().to_le_bytes());
+
+        match decode(&bytes) {
+            Err(RoutingError::Parse(msg)) => assert!(msg.contains("overflow"), "{}", msg),
+            other => panic!("expected overflow error, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_memory_remove_counts() {
+        let bbox = BoundingBox::new(39.9, -75.2, 40.0, -75.1);
+        let mut cache = MemoryCache::default();
+        cache.insert("a".into(), "overpass", bbox, Arc::new(RoadNetwork::new()));
+        cache.insert("b".into(), "overpass", bbox, Arc::new(RoadNetwork::new()));
+
+        // A memory-only network still counts as purged
+        assert_eq!(cache.remove(Some("a")), 1);
+        assert_eq!(cache.remove(Some("a")), 0);
+        assert_eq!(cache.remove(None), 1);
+    }
+
+    #[test]
+    fn test_scan_and_lru_eviction() {
+        let dir = temp_dir("lru");
+        let boxes = [
+            ("big", BoundingBox::new(39.0, -76.0, 41.0, -74.0)),
+            ("small", BoundingBox::new(39.9, -75.2, 40.0, -75.1)),
+            ("other", BoundingBox::new(10.0, 10.0, 11.0, 11.0)),
+        ];
+        for (i, (key, bbox)) in boxes.iter().enumerate() {
+            let path = dir.join(format!("{}.{}", key, EXTENSION));
+            write_atomic(&path, &encode("overpass", bbox, &sample()).unwrap()).unwrap();
+            let file = File::options().write(true).open(&path).unwrap();
+            file.set_modified(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000 + i as u64))
+                .unwrap();
+        }
+
+        let requested = BoundingBox::new(39.95, -75.15, 39.96, -75.14);
+        let containing: Vec<String> = scan(&dir)
+            .into_iter()
+            .filter(|(_, h)| h.bbox.contains_box(&requested))
+            .filter_map(|(p, _)| file_key(&p))
+            .collect();
+        assert_eq!(containing.len(), 2);
+
+        // Listing is most recently used first
+        let keys: Vec<String> = list_dir(&dir).into_iter().map(|i| i.key).collect();
+        assert_eq!(keys, vec!["other", "small", "big"]);
+
+        // Room for two files: the oldest goes, the kept file survives
+        let size = fs::metadata(dir.join(format!("big.{}", EXTENSION))).unwrap().len();
+        enforce_limit(&dir, size * 2, &dir.join(format!("big.{}", EXTENSION)));
+        let keys: Vec<String> = list_dir(&dir).into_iter().map(|i| i.key).collect();
+        assert_eq!(keys, vec!["other", "big"]);
+
+        assert_eq!(purge_dir(&dir, Some("other")), 1);
+        assert_eq!(purge_dir(&dir, None), 1);
+        assert!(list_dir(&dir).is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
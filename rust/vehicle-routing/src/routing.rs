@@ -1,36 +1,165 @@
 //! Local OSM road routing using Overpass API and petgraph.
 //!
-//! Downloads OpenStreetMap road network data via Overpass API,
-//! builds a graph locally, and computes shortest paths with Dijkstra.
+//! Downloads OpenStreetMap road network data via Overpass API (or reads a
+//! local extract, see [`RoadNetworkSource`]), builds a graph locally, and
+//! computes shortest paths with Dijkstra.
 //! Results are cached in memory (per-process) and `.osm_cache/` (persistent).
 
+use crate::metrics::{self, CacheLookup};
+use crate::road_cache::{self, CachedEdge, CachedNetwork, CachedNode, CachedRestriction};
 use ordered_float::OrderedFloat;
-use petgraph::algo::{astar, dijkstra};
-use petgraph::graph::{DiGraph, NodeIndex};
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::Path;
+use parking_lot::Mutex;
+use petgraph::graph::{DiGraph, EdgeIndex, NodeIndex};
+use petgraph::visit::EdgeRef;
+use rayon::prelude::*;
+use rstar::primitives::GeomWithData;
+use rstar::RTree;
+use serde::Deserialize;
+use std::cmp::Reverse;
+use std::collections::hash_map::Entry;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, OnceLock};
-use tokio::sync::RwLock;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
-/// In-memory cache of road networks, keyed by bbox cache key.
-/// First request downloads, subsequent requests reuse the cached network.
-static NETWORK_CACHE: OnceLock<RwLock<HashMap<String, Arc<RoadNetwork>>>> = OnceLock::new();
+/// Overpass API URL.
+const OVERPASS_URL: &str = "https://overpass-api.de/api/interpreter";
 
-fn network_cache() -> &'static RwLock<HashMap<String, Arc<RoadNetwork>>> {
-    NETWORK_CACHE.get_or_init(|| RwLock::new(HashMap::new()))
+/// Environment variable naming a local `.osm.pbf` or `.osm` extract to read
+/// road networks from instead of the Overpass API.
+pub const OSM_EXTRACT_ENV: &str = "OSM_EXTRACT";
+
+/// Highway types included in the road network.
+pub(crate) const ROUTABLE_HIGHWAYS: &[&str] = &[
+    "motorway",
+    "trunk",
+    "primary",
+    "secondary",
+    "tertiary",
+    "residential",
+    "unclassified",
+    "service",
+    "living_street",
+];
+
+/// Road class of edges whose highway type isn't in [`ROUTABLE_HIGHWAYS`].
+pub(crate) const UNKNOWN_ROAD_CLASS: u8 = u8::MAX;
+
+/// Speed multipliers per road class, e.g. for one bucket of a
+/// [`SpeedProfile`](crate::traffic::SpeedProfile).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpeedFactors {
+    /// `by_class[c]` multiplies the speed on roads of class `c` (the highway
+    /// type at index `c` of [`ROUTABLE_HIGHWAYS`]).
+    pub by_class: Vec<f64>,
+    /// Multiplier for roads of any other class.
+    pub default: f64,
 }
 
-/// Overpass API URL.
-const OVERPASS_URL: &str = "https://overpass-api.de/api/interpreter";
+impl SpeedFactors {
+    /// Every road at its free-flow speed.
+    pub const FREE_FLOW: SpeedFactors = SpeedFactors {
+        by_class: Vec::new(),
+        default: 1.0,
+    };
+
+    /// Speed multiplier for roads of `class`.
+    #[inline]
+    fn get(&self, class: u8) -> f64 {
+        self.by_class
+            .get(class as usize)
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
+
+static NETWORK_SOURCE: OnceLock<RoadNetworkSource> = OnceLock::new();
+
+/// Where road network data comes from.
+///
+/// # Examples
+///
+/// ```
+/// use vehicle_routing::routing::RoadNetworkSource;
+///
+/// let source = RoadNetworkSource::from_path(Some("/data/pennsylvania.osm.pbf".into()));
+/// assert!(matches!(source, RoadNetworkSource::Extract(_)));
+/// assert_eq!(RoadNetworkSource::from_path(None), RoadNetworkSource::Overpass);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RoadNetworkSource {
+    /// Download from the Overpass API.
+    Overpass,
+    /// Read a local `.osm.pbf` or `.osm` XML extract, clipped to the plan's
+    /// bounding box.
+    Extract(PathBuf),
+}
+
+impl RoadNetworkSource {
+    /// Uses the extract at `path` if given, otherwise the Overpass API.
+    pub fn from_path(path: Option<PathBuf>) -> Self {
+        match path {
+            Some(path) => RoadNetworkSource::Extract(path),
+            None => RoadNetworkSource::Overpass,
+        }
+    }
 
-/// Cache directory for downloaded OSM data.
-const CACHE_DIR: &str = ".osm_cache";
+    /// Reads the source from the [`OSM_EXTRACT_ENV`] environment variable.
+    pub fn from_env() -> Self {
+        Self::from_path(
+            std::env::var_os(OSM_EXTRACT_ENV)
+                .filter(|v| !v.is_empty())
+                .map(PathBuf::from),
+        )
+    }
+
+    /// Identifies the source in the road network cache.
+    pub fn tag(&self) -> String {
+        match self {
+            RoadNetworkSource::Overpass => "overpass".to_string(),
+            RoadNetworkSource::Extract(path) => format!(
+                "extract:{}",
+                path.file_name()
+                    .map(|n| n.to_string_lossy())
+                    .unwrap_or_default()
+            ),
+        }
+    }
+
+    /// Cache key for a bounding box read from this source.
+    fn cache_key(&self, bbox: &BoundingBox) -> String {
+        let tag: String = self
+            .tag()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect();
+        format!("{}_{}", tag, bbox.cache_key())
+    }
+}
+
+/// Sets the process-wide road network source.
+///
+/// Returns `false` if a source was already configured (or already read from
+/// the environment by a previous load).
+pub fn set_network_source(source: RoadNetworkSource) -> bool {
+    NETWORK_SOURCE.set(source).is_ok()
+}
+
+/// Returns the process-wide road network source, defaulting to
+/// [`RoadNetworkSource::from_env`].
+pub fn network_source() -> &'static RoadNetworkSource {
+    NETWORK_SOURCE.get_or_init(RoadNetworkSource::from_env)
+}
 
 /// Default driving speed in m/s (50 km/h = 13.89 m/s).
 const DEFAULT_SPEED_MPS: f64 = 50.0 * 1000.0 / 3600.0;
 
+/// Locations farther than this from every road node are not snapped.
+pub const MAX_SNAP_DISTANCE_M: f64 = 1_000.0;
+
+/// Earth radius in meters.
+const EARTH_RADIUS_M: f64 = 6_371_000.0;
+
 /// Error type for routing operations.
 #[derive(Debug)]
 pub enum RoutingError {
@@ -98,6 +227,21 @@ impl BoundingBox {
         }
     }
 
+    /// Returns true if the point lies inside the bounding box.
+    pub fn contains(&self, lat: f64, lng: f64) -> bool {
+        (self.min_lat..=self.max_lat).contains(&lat) && (self.min_lng..=self.max_lng).contains(&lng)
+    }
+
+    /// Returns true if `other` lies entirely inside this bounding box.
+    pub fn contains_box(&self, other: &BoundingBox) -> bool {
+        self.contains(other.min_lat, other.min_lng) && self.contains(other.max_lat, other.max_lng)
+    }
+
+    /// Area in square degrees (for picking the tightest cached network).
+    pub fn area(&self) -> f64 {
+        (self.max_lat - self.min_lat) * (self.max_lng - self.min_lng)
+    }
+
     /// Returns a cache key for this bounding box.
     fn cache_key(&self) -> String {
         format!(
@@ -121,8 +265,12 @@ struct EdgeData {
     travel_time_s: f64,
     /// Distance in meters.
     distance_m: f64,
-    /// Intermediate geometry points (for future full path reconstruction).
-    #[allow(dead_code)]
+    /// OSM way the edge belongs to, for turn restrictions.
+    way_id: i64,
+    /// Index of the highway type in [`ROUTABLE_HIGHWAYS`], or
+    /// [`UNKNOWN_ROAD_CLASS`], for speed profiles.
+    road_class: u8,
+    /// Shape points strictly between the edge's endpoints, in driving order.
     geometry: Vec<(f64, f64)>,
 }
 
@@ -137,12 +285,77 @@ pub struct RouteResult {
     pub geometry: Vec<(f64, f64)>,
 }
 
+/// All-pairs travel times and road distances between locations.
+#[derive(Debug, Clone, Default)]
+pub struct TravelMatrices {
+    /// `times[from][to]` in seconds.
+    pub times: Vec<Vec<i64>>,
+    /// `distances[from][to]` in meters.
+    pub distances: Vec<Vec<i64>>,
+    /// Locations too far from any road to snap; their rows and columns use
+    /// haversine estimates.
+    pub unsnapped: Vec<usize>,
+}
+
+/// Route geometries keyed by `(from_idx, to_idx)`.
+pub type Geometries = HashMap<(usize, usize), Vec<(f64, f64)>>;
+
+/// Turn restrictions for traffic arriving at a node on a given way.
+#[derive(Debug, Clone, Default)]
+struct TurnRule {
+    /// Ways that may not be entered (`no_*` restrictions).
+    forbidden: HashSet<i64>,
+    /// The only way that may be entered (`only_*` restrictions).
+    only: Option<i64>,
+}
+
+/// Settled node of a one-to-many search.
+#[derive(Debug, Clone, Copy)]
+struct Label {
+    /// Travel time from the source in seconds.
+    time: f64,
+    /// Distance along the fastest path in meters.
+    distance: f64,
+    /// Last edge on the fastest path (`None` at the source).
+    edge: Option<EdgeIndex>,
+}
+
+/// Search tree of a one-to-many search.
+///
+/// The search runs over edges so turn restrictions can depend on the way a
+/// node was entered from; `preds` links each settled edge to the edge before it.
+#[derive(Debug, Default)]
+struct SearchTree {
+    nodes: HashMap<NodeIndex, Label>,
+    preds: HashMap<EdgeIndex, Option<EdgeIndex>>,
+}
+
+/// Tentative arrival time and predecessor of each edge reached by a search.
+type Tentative = HashMap<EdgeIndex, (f64, Option<EdgeIndex>)>;
+
+/// Search queue ordered by (time, distance).
+type SearchHeap = BinaryHeap<Reverse<(OrderedFloat<f64>, OrderedFloat<f64>, EdgeIndex)>>;
+
+/// One source row of the all-pairs computation.
+struct RowResult {
+    times: Vec<i64>,
+    distances: Vec<i64>,
+    geometries: Vec<(usize, Vec<(f64, f64)>)>,
+}
+
+/// Spatial index entry: node position on the unit sphere.
+type IndexedNode = GeomWithData<[f64; 3], NodeIndex>;
+
 /// Road network graph built from OSM data.
 pub struct RoadNetwork {
     /// Directed graph with travel times as edge weights.
     graph: DiGraph<NodeData, EdgeData>,
     /// Map from (lat_e7, lng_e7) to node index.
     coord_to_node: HashMap<(i64, i64), NodeIndex>,
+    /// R-tree over all nodes for nearest-node snapping.
+    spatial_index: RTree<IndexedNode>,
+    /// Turn restrictions keyed by (from way, via node).
+    restrictions: HashMap<(i64, NodeIndex), TurnRule>,
 }
 
 impl RoadNetwork {
@@ -151,87 +364,140 @@ impl RoadNetwork {
         Self {
             graph: DiGraph::new(),
             coord_to_node: HashMap::new(),
+            spatial_index: RTree::new(),
+            restrictions: HashMap::new(),
         }
     }
 
+    /// Rebuilds the spatial index from the graph's nodes.
+    ///
+    /// Must be called once the graph is complete.
+    fn rebuild_spatial_index(&mut self) {
+        let entries = self
+            .graph
+            .node_indices()
+            .filter_map(|idx| {
+                let node = self.graph.node_weight(idx)?;
+                Some(IndexedNode::new(unit_vector(node.lat, node.lng), idx))
+            })
+            .collect();
+        self.spatial_index = RTree::bulk_load(entries);
+    }
+
     /// Loads or fetches road network for a bounding box.
     ///
     /// Uses three-tier caching:
     /// 1. In-memory cache (instant, per-process)
     /// 2. File cache (fast, persists across restarts)
-    /// 3. Overpass API download (slow, ~5-30s)
+    /// 3. Overpass API download (slow, ~5-30s) or local extract import,
+    ///    depending on [`network_source`]
+    ///
+    /// Both caches reuse any network from the same source whose bounding box
+    /// contains `bbox`, so nearby plans share one download.
     ///
     /// Thread-safe: concurrent requests for the same bbox will wait for
     /// the first download to complete rather than downloading multiple times.
     pub async fn load_or_fetch(bbox: &BoundingBox) -> Result<Arc<Self>, RoutingError> {
-        let cache_key = bbox.cache_key();
+        let source = network_source();
+        let tag = source.tag();
 
         // 1. Check in-memory cache (fast path, read lock)
         {
-            let cache = network_cache().read().await;
-            if let Some(network) = cache.get(&cache_key) {
-                info!("Using in-memory cached road network for {}", cache_key);
-                return Ok(Arc::clone(network));
+            let cache = road_cache::memory_cache().read().await;
+            if let Some((key, network)) = cache.find(&tag, bbox) {
+                info!("Using in-memory cached road network {}", key);
+                metrics::global().record_cache_lookup(CacheLookup::Memory);
+                return Ok(network);
             }
         }
 
         // 2. Acquire write lock and double-check (another request may have loaded it)
-        let mut cache = network_cache().write().await;
-        if let Some(network) = cache.get(&cache_key) {
-            info!("Using in-memory cached road network for {}", cache_key);
-            return Ok(Arc::clone(network));
+        let mut cache = road_cache::memory_cache().write().await;
+        if let Some((key, network)) = cache.find(&tag, bbox) {
+            info!("Using in-memory cached road network {}", key);
+            metrics::global().record_cache_lookup(CacheLookup::Memory);
+            return Ok(network);
         }
 
         // 3. Try loading from file cache
-        tokio::fs::create_dir_all(CACHE_DIR).await?;
-        let cache_path = Path::new(CACHE_DIR).join(format!("{}.json", cache_key));
-
-        let network = if tokio::fs::try_exists(&cache_path).await.unwrap_or(false) {
-            info!("Loading road network from file cache: {:?}", cache_path);
-            match Self::load_from_cache(&cache_path).await {
-                Ok(n) => n,
-                Err(e) => {
-                    // File cache failed (corrupted/old version), download fresh
-                    info!("File cache invalid ({}), downloading fresh", e);
-                    let n = Self::from_bbox(bbox).await?;
-                    n.save_to_cache(&cache_path).await?;
-                    info!("Saved road network to file cache: {:?}", cache_path);
-                    n
+        let (key, covered, network) = match road_cache::load_from_disk(&tag, bbox).await {
+            Some(found) => {
+                metrics::global().record_cache_lookup(CacheLookup::Disk);
+                found
+            }
+            None => {
+                metrics::global().record_cache_lookup(CacheLookup::Miss);
+                // 4. Download from Overpass API or read the local extract
+                let key = source.cache_key(bbox);
+                let network = Self::from_source(source, bbox).await?;
+                if let Err(e) = road_cache::save_to_disk(&key, &tag, bbox, &network).await {
+                    warn!("Could not save road network to file cache: {}", e);
                 }
+                (key, *bbox, network)
             }
-        } else {
-            // 4. Download from Overpass API
-            info!("Downloading road network from Overpass API");
-            let n = Self::from_bbox(bbox).await?;
-            n.save_to_cache(&cache_path).await?;
-            info!("Saved road network to file cache: {:?}", cache_path);
-            n
         };
 
         // Store in memory cache
         let network = Arc::new(network);
-        cache.insert(cache_key, Arc::clone(&network));
+        cache.insert(key, &tag, covered, Arc::clone(&network));
 
         Ok(network)
     }
 
+    /// Builds the road network for a bounding box from the given source.
+    pub async fn from_source(
+        source: &RoadNetworkSource,
+        bbox: &BoundingBox,
+    ) -> Result<Self, RoutingError> {
+        match source {
+            RoadNetworkSource::Overpass => {
+                info!("Downloading road network from Overpass API");
+                Self::from_bbox(bbox).await
+            }
+            RoadNetworkSource::Extract(path) => {
+                info!("Reading road network from OSM extract {:?}", path);
+                Self::from_extract(path, bbox).await
+            }
+        }
+    }
+
+    /// Builds road network from a local `.osm.pbf` or `.osm` XML extract,
+    /// keeping only roads inside the bounding box.
+    pub async fn from_extract(path: &Path, bbox: &BoundingBox) -> Result<Self, RoutingError> {
+        let path = path.to_path_buf();
+        let bbox = *bbox;
+        let osm_data =
+            tokio::task::spawn_blocking(move || crate::osm_import::read_extract(&path, &bbox))
+                .await
+                .map_err(|e| RoutingError::Parse(e.to_string()))??;
+
+        info!("Read {} OSM elements from extract", osm_data.elements.len());
+
+        Self::build_from_osm(&osm_data)
+    }
+
     /// Downloads and builds road network from Overpass API.
     pub async fn from_bbox(bbox: &BoundingBox) -> Result<Self, RoutingError> {
         let query = format!(
             r#"[out:json][timeout:120];
-(
-  way["highway"~"^(motorway|trunk|primary|secondary|tertiary|residential|unclassified|service|living_street)$"]
-    ({},{},{},{});
-);
-(._;>;);
+way["highway"~"^({})$"]
+  ({},{},{},{})->.roads;
+rel(bw.roads)["type"="restriction"]->.restrictions;
+(.roads; node(w.roads); .restrictions;);
 out body;"#,
-            bbox.min_lat, bbox.min_lng, bbox.max_lat, bbox.max_lng
+            ROUTABLE_HIGHWAYS.join("|"),
+            bbox.min_lat,
+            bbox.min_lng,
+            bbox.max_lat,
+            bbox.max_lng
         );
 
         debug!("Overpass query:\n{}", query);
 
-        info!("Preparing Overpass query for bbox: {:.4},{:.4} to {:.4},{:.4}",
-            bbox.min_lat, bbox.min_lng, bbox.max_lat, bbox.max_lng);
+        info!(
+            "Preparing Overpass query for bbox: {:.4},{:.4} to {:.4},{:.4}",
+            bbox.min_lat, bbox.min_lng, bbox.max_lat, bbox.max_lng
+        );
 
         let client = reqwest::Client::builder()
             .connect_timeout(std::time::Duration::from_secs(30))
@@ -268,16 +534,13 @@ out body;"#,
             .await
             .map_err(|e| RoutingError::Parse(e.to_string()))?;
 
-        info!(
-            "Downloaded {} OSM elements",
-            osm_data.elements.len()
-        );
+        info!("Downloaded {} OSM elements", osm_data.elements.len());
 
         Self::build_from_osm(&osm_data)
     }
 
     /// Builds the road network from parsed OSM data.
-    fn build_from_osm(osm: &OverpassResponse) -> Result<Self, RoutingError> {
+    pub(crate) fn build_from_osm(osm: &OverpassResponse) -> Result<Self, RoutingError> {
         let mut network = Self::new();
 
         // First pass: collect all nodes
@@ -294,64 +557,134 @@ out body;"#,
 
         // Second pass: process ways and build graph
         let mut way_count = 0;
+        let mut inaccessible = 0;
         for elem in &osm.elements {
-            if elem.elem_type == "way" {
-                if let Some(ref node_ids) = elem.nodes {
-                    let highway = elem.tags.as_ref().and_then(|t| t.highway.as_deref());
-                    let oneway = elem.tags.as_ref().and_then(|t| t.oneway.as_deref());
-                    let speed = get_speed_for_highway(highway.unwrap_or("residential"));
-                    let is_oneway = matches!(oneway, Some("yes") | Some("1"));
-
-                    // Process consecutive node pairs
-                    for window in node_ids.windows(2) {
-                        let n1_id = window[0];
-                        let n2_id = window[1];
-
-                        let Some(&(lat1, lng1)) = nodes.get(&n1_id) else {
-                            continue;
-                        };
-                        let Some(&(lat2, lng2)) = nodes.get(&n2_id) else {
-                            continue;
-                        };
-
-                        // Get or create node indices
-                        let idx1 = network.get_or_create_node(lat1, lng1);
-                        let idx2 = network.get_or_create_node(lat2, lng2);
-
-                        // Calculate edge properties
-                        let distance = haversine_distance(lat1, lng1, lat2, lng2);
-                        let travel_time = distance / speed;
-
-                        let edge_data = EdgeData {
-                            travel_time_s: travel_time,
-                            distance_m: distance,
-                            geometry: vec![(lat1, lng1), (lat2, lng2)],
-                        };
+            if elem.elem_type != "way" {
+                continue;
+            }
+            let Some(ref node_ids) = elem.nodes else {
+                continue;
+            };
+            let tags = elem.tags.as_ref();
+            if !tags.is_none_or(is_accessible) {
+                inaccessible += 1;
+                continue;
+            }
+            let speed = get_speed_for_way(tags);
+            let direction = way_direction(tags);
+            let road_class = road_class(tags.and_then(|t| t.highway.as_deref()));
+
+            // Every OSM node stays a graph node, so locations can snap
+            // anywhere along the way
+            for window in node_ids.windows(2) {
+                let (Some(&(lat1, lng1)), Some(&(lat2, lng2))) =
+                    (nodes.get(&window[0]), nodes.get(&window[1]))
+                else {
+                    continue;
+                };
+                let from = network.get_or_create_node(lat1, lng1);
+                let to = network.get_or_create_node(lat2, lng2);
+                let distance = haversine_distance(lat1, lng1, lat2, lng2);
+                let edge_data = EdgeData {
+                    travel_time_s: distance / speed,
+                    distance_m: distance,
+                    way_id: elem.id,
+                    road_class,
+                    geometry: Vec::new(),
+                };
+
+                if direction != Direction::Backward {
+                    network.graph.add_edge(from, to, edge_data.clone());
+                }
+                if direction != Direction::Forward {
+                    network.graph.add_edge(to, from, edge_data);
+                }
+            }
 
-                        // Add forward edge
-                        network.graph.add_edge(idx1, idx2, edge_data.clone());
+            way_count += 1;
+        }
 
-                        // Add reverse edge if not oneway
-                        if !is_oneway {
-                            network.graph.add_edge(idx2, idx1, edge_data);
-                        }
-                    }
+        if inaccessible > 0 {
+            debug!("Skipped {} ways closed to motor vehicles", inaccessible);
+        }
 
-                    way_count += 1;
-                }
+        // Third pass: turn restrictions (only those with a via node)
+        let mut restriction_count = 0;
+        for elem in &osm.elements {
+            if elem.elem_type != "relation" {
+                continue;
             }
+            let Some(tags) = elem.tags.as_ref() else {
+                continue;
+            };
+            let (Some("restriction"), Some(kind), Some(members)) = (
+                tags.relation_type.as_deref(),
+                tags.restriction.as_deref(),
+                elem.members.as_ref(),
+            ) else {
+                continue;
+            };
+            let member = |role: &str, member_type: &str| {
+                members
+                    .iter()
+                    .find(|m| m.role == role && m.member_type == member_type)
+                    .map(|m| m.member_ref)
+            };
+            let (Some(from_way), Some(via), Some(to_way)) = (
+                member("from", "way"),
+                member("via", "node"),
+                member("to", "way"),
+            ) else {
+                continue;
+            };
+            let Some(via) = nodes
+                .get(&via)
+                .and_then(|&(lat, lng)| network.coord_to_node.get(&coord_key(lat, lng)).copied())
+            else {
+                continue;
+            };
+            let only = if kind.starts_with("only_") {
+                true
+            } else if kind.starts_with("no_") {
+                false
+            } else {
+                continue;
+            };
+            network.add_restriction(from_way, via, to_way, only);
+            restriction_count += 1;
         }
 
         info!(
-            "Built graph with {} nodes and {} edges from {} ways",
+            "Built graph with {} nodes and {} edges from {} ways ({} turn restrictions)",
             network.graph.node_count(),
             network.graph.edge_count(),
-            way_count
+            way_count,
+            restriction_count
         );
 
+        network.rebuild_spatial_index();
         Ok(network)
     }
 
+    /// Records a turn restriction from `from_way` into `to_way` at `via`:
+    /// an `only_*` restriction if `only`, otherwise a `no_*` one.
+    fn add_restriction(&mut self, from_way: i64, via: NodeIndex, to_way: i64, only: bool) {
+        let rule = self.restrictions.entry((from_way, via)).or_default();
+        if only {
+            rule.only = Some(to_way);
+        } else {
+            rule.forbidden.insert(to_way);
+        }
+    }
+
+    /// Whether a vehicle arriving at `via` on `from_way` may continue onto
+    /// `to_way`.
+    fn turn_allowed(&self, from_way: i64, via: NodeIndex, to_way: i64) -> bool {
+        self.restrictions.get(&(from_way, via)).is_none_or(|rule| {
+            !rule.forbidden.contains(&to_way) && rule.only.is_none_or(|only| only == to_way)
+        })
+    }
+
     /// Gets or creates a node for the given coordinates.
     fn get_or_create_node(&mut self, lat: f64, lng: f64) -> NodeIndex {
         let key = coord_key(lat, lng);
@@ -365,15 +698,25 @@ out body;"#,
     }
 
     /// Finds the nearest road node to the given coordinates.
+    ///
+    /// Returns `None` if the network is empty or the nearest node is more
+    /// than [`MAX_SNAP_DISTANCE_M`] away.
     pub fn snap_to_road(&self, lat: f64, lng: f64) -> Option<NodeIndex> {
-        self.coord_to_node
-            .iter()
-            .min_by_key(|((lat_e7, lng_e7), _)| {
-                let node_lat = *lat_e7 as f64 / 1e7;
-                let node_lng = *lng_e7 as f64 / 1e7;
-                OrderedFloat(haversine_distance(lat, lng, node_lat, node_lng))
-            })
-            .map(|(_, &idx)| idx)
+        self.snap_within(lat, lng, MAX_SNAP_DISTANCE_M)
+    }
+
+    /// Finds the nearest road node no more than `max_distance_m` away.
+    ///
+    /// Uses the spatial index, so snapping is logarithmic in the number of
+    /// nodes. Chord length on the unit sphere grows monotonically with
+    /// great-circle distance, so the nearest indexed node is also the
+    /// nearest by haversine.
+    pub fn snap_within(&self, lat: f64, lng: f64, max_distance_m: f64) -> Option<NodeIndex> {
+        let nearest = self
+            .spatial_index
+            .nearest_neighbor(&unit_vector(lat, lng))?;
+        let node = self.graph.node_weight(nearest.data)?;
+        (haversine_distance(lat, lng, node.lat, node.lng) <= max_distance_m).then_some(nearest.data)
     }
 
     /// Computes shortest path between two coordinates.
@@ -391,37 +734,13 @@ out body;"#,
             });
         }
 
-        // Use A* with zero heuristic (equivalent to Dijkstra, but returns full path)
-        let (cost, path) = astar(
-            &self.graph,
-            start,
-            |n| n == end,
-            |e| OrderedFloat(e.weight().travel_time_s),
-            |_| OrderedFloat(0.0),
-        )?;
-
-        let total_time = cost.0;
-
-        // Build geometry from path nodes
-        let geometry: Vec<(f64, f64)> = path
-            .iter()
-            .filter_map(|&idx| self.graph.node_weight(idx).map(|n| (n.lat, n.lng)))
-            .collect();
-
-        // Sum actual edge distances along the path
-        let mut distance = 0.0;
-        for window in path.windows(2) {
-            if let Some(edge) = self.graph.find_edge(window[0], window[1]) {
-                if let Some(weight) = self.graph.edge_weight(edge) {
-                    distance += weight.distance_m;
-                }
-            }
-        }
+        let tree = self.search_from(start, &HashSet::from([end]), &SpeedFactors::FREE_FLOW);
+        let label = tree.nodes.get(&end)?;
 
         Some(RouteResult {
-            duration_seconds: total_time.round() as i64,
-            distance_meters: distance,
-            geometry,
+            duration_seconds: label.time.round() as i64,
+            distance_meters: label.distance,
+            geometry: self.path_coords(&tree, end),
         })
     }
 
@@ -437,8 +756,8 @@ out body;"#,
 
     /// Computes route geometries with row-level progress callback.
     ///
-    /// The callback receives `(completed_row, total_rows)` after each source row is computed.
-    /// For n locations, this computes n*(n-1) routes, calling the callback n times.
+    /// The callback receives `(completed_rows - 1, total_rows)` after each source row is computed.
+    /// For n locations, this runs n one-to-many searches, calling the callback n times.
     ///
     /// # Example
     ///
@@ -456,28 +775,13 @@ out body;"#,
     pub fn compute_all_geometries_with_progress<F>(
         &self,
         locations: &[(f64, f64)],
-        mut on_row_complete: F,
+        on_row_complete: F,
     ) -> HashMap<(usize, usize), Vec<(f64, f64)>>
     where
-        F: FnMut(usize, usize),
+        F: FnMut(usize, usize) + Send,
     {
-        let n = locations.len();
-        let mut geometries = HashMap::new();
-
-        for i in 0..n {
-            for j in 0..n {
-                if i == j {
-                    continue;
-                }
-                if let Some(result) = self.route(locations[i], locations[j]) {
-                    geometries.insert((i, j), result.geometry);
-                }
-            }
-            // Report progress after each source row
-            on_row_complete(i, n);
-        }
-
-        geometries
+        self.compute_rows(locations, true, &SpeedFactors::FREE_FLOW, on_row_complete)
+            .1
     }
 
     /// Computes all-pairs travel time matrix for given locations.
@@ -489,7 +793,7 @@ out body;"#,
 
     /// Computes all-pairs travel time matrix with row-level progress callback.
     ///
-    /// The callback receives `(completed_row, total_rows)` after each row is computed.
+    /// The callback receives `(completed_rows - 1, total_rows)` after each row is computed.
     /// This enables progress reporting during the O(n) Dijkstra runs.
     ///
     /// # Example
@@ -509,68 +813,332 @@ out body;"#,
     pub fn compute_matrix_with_progress<F>(
         &self,
         locations: &[(f64, f64)],
-        mut on_row_complete: F,
+        on_row_complete: F,
     ) -> Vec<Vec<i64>>
     where
-        F: FnMut(usize, usize),
+        F: FnMut(usize, usize) + Send,
+    {
+        self.compute_matrices_with_progress(locations, on_row_complete)
+            .times
+    }
+
+    /// Computes all-pairs travel time and distance matrices.
+    pub fn compute_matrices(&self, locations: &[(f64, f64)]) -> TravelMatrices {
+        self.compute_matrices_with_progress(locations, |_, _| {})
+    }
+
+    /// Computes all-pairs travel time and distance matrices with row-level
+    /// progress callback.
+    ///
+    /// Distances are measured along the fastest path, so both matrices
+    /// describe the same routes. Pairs without a road connection fall back
+    /// to haversine estimates.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use vehicle_routing::routing::RoadNetwork;
+    /// let network = RoadNetwork::new();
+    /// let locations = vec![(39.95, -75.16), (39.96, -75.17)];
+    /// let matrices = network.compute_matrices_with_progress(&locations, |_, _| {});
+    ///
+    /// // Empty network: haversine fallback (~1.4 km)
+    /// assert!(matrices.distances[0][1] > 1_000 && matrices.distances[0][1] < 2_000);
+    /// assert_eq!(matrices.distances[1][1], 0);
+    /// assert!(matrices.times[0][1] > 0);
+    /// ```
+    pub fn compute_matrices_with_progress<F>(
+        &self,
+        locations: &[(f64, f64)],
+        on_row_complete: F,
+    ) -> TravelMatrices
+    where
+        F: FnMut(usize, usize) + Send,
+    {
+        self.compute_rows(locations, false, &SpeedFactors::FREE_FLOW, on_row_complete)
+            .0
+    }
+
+    /// Computes all-pairs matrices with driving speeds scaled per road class,
+    /// e.g. for one bucket of a [`SpeedProfile`](crate::traffic::SpeedProfile).
+    ///
+    /// Roads of a class without its own factor use `speed_factors.default`.
+    pub fn compute_matrices_with_speed_factors(
+        &self,
+        locations: &[(f64, f64)],
+        speed_factors: &SpeedFactors,
+    ) -> TravelMatrices {
+        self.compute_rows(locations, false, speed_factors, |_, _| {})
+            .0
+    }
+
+    /// Computes matrices and route geometries together.
+    ///
+    /// Geometries are read from the same search trees as the matrices, so
+    /// this costs barely more than [`compute_matrices`](Self::compute_matrices).
+    pub fn compute_routes(&self, locations: &[(f64, f64)]) -> (TravelMatrices, Geometries) {
+        self.compute_routes_with_progress(locations, |_, _| {})
+    }
+
+    /// Computes matrices and route geometries with row-level progress callback.
+    pub fn compute_routes_with_progress<F>(
+        &self,
+        locations: &[(f64, f64)],
+        on_row_complete: F,
+    ) -> (TravelMatrices, Geometries)
+    where
+        F: FnMut(usize, usize) + Send,
+    {
+        self.compute_rows(locations, true, &SpeedFactors::FREE_FLOW, on_row_complete)
+    }
+
+    /// Runs one search per location in parallel and assembles the matrices
+    /// (and geometries, if requested).
+    ///
+    /// Rows finish in any order; the callback is serialized and receives the
+    /// number of completed rows minus one.
+    fn compute_rows<F>(
+        &self,
+        locations: &[(f64, f64)],
+        with_geometry: bool,
+        speed_factors: &SpeedFactors,
+        on_row_complete: F,
+    ) -> (TravelMatrices, Geometries)
+    where
+        F: FnMut(usize, usize) + Send,
     {
         let n = locations.len();
-        let mut matrix = vec![vec![0i64; n]; n];
 
         // Snap all locations to nodes
         let nodes: Vec<Option<NodeIndex>> = locations
             .iter()
             .map(|&(lat, lng)| self.snap_to_road(lat, lng))
             .collect();
+        let unsnapped: Vec<usize> = (0..n).filter(|&i| nodes[i].is_none()).collect();
+        if !unsnapped.is_empty() && self.node_count() > 0 {
+            warn!(
+                "{} of {} locations are more than {} m from any road: {:?}",
+                unsnapped.len(),
+                n,
+                MAX_SNAP_DISTANCE_M,
+                unsnapped
+            );
+        }
+        let targets: HashSet<NodeIndex> = nodes.iter().flatten().copied().collect();
+        let progress = Mutex::new((0, on_row_complete));
+
+        let rows: Vec<RowResult> = (0..n)
+            .into_par_iter()
+            .map(|i| {
+                let row =
+                    self.compute_row(i, locations, &nodes, &targets, with_geometry, speed_factors);
+
+                // Report progress after each row
+                let mut guard = progress.lock();
+                let (completed, on_row_complete) = &mut *guard;
+                on_row_complete(*completed, n);
+                *completed += 1;
+
+                row
+            })
+            .collect();
 
-        // Compute travel times row by row
-        for i in 0..n {
-            if let Some(from_node) = nodes[i] {
-                // Run Dijkstra from this node
-                let costs = dijkstra(&self.graph, from_node, None, |e| {
-                    OrderedFloat(e.weight().travel_time_s)
-                });
+        let mut times = Vec::with_capacity(n);
+        let mut distances = Vec::with_capacity(n);
+        let mut geometries = HashMap::new();
+        for (i, row) in rows.into_iter().enumerate() {
+            times.push(row.times);
+            distances.push(row.distances);
+            geometries.extend(row.geometries.into_iter().map(|(j, g)| ((i, j), g)));
+        }
 
-                for j in 0..n {
-                    if i == j {
-                        continue;
-                    }
-                    if let Some(to_node) = nodes[j] {
-                        if let Some(cost) = costs.get(&to_node) {
-                            matrix[i][j] = cost.0.round() as i64;
+        (
+            TravelMatrices {
+                times,
+                distances,
+                unsnapped,
+            },
+            geometries,
+        )
+    }
+
+    /// Computes one source row: times, distances and optional geometries.
+    fn compute_row(
+        &self,
+        i: usize,
+        locations: &[(f64, f64)],
+        nodes: &[Option<NodeIndex>],
+        targets: &HashSet<NodeIndex>,
+        with_geometry: bool,
+        speed_factors: &SpeedFactors,
+    ) -> RowResult {
+        let n = locations.len();
+        let mut row = RowResult {
+            times: vec![0; n],
+            distances: vec![0; n],
+            geometries: Vec::new(),
+        };
+
+        // Search from this node (empty if it couldn't be snapped)
+        let tree = nodes[i]
+            .map(|from_node| self.search_from(from_node, targets, speed_factors))
+            .unwrap_or_default();
+
+        for j in 0..n {
+            if i == j {
+                continue;
+            }
+            match nodes[j]
+                .and_then(|to_node| tree.nodes.get(&to_node).map(|label| (to_node, label)))
+            {
+                Some((to_node, label)) => {
+                    row.times[j] = label.time.round() as i64;
+                    row.distances[j] = label.distance.round() as i64;
+                    if with_geometry {
+                        let geometry = if Some(to_node) == nodes[i] {
+                            vec![locations[i], locations[j]]
                         } else {
-                            // No route found, use haversine estimate
-                            let dist = haversine_distance(
-                                locations[i].0,
-                                locations[i].1,
-                                locations[j].0,
-                                locations[j].1,
-                            );
-                            matrix[i][j] = (dist / DEFAULT_SPEED_MPS).round() as i64;
-                        }
+                            self.path_coords(&tree, to_node)
+                        };
+                        row.geometries.push((j, geometry));
                     }
                 }
-            } else {
-                // Location couldn't be snapped, use haversine for all
-                for j in 0..n {
-                    if i == j {
-                        continue;
-                    }
+                None => {
+                    // No route found, use haversine estimate
                     let dist = haversine_distance(
                         locations[i].0,
                         locations[i].1,
                         locations[j].0,
                         locations[j].1,
                     );
-                    matrix[i][j] = (dist / DEFAULT_SPEED_MPS).round() as i64;
+                    row.times[j] = (dist / DEFAULT_SPEED_MPS).round() as i64;
+                    row.distances[j] = dist.round() as i64;
                 }
             }
+        }
 
-            // Report progress after each row
-            on_row_complete(i, n);
+        row
+    }
+
+    /// Dijkstra on travel time that also accumulates distance along the
+    /// fastest path and records predecessors.
+    ///
+    /// Runs over edges rather than nodes so turn restrictions can be honored.
+    /// Stops as soon as every node in `targets` is settled. Travel times are
+    /// divided by the road class's speed factor.
+    fn search_from(
+        &self,
+        from: NodeIndex,
+        targets: &HashSet<NodeIndex>,
+        speed_factors: &SpeedFactors,
+    ) -> SearchTree {
+        let mut tree = SearchTree::default();
+        let mut tentative = Tentative::new();
+        let mut heap = BinaryHeap::new();
+        let mut remaining = targets.len();
+
+        let root = Label {
+            time: 0.0,
+            distance: 0.0,
+            edge: None,
+        };
+        tree.nodes.insert(from, root);
+        if targets.contains(&from) {
+            remaining -= 1;
+            if remaining == 0 {
+                return tree;
+            }
         }
+        self.relax(from, root, speed_factors, &mut tentative, &mut heap);
 
-        matrix
+        while let Some(Reverse((OrderedFloat(time), OrderedFloat(distance), edge))) = heap.pop() {
+            if tree.preds.contains_key(&edge) {
+                continue;
+            }
+            tree.preds.insert(edge, tentative[&edge].1);
+
+            let Some((_, node)) = self.graph.edge_endpoints(edge) else {
+                continue;
+            };
+            let label = Label {
+                time,
+                distance,
+                edge: Some(edge),
+            };
+            // The first settled edge into a node gives its fastest arrival
+            if let Entry::Vacant(entry) = tree.nodes.entry(node) {
+                entry.insert(label);
+                if targets.contains(&node) {
+                    remaining -= 1;
+                    if remaining == 0 {
+                        break;
+                    }
+                }
+            }
+            self.relax(node, label, speed_factors, &mut tentative, &mut heap);
+        }
+
+        tree
+    }
+
+    /// Pushes the edges leaving `node` that the turn restrictions allow
+    /// after arriving via `label.edge`.
+    fn relax(
+        &self,
+        node: NodeIndex,
+        label: Label,
+        speed_factors: &SpeedFactors,
+        tentative: &mut Tentative,
+        heap: &mut SearchHeap,
+    ) {
+        let from_way = label.edge.map(|e| self.graph[e].way_id);
+        for edge in self.graph.edges(node) {
+            let weight = edge.weight();
+            if from_way.is_some_and(|way| !self.turn_allowed(way, node, weight.way_id)) {
+                continue;
+            }
+            let next_time =
+                label.time + weight.travel_time_s / speed_factors.get(weight.road_class);
+            let next_distance = label.distance + weight.distance_m;
+            if tentative
+                .get(&edge.id())
+                .is_none_or(|&(t, _)| next_time < t)
+            {
+                tentative.insert(edge.id(), (next_time, label.edge));
+                heap.push(Reverse((
+                    OrderedFloat(next_time),
+                    OrderedFloat(next_distance),
+                    edge.id(),
+                )));
+            }
+        }
+    }
+
+    /// Coordinates of the path from the search root to `to`, including the
+    /// shape points of every edge.
+    fn path_coords(&self, tree: &SearchTree, to: NodeIndex) -> Vec<(f64, f64)> {
+        let mut edges = Vec::new();
+        let mut edge = tree.nodes.get(&to).and_then(|l| l.edge);
+        while let Some(e) = edge {
+            edges.push(e);
+            edge = tree.preds.get(&e).copied().flatten();
+        }
+
+        let coord = |idx: NodeIndex| self.graph.node_weight(idx).map(|n| (n.lat, n.lng));
+        let root = edges
+            .last()
+            .and_then(|&e| self.graph.edge_endpoints(e))
+            .map_or(to, |(source, _)| source);
+        let mut coords: Vec<(f64, f64)> = coord(root).into_iter().collect();
+        for &e in edges.iter().rev() {
+            if let (Some(weight), Some((_, target))) =
+                (self.graph.edge_weight(e), self.graph.edge_endpoints(e))
+            {
+                coords.extend_from_slice(&weight.geometry);
+                coords.extend(coord(target));
+            }
+        }
+        coords
     }
 
     /// Returns the number of nodes in the graph.
@@ -583,33 +1151,10 @@ out body;"#,
         self.graph.edge_count()
     }
 
-    /// Loads road network from cache file.
-    async fn load_from_cache(path: &Path) -> Result<Self, RoutingError> {
-        let data = tokio::fs::read_to_string(path).await?;
-
-        // Parse cached data, handling corrupted files
-        let cached: CachedNetwork = match serde_json::from_str(&data) {
-            Ok(c) => c,
-            Err(e) => {
-                info!("Cache file corrupted, will re-download: {}", e);
-                let _ = tokio::fs::remove_file(path).await;
-                return Err(RoutingError::Parse(e.to_string()));
-            }
-        };
-
-        // Check version - delete old format and re-download
-        if cached.version != CACHE_VERSION {
-            info!(
-                "Cache version mismatch (got {}, need {}), will re-download",
-                cached.version, CACHE_VERSION
-            );
-            let _ = tokio::fs::remove_file(path).await;
-            return Err(RoutingError::Parse("cache version mismatch".into()));
-        }
-
+    /// Rebuilds a network from cached node and edge arrays.
+    pub(crate) fn from_cached(cached: &CachedNetwork) -> Self {
         let mut network = Self::new();
 
-        // Rebuild graph from cached data
         for node in &cached.nodes {
             let idx = network.graph.add_node(NodeData {
                 lat: node.lat,
@@ -628,16 +1173,23 @@ out body;"#,
                 EdgeData {
                     travel_time_s: edge.travel_time_s,
                     distance_m: edge.distance_m,
-                    geometry: vec![],
+                    way_id: edge.way_id,
+                    road_class: edge.road_class,
+                    geometry: edge.shape.clone(),
                 },
             );
         }
 
-        Ok(network)
+        for r in &cached.restrictions {
+            network.add_restriction(r.from_way, NodeIndex::new(r.via), r.to_way, r.only);
+        }
+
+        network.rebuild_spatial_index();
+        network
     }
 
-    /// Saves road network to cache file.
-    async fn save_to_cache(&self, path: &Path) -> Result<(), RoutingError> {
+    /// Extracts node and edge arrays for the cache.
+    pub(crate) fn to_cached(&self) -> CachedNetwork {
         let nodes: Vec<CachedNode> = self
             .graph
             .node_indices()
@@ -660,19 +1212,37 @@ out body;"#,
                     to: to.index(),
                     travel_time_s: weight.travel_time_s,
                     distance_m: weight.distance_m,
+                    way_id: weight.way_id,
+                    road_class: weight.road_class,
+                    shape: weight.geometry.clone(),
                 })
             })
             .collect();
 
-        let cached = CachedNetwork {
-            version: CACHE_VERSION,
+        let mut restrictions: Vec<CachedRestriction> = self
+            .restrictions
+            .iter()
+            .flat_map(|(&(from_way, via), rule)| {
+                let forbidden = rule.forbidden.iter().map(move |&to_way| (to_way, false));
+                let only = rule.only.map(|to_way| (to_way, true));
+                forbidden
+                    .chain(only)
+                    .map(move |(to_way, only)| CachedRestriction {
+                        from_way,
+                        via: via.index(),
+                        to_way,
+                        only,
+                    })
+            })
+            .collect();
+        // Deterministic file contents
+        restrictions.sort_by_key(|r| (r.from_way, r.via, r.to_way, r.only));
+
+        CachedNetwork {
             nodes,
             edges,
-        };
-        let data = serde_json::to_string(&cached).map_err(|e| RoutingError::Parse(e.to_string()))?;
-        tokio::fs::write(path, data).await?;
-
-        Ok(())
+            restrictions,
+        }
     }
 }
 
@@ -687,57 +1257,47 @@ impl Default for RoadNetwork {
 // ============================================================================
 
 #[derive(Debug, Deserialize)]
-struct OverpassResponse {
-    elements: Vec<OsmElement>,
+pub(crate) struct OverpassResponse {
+    pub(crate) elements: Vec<OsmElement>,
 }
 
 #[derive(Debug, Deserialize)]
-struct OsmElement {
+pub(crate) struct OsmElement {
     #[serde(rename = "type")]
-    elem_type: String,
-    id: i64,
-    lat: Option<f64>,
-    lon: Option<f64>,
-    nodes: Option<Vec<i64>>,
-    tags: Option<OsmTags>,
-}
-
-#[derive(Debug, Deserialize)]
-struct OsmTags {
-    highway: Option<String>,
-    oneway: Option<String>,
-    /// Maxspeed tag (for future use with dynamic speed calculation).
-    #[allow(dead_code)]
-    maxspeed: Option<String>,
-}
-
-// ============================================================================
-// Cache Data Structures
-// ============================================================================
-
-/// Cache format version. Bump this when changing the cache structure.
-const CACHE_VERSION: u32 = 1;
-
-#[derive(Debug, Serialize, Deserialize)]
-struct CachedNetwork {
-    /// Cache format version for automatic invalidation.
-    version: u32,
-    nodes: Vec<CachedNode>,
-    edges: Vec<CachedEdge>,
+    pub(crate) elem_type: String,
+    pub(crate) id: i64,
+    pub(crate) lat: Option<f64>,
+    pub(crate) lon: Option<f64>,
+    pub(crate) nodes: Option<Vec<i64>>,
+    pub(crate) members: Option<Vec<OsmMember>>,
+    pub(crate) tags: Option<OsmTags>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct CachedNode {
-    lat: f64,
-    lng: f64,
+/// Member of a relation.
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct OsmMember {
+    #[serde(rename = "type")]
+    pub(crate) member_type: String,
+    #[serde(rename = "ref")]
+    pub(crate) member_ref: i64,
+    pub(crate) role: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct CachedEdge {
-    from: usize,
-    to: usize,
-    travel_time_s: f64,
-    distance_m: f64,
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct OsmTags {
+    pub(crate) highway: Option<String>,
+    pub(crate) oneway: Option<String>,
+    pub(crate) junction: Option<String>,
+    pub(crate) maxspeed: Option<String>,
+    pub(crate) access: Option<String>,
+    pub(crate) vehicle: Option<String>,
+    pub(crate) motor_vehicle: Option<String>,
+    pub(crate) motorcar: Option<String>,
+    /// Relation type (`restriction` for turn restrictions).
+    #[serde(rename = "type")]
+    pub(crate) relation_type: Option<String>,
+    /// Turn restriction kind, e.g. `no_left_turn` or `only_straight_on`.
+    pub(crate) restriction: Option<String>,
 }
 
 // ============================================================================
@@ -766,20 +1326,147 @@ fn get_speed_for_highway(highway: &str) -> f64 {
     kmh * 1000.0 / 3600.0
 }
 
+/// Index of a highway type in [`ROUTABLE_HIGHWAYS`].
+fn road_class(highway: Option<&str>) -> u8 {
+    highway
+        .and_then(|h| ROUTABLE_HIGHWAYS.iter().position(|&r| r == h))
+        .map_or(UNKNOWN_ROAD_CLASS, |i| i as u8)
+}
+
+/// Returns speed in m/s for a way: its `maxspeed` if it can be parsed,
+/// otherwise the highway default.
+fn get_speed_for_way(tags: Option<&OsmTags>) -> f64 {
+    let highway = tags
+        .and_then(|t| t.highway.as_deref())
+        .unwrap_or("residential");
+    tags.and_then(|t| t.maxspeed.as_deref())
+        .and_then(parse_maxspeed)
+        .map(|kmh| kmh * 1000.0 / 3600.0)
+        .unwrap_or_else(|| get_speed_for_highway(highway))
+}
+
+const KMH_PER_MPH: f64 = 1.609344;
+const KMH_PER_KNOT: f64 = 1.852;
+
+/// Parses an OSM `maxspeed` value into km/h.
+///
+/// Understands plain numbers (km/h), `mph` and `knots` units, implicit zone
+/// values such as `DE:urban`, `RU:rural` or `DE:zone30`, and `walk`. Values
+/// without a usable limit (`none`, `signals`, `variable`) return `None` so
+/// the highway default applies. Lists like `50;30` use the first value.
+fn parse_maxspeed(value: &str) -> Option<f64> {
+    let value = value.split(';').next()?.trim();
+    if let Some((country, zone)) = value.split_once(':') {
+        return zone_speed(country.trim(), zone.trim());
+    }
+    if value == "walk" {
+        return Some(7.0);
+    }
+
+    let split = value
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(value.len());
+    let number: f64 = value[..split].parse().ok()?;
+    let factor = match value[split..].trim() {
+        "" | "km/h" | "kmh" | "kph" => 1.0,
+        "mph" => KMH_PER_MPH,
+        "knots" => KMH_PER_KNOT,
+        _ => return None,
+    };
+    (number > 0.0).then_some(number * factor)
+}
+
+/// Implicit speed limit of a zone value (`<country>:<zone>`) in km/h.
+fn zone_speed(country: &str, zone: &str) -> Option<f64> {
+    // DE:zone30, DE:zone:30
+    if let Some(limit) = zone.strip_prefix("zone") {
+        return parse_maxspeed(limit.trim_start_matches(':'));
+    }
+    match (country, zone) {
+        (_, "walk" | "living_street") => Some(7.0),
+        (_, "bicycle_road") => Some(30.0),
+        (_, "urban") => Some(50.0),
+        ("GB", "nsl_single") => Some(60.0 * KMH_PER_MPH),
+        ("GB", "nsl_dual" | "motorway") => Some(70.0 * KMH_PER_MPH),
+        ("DE" | "AT", "rural") => Some(100.0),
+        ("FR", "rural") => Some(80.0),
+        (_, "rural") => Some(90.0),
+        (_, "trunk") => Some(100.0),
+        // No general limit on German motorways
+        ("DE", "motorway") => None,
+        ("RU", "motorway") => Some(110.0),
+        (_, "motorway") => Some(120.0),
+        _ => None,
+    }
+}
+
+/// Whether motor vehicles may use a way, judged by the most specific access
+/// tag present (`motorcar`, `motor_vehicle`, `vehicle`, then `access`).
+fn is_accessible(tags: &OsmTags) -> bool {
+    [
+        &tags.motorcar,
+        &tags.motor_vehicle,
+        &tags.vehicle,
+        &tags.access,
+    ]
+    .into_iter()
+    .find_map(|t| t.as_deref())
+    .is_none_or(|v| {
+        !matches!(
+            v,
+            "no" | "private" | "agricultural" | "forestry" | "emergency"
+        )
+    })
+}
+
+/// Directions in which a way may be travelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Both,
+    Forward,
+    Backward,
+}
+
+/// Travel direction from `oneway`, with roundabouts and motorways implying
+/// `oneway=yes`.
+fn way_direction(tags: Option<&OsmTags>) -> Direction {
+    let Some(tags) = tags else {
+        return Direction::Both;
+    };
+    match tags.oneway.as_deref() {
+        Some("yes" | "1" | "true") => Direction::Forward,
+        Some("-1" | "reverse") => Direction::Backward,
+        Some("no" | "0" | "false") => Direction::Both,
+        _ => {
+            let implied = matches!(tags.junction.as_deref(), Some("roundabout" | "circular"))
+                || tags.highway.as_deref() == Some("motorway");
+            if implied {
+                Direction::Forward
+            } else {
+                Direction::Both
+            }
+        }
+    }
+}
+
+/// Position of a coordinate on the unit sphere.
+fn unit_vector(lat: f64, lng: f64) -> [f64; 3] {
+    let (lat, lng) = (lat.to_radians(), lng.to_radians());
+    [lat.cos() * lng.cos(), lat.cos() * lng.sin(), lat.sin()]
+}
+
 /// Haversine distance between two points in meters.
 fn haversine_distance(lat1: f64, lng1: f64, lat2: f64, lng2: f64) -> f64 {
-    const R: f64 = 6_371_000.0; // Earth radius in meters
-
     let lat1_rad = lat1.to_radians();
     let lat2_rad = lat2.to_radians();
     let dlat = (lat2 - lat1).to_radians();
     let dlng = (lng2 - lng1).to_radians();
 
-    let a = (dlat / 2.0).sin().powi(2)
-        + lat1_rad.cos() * lat2_rad.cos() * (dlng / 2.0).sin().powi(2);
+    let a =
+        (dlat / 2.0).sin().powi(2) + lat1_rad.cos() * lat2_rad.cos() * (dlng / 2.0).sin().powi(2);
     let c = 2.0 * a.sqrt().asin();
 
-    R * c
+    EARTH_RADIUS_M * c
 }
 
 #[cfg(test)]
@@ -819,4 +1506,388 @@ mod tests {
         let network = RoadNetwork::new();
         assert!(network.snap_to_road(39.95, -75.16).is_none());
     }
+
+    #[test]
+    fn test_snap_to_road_matches_linear_scan() {
+        let mut network = RoadNetwork::new();
+        for i in 0..20 {
+            for j in 0..20 {
+                network.get_or_create_node(39.90 + i as f64 * 0.005, -75.20 + j as f64 * 0.005);
+            }
+        }
+        network.rebuild_spatial_index();
+
+        for &(lat, lng) in &[
+            (39.9512, -75.1633),
+            (39.9001, -75.1049),
+            (39.9977, -75.1999),
+        ] {
+            let expected = network
+                .graph
+                .node_indices()
+                .min_by_key(|&idx| {
+                    let node = &network.graph[idx];
+                    OrderedFloat(haversine_distance(lat, lng, node.lat, node.lng))
+                })
+                .unwrap();
+            assert_eq!(network.snap_to_road(lat, lng), Some(expected));
+        }
+    }
+
+    /// 5x5 grid of two-way residential streets plus a oneway primary
+    /// shortcut along the bottom row.
+    fn grid_network() -> RoadNetwork {
+        let mut elements = Vec::new();
+        let id = |r: i64, c: i64| r * 10 + c;
+        for r in 0..5 {
+            for c in 0..5 {
+                elements.push(OsmElement {
+                    elem_type: "node".into(),
+                    id: id(r, c),
+                    lat: Some(39.95 + r as f64 * 0.002),
+                    lon: Some(-75.16 + c as f64 * 0.002),
+                    nodes: None,
+                    members: None,
+                    tags: None,
+                });
+            }
+        }
+        let mut way = |way_id: i64, nodes: Vec<i64>, highway: &str, oneway: bool| {
+            elements.push(OsmElement {
+                elem_type: "way".into(),
+                id: way_id,
+                lat: None,
+                lon: None,
+                nodes: Some(nodes),
+                members: None,
+                tags: Some(OsmTags {
+                    highway: Some(highway.into()),
+                    oneway: oneway.then(|| "yes".into()),
+                    ..Default::default()
+                }),
+            });
+        };
+        for k in 0..5 {
+            way(
+                100 + k,
+                (0..5).map(|c| id(k, c)).collect(),
+                "residential",
+                false,
+            );
+            way(
+                200 + k,
+                (0..5).map(|r| id(r, k)).collect(),
+                "residential",
+                false,
+            );
+        }
+        way(300, (0..5).map(|c| id(0, c)).collect(), "primary", true);
+        RoadNetwork::build_from_osm(&OverpassResponse { elements }).unwrap()
+    }
+
+    #[test]
+    fn test_compute_routes_matches_pairwise_search() {
+        let network = grid_network();
+        let locations = [
+            (39.95, -75.16),
+            (39.958, -75.152),
+            (39.95, -75.152),
+            (39.954, -75.156),
+        ];
+
+        let mut calls = 0;
+        let (matrices, geometries) =
+            network.compute_routes_with_progress(&locations, |_, _| calls += 1);
+        assert_eq!(calls, locations.len());
+
+        for i in 0..locations.len() {
+            for j in 0..locations.len() {
+                if i == j {
+                    continue;
+                }
+                let route = network.route(locations[i], locations[j]).unwrap();
+                assert_eq!(
+                    matrices.times[i][j], route.duration_seconds,
+                    "time {}->{}",
+                    i, j
+                );
+                let geometry = &geometries[&(i, j)];
+                assert_eq!(geometry.first(), route.geometry.first());
+                assert_eq!(geometry.last(), route.geometry.last());
+            }
+        }
+
+        // The oneway shortcut is only used eastbound
+        assert!(matrices.times[0][2] < matrices.times[2][0]);
+    }
+
+    #[test]
+    fn test_speed_factors_by_road_class() {
+        let network = grid_network();
+        let locations = [
+            (39.958, -75.16),
+            (39.958, -75.152),
+            (39.95, -75.16),
+            (39.95, -75.152),
+        ];
+        let free_flow = network.compute_matrices(&locations);
+
+        // Residential streets at half speed: the top row takes twice as long,
+        // the primary shortcut along the bottom row is unaffected
+        let mut factors = SpeedFactors {
+            by_class: vec![1.0; ROUTABLE_HIGHWAYS.len()],
+            default: 1.0,
+        };
+        factors.by_class[road_class(Some("residential")) as usize] = 0.5;
+        let congested = network.compute_matrices_with_speed_factors(&locations, &factors);
+        assert!((congested.times[0][1] - 2 * free_flow.times[0][1]).abs() <= 1);
+        assert_eq!(congested.times[2][3], free_flow.times[2][3]);
+
+        // Classes without a factor of their own fall back to the default
+        let slow_rest = SpeedFactors {
+            by_class: Vec::new(),
+            default: 0.5,
+        };
+        let congested = network.compute_matrices_with_speed_factors(&locations, &slow_rest);
+        assert!((congested.times[2][3] - 2 * free_flow.times[2][3]).abs() <= 1);
+    }
+
+    #[test]
+    fn test_snap_beyond_max_distance() {
+        let mut network = RoadNetwork::new();
+        network.get_or_create_node(39.95, -75.16);
+        network.rebuild_spatial_index();
+
+        // ~1.1 km north of the only node
+        assert!(network.snap_to_road(39.96, -75.16).is_none());
+        assert!(network.snap_within(39.96, -75.16, 2_000.0).is_some());
+
+        let matrices = network.compute_matrices(&[(39.95, -75.16), (39.96, -75.16)]);
+        assert_eq!(matrices.unsnapped, vec![1]);
+    }
+
+    fn node(id: i64, lat: f64, lon: f64) -> OsmElement {
+        OsmElement {
+            elem_type: "node".into(),
+            id,
+            lat: Some(lat),
+            lon: Some(lon),
+            nodes: None,
+            members: None,
+            tags: None,
+        }
+    }
+
+    fn way(id: i64, nodes: Vec<i64>, tags: OsmTags) -> OsmElement {
+        OsmElement {
+            elem_type: "way".into(),
+            id,
+            lat: None,
+            lon: None,
+            nodes: Some(nodes),
+            members: None,
+            tags: Some(OsmTags {
+                highway: Some("residential".into()),
+                ..tags
+            }),
+        }
+    }
+
+    #[test]
+    fn test_parse_maxspeed() {
+        assert_eq!(parse_maxspeed("50"), Some(50.0));
+        assert_eq!(parse_maxspeed("50 km/h"), Some(50.0));
+        assert!((parse_maxspeed("30 mph").unwrap() - 48.28).abs() < 0.01);
+        assert_eq!(parse_maxspeed("DE:urban"), Some(50.0));
+        assert_eq!(parse_maxspeed("RU:rural"), Some(90.0));
+        assert_eq!(parse_maxspeed("DE:zone30"), Some(30.0));
+        assert_eq!(parse_maxspeed("DE:zone:20"), Some(20.0));
+        assert_eq!(parse_maxspeed("walk"), Some(7.0));
+        assert_eq!(parse_maxspeed("60;40"), Some(60.0));
+        assert_eq!(parse_maxspeed("none"), None);
+        assert_eq!(parse_maxspeed("signals"), None);
+        assert_eq!(parse_maxspeed("DE:motorway"), None);
+    }
+
+    #[test]
+    fn test_access_oneway_and_maxspeed() {
+        let elements = vec![
+            node(1, 39.950, -75.160),
+            node(2, 39.951, -75.160),
+            node(3, 39.952, -75.160),
+            node(4, 39.953, -75.160),
+            node(5, 39.954, -75.160),
+            way(
+                10,
+                vec![1, 2],
+                OsmTags {
+                    maxspeed: Some("30 mph".into()),
+                    ..Default::default()
+                },
+            ),
+            way(
+                11,
+                vec![2, 3],
+                OsmTags {
+                    access: Some("private".into()),
+                    ..Default::default()
+                },
+            ),
+            way(
+                12,
+                vec![3, 4],
+                OsmTags {
+                    access: Some("no".into()),
+                    motor_vehicle: Some("yes".into()),
+                    ..Default::default()
+                },
+            ),
+            way(
+                13,
+                vec![4, 5],
+                OsmTags {
+                    oneway: Some("-1".into()),
+                    ..Default::default()
+                },
+            ),
+        ];
+        let network = RoadNetwork::build_from_osm(&OverpassResponse { elements }).unwrap();
+
+        // 1-2 and 3-4 both ways, 5->4 only; the private road is dropped
+        assert_eq!(network.edge_count(), 5);
+        assert!(network
+            .route((39.950, -75.160), (39.952, -75.160))
+            .is_none());
+        assert!(network
+            .route((39.953, -75.160), (39.954, -75.160))
+            .is_none());
+        assert!(network
+            .route((39.954, -75.160), (39.953, -75.160))
+            .is_some());
+
+        // ~111 m at 30 mph instead of the 30 km/h residential default
+        let route = network.route((39.950, -75.160), (39.951, -75.160)).unwrap();
+        assert_eq!(
+            route.duration_seconds,
+            (route.distance_meters / (30.0 * KMH_PER_MPH / 3.6)).round() as i64
+        );
+    }
+
+    #[test]
+    fn test_way_geometry_preserved() {
+        // A curved way 1-2-3-4-5 crossed by way 11 at 3
+        let curve = [
+            (39.95000, -75.16000),
+            (39.95010, -75.15990),
+            (39.95020, -75.15970),
+            (39.95030, -75.15940),
+            (39.95035, -75.15900),
+        ];
+        let mut elements: Vec<OsmElement> = curve
+            .iter()
+            .enumerate()
+            .map(|(i, &(lat, lon))| node(i as i64 + 1, lat, lon))
+            .collect();
+        elements.push(node(6, 39.95100, -75.15970));
+        elements.push(way(10, vec![1, 2, 3, 4, 5], OsmTags::default()));
+        elements.push(way(11, vec![3, 6], OsmTags::default()));
+        let network = RoadNetwork::build_from_osm(&OverpassResponse { elements }).unwrap();
+
+        // Every node of the curve stays a snapping target
+        assert_eq!(network.node_count(), 6);
+        assert_eq!(network.edge_count(), 10);
+
+        let forward = network.route(curve[0], curve[4]).unwrap();
+        assert_eq!(forward.geometry, curve);
+        let expected: f64 = curve
+            .windows(2)
+            .map(|w| haversine_distance(w[0].0, w[0].1, w[1].0, w[1].1))
+            .sum();
+        assert!((forward.distance_meters - expected).abs() < 1e-6);
+
+        // The way back follows the same points and survives the cache
+        let cached = RoadNetwork::from_cached(&network.to_cached());
+        let back = cached.route(curve[4], curve[0]).unwrap();
+        let reversed: Vec<_> = curve.iter().rev().copied().collect();
+        assert_eq!(back.geometry, reversed);
+    }
+
+    #[test]
+    fn test_turn_restrictions() {
+        //   4 ------ 3
+        //   |        |
+        //   |        |
+        //   1 ------ 2 ------ 5
+        let base = || {
+            vec![
+                node(1, 39.950, -75.160),
+                node(2, 39.950, -75.159),
+                node(3, 39.951, -75.159),
+                node(4, 39.952, -75.160),
+                node(5, 39.950, -75.158),
+                way(10, vec![1, 2], OsmTags::default()),
+                way(11, vec![2, 3], OsmTags::default()),
+                way(12, vec![1, 4, 3], OsmTags::default()),
+                way(13, vec![2, 5], OsmTags::default()),
+            ]
+        };
+        let unrestricted =
+            RoadNetwork::build_from_osm(&OverpassResponse { elements: base() }).unwrap();
+        let direct = unrestricted
+            .route((39.950, -75.160), (39.951, -75.159))
+            .unwrap();
+        assert_eq!(direct.geometry.len(), 3);
+
+        let restriction = |id: i64, kind: &str, to: i64| OsmElement {
+            elem_type: "relation".into(),
+            id,
+            lat: None,
+            lon: None,
+            nodes: None,
+            members: Some(vec![
+                OsmMember {
+                    member_type: "way".into(),
+                    member_ref: 10,
+                    role: "from".into(),
+                },
+                OsmMember {
+                    member_type: "node".into(),
+                    member_ref: 2,
+                    role: "via".into(),
+                },
+                OsmMember {
+                    member_type: "way".into(),
+                    member_ref: to,
+                    role: "to".into(),
+                },
+            ]),
+            tags: Some(OsmTags {
+                relation_type: Some("restriction".into()),
+                restriction: Some(kind.into()),
+                ..Default::default()
+            }),
+        };
+
+        for relation in [
+            restriction(20, "no_left_turn", 11),
+            restriction(21, "only_straight_on", 13),
+        ] {
+            let mut elements = base();
+            elements.push(relation);
+            let network = RoadNetwork::build_from_osm(&OverpassResponse { elements }).unwrap();
+
+            // The left turn at 2 is forbidden: detour via 4
+            let detour = network.route((39.950, -75.160), (39.951, -75.159)).unwrap();
+            assert!(detour.duration_seconds > direct.duration_seconds);
+            assert!(detour.geometry.contains(&(39.952, -75.160)));
+
+            // Straight on is still allowed, and the restriction survives the cache
+            assert!(network
+                .route((39.950, -75.160), (39.950, -75.158))
+                .is_some());
+            let cached = RoadNetwork::from_cached(&network.to_cached());
+            let matrices = cached.compute_matrices(&[(39.950, -75.160), (39.951, -75.159)]);
+            assert_eq!(matrices.times[0][1], detour.duration_seconds);
+        }
+    }
 }
@@ -3,18 +3,20 @@
 //! Uses Late Acceptance local search with 3-opt moves for efficient route optimization.
 //! Direct score calculation with full solution access (no global state).
 
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock, RwLockWriteGuard};
 use rand::Rng;
 use solverforge::prelude::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::oneshot;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 use crate::console::{self, PhaseTimer};
 use crate::constraints::calculate_score;
 use crate::domain::VehicleRoutePlan;
+use crate::job_store::{JobRecord, JobRepository, MemoryJobRepository};
+use crate::metrics;
 
 /// Default solving time: 30 seconds.
 const DEFAULT_TIME_LIMIT_SECS: u64 = 30;
@@ -22,10 +24,29 @@ const DEFAULT_TIME_LIMIT_SECS: u64 = 30;
 /// Late acceptance history size.
 const LATE_ACCEPTANCE_SIZE: usize = 400;
 
+/// Minimum time between saving intermediate solutions to the job repository.
+const SAVE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Environment variable limiting the number of jobs solved at once.
+pub const MAX_CONCURRENT_ENV: &str = "SOLVER_MAX_CONCURRENT";
+
+/// Environment variable capping the solving time of every job, in seconds.
+pub const MAX_WALL_TIME_ENV: &str = "SOLVER_MAX_WALL_TIME_SECS";
+
+/// Environment variable setting how long finished jobs are kept, in seconds.
+pub const JOB_TTL_ENV: &str = "SOLVER_JOB_TTL_SECS";
+
+/// Default cap on the solving time of a job: 30 minutes.
+const DEFAULT_MAX_WALL_TIME_SECS: u64 = 30 * 60;
+
+/// Default time finished jobs are kept: 24 hours.
+const DEFAULT_JOB_TTL_SECS: u64 = 24 * 3600;
+
 /// Solver configuration with termination criteria.
 ///
 /// Multiple termination conditions combine with OR logic (any triggers termination).
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct SolverConfig {
     /// Stop after this duration.
     pub time_limit: Option<Duration>,
@@ -78,12 +99,62 @@ impl SolverConfig {
     }
 }
 
+/// Service-wide limits on solving.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolverLimits {
+    /// Jobs solved at the same time; further jobs wait in a FIFO queue.
+    pub max_concurrent: usize,
+    /// Upper bound on the solving time of every job, whatever its
+    /// termination config.
+    pub max_wall_time: Duration,
+    /// Finished jobs are evicted this long after they stopped solving.
+    pub job_ttl: Duration,
+}
+
+impl SolverLimits {
+    /// Reads the limits from [`MAX_CONCURRENT_ENV`], [`MAX_WALL_TIME_ENV`]
+    /// and [`JOB_TTL_ENV`], falling back to the defaults.
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let var = |name: &str| std::env::var(name).ok().and_then(|v| v.parse::<u64>().ok());
+        Self {
+            max_concurrent: var(MAX_CONCURRENT_ENV)
+                .map(|n| n.max(1) as usize)
+                .unwrap_or(defaults.max_concurrent),
+            max_wall_time: var(MAX_WALL_TIME_ENV)
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.max_wall_time),
+            job_ttl: var(JOB_TTL_ENV).map(Duration::from_secs).unwrap_or(defaults.job_ttl),
+        }
+    }
+
+    /// Applies the wall time cap to a job's termination config.
+    fn cap(&self, mut config: SolverConfig) -> SolverConfig {
+        config.time_limit = Some(config.time_limit.map_or(self.max_wall_time, |t| t.min(self.max_wall_time)));
+        config
+    }
+}
+
+impl Default for SolverLimits {
+    /// One job per available core, 30 minutes per job, finished jobs kept
+    /// for a day.
+    fn default() -> Self {
+        Self {
+            max_concurrent: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            max_wall_time: Duration::from_secs(DEFAULT_MAX_WALL_TIME_SECS),
+            job_ttl: Duration::from_secs(DEFAULT_JOB_TTL_SECS),
+        }
+    }
+}
+
 /// Status of a solving job.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum SolverStatus {
     /// Not currently solving.
     NotSolving,
+    /// Waiting in the queue for a free solver.
+    SolvingScheduled,
     /// Actively solving.
     Solving,
 }
@@ -95,14 +166,104 @@ impl SolverStatus {
     /// use vehicle_routing::solver::SolverStatus;
     ///
     /// assert_eq!(SolverStatus::NotSolving.as_str(), "NOT_SOLVING");
+    /// assert_eq!(SolverStatus::SolvingScheduled.as_str(), "SOLVING_SCHEDULED");
     /// assert_eq!(SolverStatus::Solving.as_str(), "SOLVING");
     /// ```
     pub fn as_str(self) -> &'static str {
         match self {
             SolverStatus::NotSolving => "NOT_SOLVING",
+            SolverStatus::SolvingScheduled => "SOLVING_SCHEDULED",
             SolverStatus::Solving => "SOLVING",
         }
     }
+
+    /// Whether the job is solving or queued to solve.
+    pub fn is_active(self) -> bool {
+        self != SolverStatus::NotSolving
+    }
+}
+
+/// Solver progress of a job.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SolveStats {
+    /// Moves evaluated, over all phases.
+    pub moves_evaluated: u64,
+    /// Steps accepted, over all phases.
+    pub steps_accepted: u64,
+    /// Best score found.
+    pub best_score: Option<HardSoftScore>,
+    /// Time spent solving.
+    pub solving_time: Duration,
+}
+
+impl SolveStats {
+    /// Average moves evaluated per second of solving.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use vehicle_routing::solver::SolveStats;
+    ///
+    /// let stats = SolveStats {
+    ///     moves_evaluated: 5000,
+    ///     solving_time: Duration::from_secs(2),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(stats.moves_per_second(), 2500.0);
+    /// assert_eq!(SolveStats::default().moves_per_second(), 0.0);
+    /// ```
+    pub fn moves_per_second(&self) -> f64 {
+        let secs = self.solving_time.as_secs_f64();
+        if secs > 0.0 {
+            self.moves_evaluated as f64 / secs
+        } else {
+            0.0
+        }
+    }
+}
+
+/// A new best score found while solving.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BestScorePoint {
+    /// Time since solving started.
+    pub elapsed: Duration,
+    /// Local search steps taken, including the one that found the score
+    /// (0 for the construction heuristic).
+    pub step: u64,
+    /// The new best score.
+    pub score: HardSoftScore,
+}
+
+/// Renders a best-score timeline as CSV, one row per new best score.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use solverforge::prelude::HardSoftScore;
+/// use vehicle_routing::solver::{history_csv, BestScorePoint};
+///
+/// let history = [BestScorePoint {
+///     elapsed: Duration::from_millis(1500),
+///     step: 42,
+///     score: HardSoftScore::of(-1, -3600),
+/// }];
+/// assert_eq!(
+///     history_csv(&history),
+///     "elapsedMs,step,hardScore,softScore\n1500,42,-1,-3600\n"
+/// );
+/// ```
+pub fn history_csv(history: &[BestScorePoint]) -> String {
+    let mut csv = String::from("elapsedMs,step,hardScore,softScore\n");
+    for point in history {
+        csv.push_str(&format!(
+            "{},{},{},{}\n",
+            point.elapsed.as_millis(),
+            point.step,
+            point.score.hard(),
+            point.score.soft()
+        ));
+    }
+    csv
 }
 
 /// A solving job with current state.
@@ -115,8 +276,18 @@ pub struct SolveJob {
     pub plan: VehicleRoutePlan,
     /// Solver configuration.
     pub config: SolverConfig,
+    /// The service stopped while this job was solving; it can be resumed.
+    pub interrupted: bool,
+    /// Progress of the current (or last) solve.
+    pub stats: SolveStats,
+    /// Best scores of the current (or last) solve, oldest first.
+    pub history: Vec<BestScorePoint>,
     /// Stop signal sender.
     stop_signal: Option<oneshot::Sender<()>>,
+    /// Set once the job is removed, so the solver stops saving it.
+    removed: bool,
+    /// When the job last stopped solving (or was loaded stopped).
+    finished_at: Option<Instant>,
 }
 
 impl SolveJob {
@@ -127,7 +298,12 @@ impl SolveJob {
             status: SolverStatus::NotSolving,
             plan,
             config: SolverConfig::default_config(),
+            interrupted: false,
+            stats: SolveStats::default(),
+            history: Vec::new(),
             stop_signal: None,
+            removed: false,
+            finished_at: None,
         }
     }
 
@@ -138,13 +314,36 @@ impl SolveJob {
             status: SolverStatus::NotSolving,
             plan,
             config,
+            interrupted: false,
+            stats: SolveStats::default(),
+            history: Vec::new(),
             stop_signal: None,
+            removed: false,
+            finished_at: None,
+        }
+    }
+
+    /// The job's state as stored in a [`JobRepository`].
+    pub fn record(&self) -> JobRecord {
+        JobRecord {
+            id: self.id.clone(),
+            status: self.status,
+            interrupted: self.interrupted,
+            score: self.plan.score,
+            config: self.config.clone(),
+            updated_at: chrono::Utc::now(),
         }
     }
 }
 
 /// Manages VRP solving jobs.
 ///
+/// Jobs are saved to a [`JobRepository`] when they are created, change
+/// status, and periodically while solving. At most
+/// [`SolverLimits::max_concurrent`] jobs solve at once; the others wait in a
+/// FIFO queue as [`SolverStatus::SolvingScheduled`]. Finished jobs are
+/// evicted once [`SolverLimits::job_ttl`] has passed.
+///
 /// # Examples
 ///
 /// ```
@@ -160,21 +359,111 @@ impl SolveJob {
 /// ```
 pub struct SolverService {
     jobs: RwLock<HashMap<String, Arc<RwLock<SolveJob>>>>,
+    repository: Arc<dyn JobRepository>,
+    limits: SolverLimits,
+    queue: Arc<SolveQueue>,
 }
 
 impl SolverService {
-    /// Creates a new solver service.
+    /// Creates a new solver service that keeps jobs in memory only.
     pub fn new() -> Self {
+        Self::with_repository(Arc::new(MemoryJobRepository::default()))
+    }
+
+    /// Creates a solver service backed by `repository`, reloading the jobs
+    /// stored in it.
+    ///
+    /// Jobs that were solving when the service stopped are loaded with
+    /// their best solution, marked as interrupted and left stopped until
+    /// they are resumed with [`start_solving`](Self::start_solving).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use vehicle_routing::demo_data::generate_philadelphia;
+    /// use vehicle_routing::job_store::{JobRepository, MemoryJobRepository};
+    /// use vehicle_routing::solver::{SolverService, SolverStatus};
+    ///
+    /// let repository: Arc<dyn JobRepository> = Arc::new(MemoryJobRepository::default());
+    /// let service = SolverService::with_repository(repository.clone());
+    /// let job = service.create_job("job-1".to_string(), generate_philadelphia());
+    /// job.write().status = SolverStatus::Solving;
+    /// repository.update(&job.read().record(), None).unwrap();
+    ///
+    /// // After a restart the job is back, stopped and marked as interrupted
+    /// let restarted = SolverService::with_repository(repository);
+    /// let job = restarted.get_job("job-1").unwrap();
+    /// assert_eq!(job.read().status, SolverStatus::NotSolving);
+    /// assert!(job.read().interrupted);
+    /// ```
+    pub fn with_repository(repository: Arc<dyn JobRepository>) -> Self {
+        let stored = repository.load_all().unwrap_or_else(|e| {
+            warn!("Failed to load stored jobs: {}", e);
+            Vec::new()
+        });
+
+        let mut jobs = HashMap::new();
+        let mut interrupted = 0;
+        for stored in stored {
+            let mut job = SolveJob::with_config(
+                stored.record.id.clone(),
+                stored.best_plan().clone(),
+                stored.record.config.clone(),
+            );
+            job.plan.score = stored.record.score.or(job.plan.score);
+            job.interrupted = stored.record.interrupted || stored.record.status.is_active();
+            job.finished_at = Some(Instant::now());
+            if job.interrupted {
+                interrupted += 1;
+                if stored.record.status.is_active() {
+                    save(repository.as_ref(), &job, None);
+                }
+            }
+            jobs.insert(job.id.clone(), Arc::new(RwLock::new(job)));
+        }
+        if !jobs.is_empty() {
+            info!(jobs = jobs.len(), interrupted, "Loaded stored jobs");
+        }
+
+        let limits = SolverLimits::default();
         Self {
-            jobs: RwLock::new(HashMap::new()),
+            jobs: RwLock::new(jobs),
+            queue: SolveQueue::new(limits.max_concurrent, repository.clone()),
+            repository,
+            limits,
         }
     }
 
+    /// Replaces the default [`SolverLimits`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use vehicle_routing::solver::{SolverLimits, SolverService};
+    ///
+    /// let service = SolverService::new().with_limits(SolverLimits {
+    ///     max_concurrent: 2,
+    ///     max_wall_time: Duration::from_secs(600),
+    ///     job_ttl: Duration::from_secs(3600),
+    /// });
+    /// assert_eq!(service.limits().max_concurrent, 2);
+    /// ```
+    pub fn with_limits(mut self, limits: SolverLimits) -> Self {
+        self.queue = SolveQueue::new(limits.max_concurrent, self.repository.clone());
+        self.limits = limits;
+        self
+    }
+
+    /// The service's solving limits.
+    pub fn limits(&self) -> &SolverLimits {
+        &self.limits
+    }
+
     /// Creates a new job for the given plan with default config.
     pub fn create_job(&self, id: String, plan: VehicleRoutePlan) -> Arc<RwLock<SolveJob>> {
-        let job = Arc::new(RwLock::new(SolveJob::new(id.clone(), plan)));
-        self.jobs.write().insert(id, job.clone());
-        job
+        self.insert_job(SolveJob::new(id, plan))
     }
 
     /// Creates a new job with custom config.
@@ -184,8 +473,16 @@ impl SolverService {
         plan: VehicleRoutePlan,
         config: SolverConfig,
     ) -> Arc<RwLock<SolveJob>> {
-        let job = Arc::new(RwLock::new(SolveJob::with_config(id.clone(), plan, config)));
-        self.jobs.write().insert(id, job.clone());
+        self.insert_job(SolveJob::with_config(id, plan, config))
+    }
+
+    fn insert_job(&self, job: SolveJob) -> Arc<RwLock<SolveJob>> {
+        self.evict_expired();
+        if let Err(e) = self.repository.create(&job.record(), &job.plan) {
+            warn!(job_id = %job.id, "Failed to store job: {}", e);
+        }
+        let job = Arc::new(RwLock::new(job));
+        self.jobs.write().insert(job.read().id.clone(), job.clone());
         job
     }
 
@@ -196,39 +493,96 @@ impl SolverService {
 
     /// Lists all job IDs.
     pub fn list_jobs(&self) -> Vec<String> {
+        self.evict_expired();
         self.jobs.read().keys().cloned().collect()
     }
 
-    /// Removes a job by ID.
+    /// 1-based position of a job in the solve queue, if it is waiting.
+    pub fn queue_position(&self, id: &str) -> Option<usize> {
+        self.queue.position(id)
+    }
+
+    /// Removes jobs that finished longer than [`SolverLimits::job_ttl`]
+    /// ago. Returns the number of jobs removed.
+    ///
+    /// Called whenever jobs are created or listed, and periodically by the
+    /// API server.
+    pub fn evict_expired(&self) -> usize {
+        let expired: Vec<String> = self
+            .jobs
+            .read()
+            .iter()
+            .filter(|(_, job)| {
+                let job = job.read();
+                !job.status.is_active() && job.finished_at.is_some_and(|t| t.elapsed() >= self.limits.job_ttl)
+            })
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        for id in &expired {
+            self.remove_job(id);
+        }
+        if !expired.is_empty() {
+            info!(jobs = expired.len(), "Evicted expired jobs");
+        }
+        expired.len()
+    }
+
+    /// Removes a job by ID, also from the solve queue and the repository.
     pub fn remove_job(&self, id: &str) -> Option<Arc<RwLock<SolveJob>>> {
-        self.jobs.write().remove(id)
+        self.queue.remove(id);
+        let job = self.jobs.write().remove(id)?;
+        // Under the job lock, so a solver thread can't save it again afterwards
+        let mut guard = job.write();
+        guard.removed = true;
+        if let Err(e) = self.repository.remove(id) {
+            warn!(job_id = %id, "Failed to delete stored job: {}", e);
+        }
+        drop(guard);
+        Some(job)
     }
 
-    /// Starts solving a job in the background.
-    pub fn start_solving(&self, job: Arc<RwLock<SolveJob>>) {
+    /// Queues a job for solving in the background. It starts as soon as
+    /// fewer than [`SolverLimits::max_concurrent`] jobs are solving.
+    ///
+    /// Fails with the job's status if it is already solving or queued; the
+    /// check and the status change happen under one lock, so concurrent
+    /// calls queue the job once.
+    pub fn start_solving(&self, job: Arc<RwLock<SolveJob>>) -> Result<(), SolverStatus> {
         let (tx, rx) = oneshot::channel();
-        let config = job.read().config.clone();
 
-        {
+        let (id, config) = {
             let mut job_guard = job.write();
-            job_guard.status = SolverStatus::Solving;
+            if job_guard.status.is_active() {
+                return Err(job_guard.status);
+            }
+            job_guard.status = SolverStatus::SolvingScheduled;
+            job_guard.interrupted = false;
+            job_guard.finished_at = None;
             job_guard.stop_signal = Some(tx);
-        }
-
-        let job_clone = job.clone();
+            save(self.repository.as_ref(), &job_guard, None);
+            (job_guard.id.clone(), self.limits.cap(job_guard.config.clone()))
+        };
 
-        tokio::task::spawn_blocking(move || {
-            solve_blocking(job_clone, rx, config);
+        self.queue.push(QueuedSolve {
+            id,
+            job,
+            stop_rx: rx,
+            config,
         });
+        Ok(())
     }
 
-    /// Stops a solving job.
+    /// Stops a solving job, or takes it out of the queue.
     pub fn stop_solving(&self, id: &str) -> bool {
+        self.queue.remove(id);
         if let Some(job) = self.get_job(id) {
             let mut job_guard = job.write();
             if let Some(stop_signal) = job_guard.stop_signal.take() {
                 let _ = stop_signal.send(());
                 job_guard.status = SolverStatus::NotSolving;
+                job_guard.finished_at = Some(Instant::now());
+                save(self.repository.as_ref(), &job_guard, None);
                 return true;
             }
         }
@@ -236,6 +590,92 @@ impl SolverService {
     }
 }
 
+/// A job waiting for a solver.
+struct QueuedSolve {
+    id: String,
+    job: Arc<RwLock<SolveJob>>,
+    stop_rx: oneshot::Receiver<()>,
+    config: SolverConfig,
+}
+
+/// FIFO queue feeding a bounded number of solver threads.
+///
+/// Lock order: the queue before any job, never the other way around.
+struct SolveQueue {
+    state: Mutex<QueueState>,
+    max_concurrent: usize,
+    repository: Arc<dyn JobRepository>,
+}
+
+#[derive(Default)]
+struct QueueState {
+    waiting: VecDeque<QueuedSolve>,
+    running: usize,
+}
+
+impl SolveQueue {
+    fn new(max_concurrent: usize, repository: Arc<dyn JobRepository>) -> Arc<Self> {
+        Arc::new(Self {
+            state: Mutex::new(QueueState::default()),
+            max_concurrent: max_concurrent.max(1),
+            repository,
+        })
+    }
+
+    fn push(self: &Arc<Self>, solve: QueuedSolve) {
+        self.state.lock().waiting.push_back(solve);
+        self.dispatch();
+    }
+
+    fn remove(&self, id: &str) -> bool {
+        let mut state = self.state.lock();
+        let before = state.waiting.len();
+        state.waiting.retain(|s| s.id != id);
+        state.waiting.len() != before
+    }
+
+    fn position(&self, id: &str) -> Option<usize> {
+        self.state.lock().waiting.iter().position(|s| s.id == id).map(|p| p + 1)
+    }
+
+    /// Starts waiting jobs while solver slots are free.
+    fn dispatch(self: &Arc<Self>) {
+        let mut state = self.state.lock();
+        while state.running < self.max_concurrent {
+            let Some(solve) = state.waiting.pop_front() else {
+                break;
+            };
+            state.running += 1;
+
+            {
+                let mut job_guard = solve.job.write();
+                job_guard.status = SolverStatus::Solving;
+                job_guard.stats = SolveStats::default();
+                job_guard.history.clear();
+                save(self.repository.as_ref(), &job_guard, None);
+                metrics::global().record_solve_started();
+            }
+
+            let slot = SolverSlot(self.clone());
+            tokio::task::spawn_blocking(move || {
+                solve_blocking(solve.job, solve.stop_rx, solve.config, slot.0.repository.clone());
+                drop(slot);
+            });
+        }
+    }
+}
+
+/// A running solve; frees its slot and starts the next job when dropped,
+/// even if the solver panics.
+struct SolverSlot(Arc<SolveQueue>);
+
+impl Drop for SolverSlot {
+    fn drop(&mut self) {
+        self.0.state.lock().running -= 1;
+        self.0.dispatch();
+    }
+}
+
 impl Default for SolverService {
     fn default() -> Self {
         Self::new()
@@ -247,7 +687,9 @@ fn solve_blocking(
     job: Arc<RwLock<SolveJob>>,
     mut stop_rx: oneshot::Receiver<()>,
     config: SolverConfig,
+    repository: Arc<dyn JobRepository>,
 ) {
+    let mut saver = JobSaver::new(repository);
     let mut solution = job.read().plan.clone();
     let job_id = job.read().id.clone();
     let solve_start = Instant::now();
@@ -269,7 +711,18 @@ fn solve_blocking(
     // Phase 1: Construction heuristic (round-robin)
     let mut ch_timer = PhaseTimer::start("ConstructionHeuristic", 0);
     let mut current_score = construction_heuristic(&mut solution, &mut ch_timer);
+    let construction = SolveStats {
+        moves_evaluated: ch_timer.moves_evaluated(),
+        steps_accepted: ch_timer.steps_accepted(),
+        best_score: Some(current_score),
+        solving_time: solve_start.elapsed(),
+    };
     ch_timer.finish();
+    let mut history = vec![BestScorePoint {
+        elapsed: construction.solving_time,
+        step: 0,
+        score: current_score,
+    }];
 
     // Print solving started after construction
     console::print_solving_started(
@@ -281,7 +734,7 @@ fn solve_blocking(
     );
 
     // Update job with constructed solution
-    update_job(&job, &solution, current_score);
+    update_job(&job, &solution, current_score, construction, &history, &mut saver);
 
     // Phase 2: Late Acceptance local search with 3-opt
     let n_vehicles = solution.vehicles.len();
@@ -294,16 +747,20 @@ fn solve_blocking(
             &current_score.to_string(),
             current_score.is_feasible(),
         );
-        finish_job(&job, &solution, current_score);
+        finish_job(&job, &solution, current_score, construction, &history, &mut saver);
         return;
     }
 
+    let has_pairs = !solution.pickup_delivery_pairs().is_empty();
+    let has_optional = solution.visits.iter().any(|v| v.optional);
     let mut ls_timer = PhaseTimer::start("LateAcceptance", 1);
     let mut late_scores = vec![current_score; LATE_ACCEPTANCE_SIZE];
     let mut step: u64 = 0;
     let mut rng = rand::thread_rng();
 
-    // Track best score and improvement times
+    // Track best solution and improvement times; late acceptance may leave
+    // the current solution worse than the best, so the best is what is saved
+    let mut best_solution = solution.clone();
     let mut best_score = current_score;
     let mut last_improvement_time = solve_start;
     let mut last_improvement_step: u64 = 0;
@@ -325,8 +782,19 @@ fn solve_blocking(
             break;
         }
 
-        // Alternate between list-change and 2-opt moves
-        let accepted = if step % 3 == 0 {
+        // Alternate between list-change, 2-opt, pair-relocate and (un)assign
+        // moves; the offsets keep the (un)assign and pair steps apart from 2-opt
+        let accepted = if has_optional && (step + 3).is_multiple_of(5) {
+            // Unassign an optional visit or reassign a dropped one
+            if rng.gen_bool(0.5) {
+                try_unassign_move(&mut solution, &mut current_score, &late_scores, step, &mut rng, &mut ls_timer)
+            } else {
+                try_reassign_move(&mut solution, &mut current_score, &late_scores, step, &mut rng, &mut ls_timer)
+            }
+        } else if has_pairs && (step + 3).is_multiple_of(4) {
+            // Pair relocate move (pickup and delivery together)
+            try_pair_relocate_move(&mut solution, &mut current_score, &late_scores, step, &mut rng, &mut ls_timer)
+        } else if step.is_multiple_of(3) {
             // 2-opt move (intra-route segment reversal)
             try_two_opt_move(&mut solution, &mut current_score, &late_scores, step, &mut rng, &mut ls_timer)
         } else {
@@ -341,14 +809,21 @@ fn solve_blocking(
 
             // Track improvements
             if current_score > best_score {
+                best_solution = solution.clone();
                 best_score = current_score;
                 last_improvement_time = Instant::now();
                 last_improvement_step = step;
+                history.push(BestScorePoint {
+                    elapsed: last_improvement_time - solve_start,
+                    step: step + 1,
+                    score: best_score,
+                });
             }
 
             // Periodic update
             if ls_timer.steps_accepted().is_multiple_of(1000) {
-                update_job(&job, &solution, current_score);
+                let stats = progress(&construction, &ls_timer, best_score, solve_start);
+                update_job(&job, &best_solution, best_score, stats, &history, &mut saver);
                 debug!(
                     step,
                     moves_accepted = ls_timer.steps_accepted(),
@@ -372,6 +847,7 @@ fn solve_blocking(
         step += 1;
     }
 
+    let stats = progress(&construction, &ls_timer, best_score, solve_start);
     ls_timer.finish();
 
     let total_duration = solve_start.elapsed();
@@ -381,8 +857,8 @@ fn solve_blocking(
         job_id = %job_id,
         duration_secs = total_duration.as_secs_f64(),
         steps = step,
-        score = %current_score,
-        feasible = current_score.is_feasible(),
+        score = %best_score,
+        feasible = best_score.is_feasible(),
         "Solving complete"
     );
 
@@ -390,15 +866,31 @@ fn solve_blocking(
         total_duration,
         total_moves,
         2,
-        &current_score.to_string(),
-        current_score.is_feasible(),
+        &best_score.to_string(),
+        best_score.is_feasible(),
     );
 
-    finish_job(&job, &solution, current_score);
+    finish_job(&job, &best_solution, best_score, stats, &history, &mut saver);
+}
+
+/// Solver progress after the construction phase and `timer`'s local search.
+fn progress(construction: &SolveStats, timer: &PhaseTimer, best_score: HardSoftScore, start: Instant) -> SolveStats {
+    SolveStats {
+        moves_evaluated: construction.moves_evaluated + timer.moves_evaluated(),
+        steps_accepted: construction.steps_accepted + timer.steps_accepted(),
+        best_score: Some(best_score),
+        solving_time: start.elapsed(),
+    }
 }
 
 /// Construction heuristic: round-robin visit assignment.
 ///
+/// A pickup is assigned together with its paired delivery, which is placed
+/// right after it on the same vehicle. Visits skip vehicles they are not
+/// compatible with. When a visit overflows the vehicle, an unused reload
+/// stop is tried in front of it. Optional visits are only kept when they
+/// improve the score, so they stay unassigned rather than breaking hard
+/// constraints.
 /// Skips construction if all visits are already assigned (continue mode).
 fn construction_heuristic(solution: &mut VehicleRoutePlan, timer: &mut PhaseTimer) -> HardSoftScore {
     let n_visits = solution.visits.len();
@@ -418,29 +910,82 @@ fn construction_heuristic(solution: &mut VehicleRoutePlan, timer: &mut PhaseTime
     }
 
     // Build set of already-assigned visits
-    let assigned: std::collections::HashSet<usize> = solution
+    let mut assigned: HashSet<usize> = solution
         .vehicles
         .iter()
         .flat_map(|v| v.visits.iter().copied())
         .collect();
+    let mut dropped: HashSet<usize> = HashSet::new();
+    let mut score = calculate_score(solution);
 
     // Round-robin assignment for unassigned visits only
     let mut vehicle_idx = 0;
     for visit_idx in 0..n_visits {
-        if assigned.contains(&visit_idx) {
+        if assigned.contains(&visit_idx) || dropped.contains(&visit_idx) {
             continue;
         }
 
+        // Paired deliveries follow their pickup, and vice versa
+        let mut group = solution.visit_group(visit_idx);
+        group.retain(|idx| !assigned.contains(idx));
+
+        // Prefer the next compatible vehicle in round-robin order
+        let target = (0..n_vehicles)
+            .map(|offset| (vehicle_idx + offset) % n_vehicles)
+            .find(|&v| {
+                let vehicle = &solution.vehicles[v];
+                group
+                    .iter()
+                    .all(|&idx| solution.visits[idx].is_compatible_with(vehicle))
+            })
+            .unwrap_or(vehicle_idx);
+
         timer.record_move();
-        solution.vehicles[vehicle_idx].visits.push(visit_idx);
+        for &idx in &group {
+            solution.vehicles[target].visits.push(idx);
+        }
+
+        let mut new_score = calculate_score(solution);
+
+        // Try starting a new trip before the visit with an unused reload stop
+        let target_id = solution.vehicles[target].id;
+        let reload = (0..n_visits).find(|idx| {
+            let visit = &solution.visits[*idx];
+            visit.is_reload() && !assigned.contains(idx) && visit.allowed_vehicles == [target_id]
+        });
+        let mut reload_used = None;
+        if let Some(reload) = reload {
+            let route = &mut solution.vehicles[target].visits;
+            let pos = route.len() - group.len();
+            route.insert(pos, reload);
+            let reload_score = calculate_score(solution);
+            if reload_score > new_score {
+                new_score = reload_score;
+                reload_used = Some(reload);
+            } else {
+                solution.vehicles[target].visits.remove(pos);
+            }
+        }
+
+        let optional = group.iter().all(|&idx| solution.visits[idx].optional);
+        if optional && new_score < score {
+            // Leave optional visits out when serving them makes things worse
+            let route = &mut solution.vehicles[target].visits;
+            route.retain(|idx| !group.contains(idx) && Some(*idx) != reload_used);
+            dropped.extend(group);
+            continue;
+        }
+
+        assigned.extend(reload_used);
 
-        let score = calculate_score(solution);
+        assigned.extend(group);
+        score = new_score;
         timer.record_accepted(&score.to_string());
 
         vehicle_idx = (vehicle_idx + 1) % n_vehicles;
     }
 
-    calculate_score(solution)
+    score
 }
 
 /// Tries a list-change (visit relocation) move.
@@ -589,19 +1134,284 @@ fn try_two_opt_move<R: Rng>(
     }
 }
 
-/// Updates job with current solution.
-fn update_job(job: &Arc<RwLock<SolveJob>>, solution: &VehicleRoutePlan, score: HardSoftScore) {
+/// Tries a pair-relocate move: moves a pickup and its delivery together.
+///
+/// Both halves are removed from their routes and reinserted into a random
+/// vehicle with the pickup before the delivery.
+/// Returns true if the move was accepted.
+fn try_pair_relocate_move<R: Rng>(
+    solution: &mut VehicleRoutePlan,
+    current_score: &mut HardSoftScore,
+    late_scores: &[HardSoftScore],
+    step: u64,
+    rng: &mut R,
+    timer: &mut PhaseTimer,
+) -> bool {
+    let pairs = solution.pickup_delivery_pairs();
+    if pairs.is_empty() || solution.vehicles.is_empty() {
+        return false;
+    }
+
+    let (pickup, delivery) = pairs[rng.gen_range(0..pairs.len())];
+    let positions = solution.visit_positions();
+    let (Some(Some(p_pos)), Some(Some(d_pos))) = (positions.get(pickup), positions.get(delivery)) else {
+        return false;
+    };
+
+    let dst_vehicle = rng.gen_range(0..solution.vehicles.len());
+
+    // Snapshot affected routes for undo
+    let affected: Vec<usize> = {
+        let mut v = vec![p_pos.0, d_pos.0, dst_vehicle];
+        v.sort_unstable();
+        v.dedup();
+        v
+    };
+    let snapshot: Vec<Vec<usize>> = affected
+        .iter()
+        .map(|&i| solution.vehicles[i].visits.clone())
+        .collect();
+
+    // Remove both halves
+    for &i in &affected {
+        solution.vehicles[i].visits.retain(|&v| v != pickup && v != delivery);
+    }
+
+    // Reinsert with pickup before delivery
+    let dst_len = solution.vehicles[dst_vehicle].visits.len();
+    let pickup_pos = rng.gen_range(0..=dst_len);
+    let delivery_pos = rng.gen_range(pickup_pos..=dst_len) + 1;
+    solution.vehicles[dst_vehicle].visits.insert(pickup_pos, pickup);
+    solution.vehicles[dst_vehicle].visits.insert(delivery_pos, delivery);
+
+    timer.record_move();
+
+    // Evaluate
+    let new_score = calculate_score(solution);
+    let late_idx = (step as usize) % late_scores.len();
+    let late_score = late_scores[late_idx];
+
+    if new_score >= *current_score || new_score >= late_score {
+        // Accept
+        timer.record_accepted(&new_score.to_string());
+        *current_score = new_score;
+        true
+    } else {
+        // Reject - restore affected routes
+        for (&i, visits) in affected.iter().zip(snapshot) {
+            solution.vehicles[i].visits = visits;
+        }
+        false
+    }
+}
+
+/// Tries an unassign move: removes an optional visit (and its pair partner)
+/// from its route.
+/// Returns true if the move was accepted.
+fn try_unassign_move<R: Rng>(
+    solution: &mut VehicleRoutePlan,
+    current_score: &mut HardSoftScore,
+    late_scores: &[HardSoftScore],
+    step: u64,
+    rng: &mut R,
+    timer: &mut PhaseTimer,
+) -> bool {
+    let positions = solution.visit_positions();
+    let candidates: Vec<usize> = solution
+        .visits
+        .iter()
+        .filter(|v| v.optional && positions[v.index].is_some())
+        .map(|v| v.index)
+        .collect();
+
+    if candidates.is_empty() {
+        return false;
+    }
+
+    let group = solution.visit_group(candidates[rng.gen_range(0..candidates.len())]);
+    if !group.iter().all(|&idx| solution.visits[idx].optional) {
+        return false;
+    }
+
+    // Snapshot affected routes for undo
+    let mut affected: Vec<usize> = group.iter().filter_map(|&idx| positions[idx]).map(|(v, _)| v).collect();
+    affected.sort_unstable();
+    affected.dedup();
+    let snapshot: Vec<Vec<usize>> = affected
+        .iter()
+        .map(|&i| solution.vehicles[i].visits.clone())
+        .collect();
+
+    timer.record_move();
+
+    for &i in &affected {
+        solution.vehicles[i].visits.retain(|v| !group.contains(v));
+    }
+
+    // Evaluate
+    let new_score = calculate_score(solution);
+    let late_idx = (step as usize) % late_scores.len();
+    let late_score = late_scores[late_idx];
+
+    if new_score >= *current_score || new_score >= late_score {
+        // Accept
+        timer.record_accepted(&new_score.to_string());
+        *current_score = new_score;
+        true
+    } else {
+        // Reject - restore affected routes
+        for (&i, visits) in affected.iter().zip(snapshot) {
+            solution.vehicles[i].visits = visits;
+        }
+        false
+    }
+}
+
+/// Tries a reassign move: inserts an unassigned visit (and its unassigned
+/// pair partner) at a random position of a random compatible vehicle.
+/// Returns true if the move was accepted.
+fn try_reassign_move<R: Rng>(
+    solution: &mut VehicleRoutePlan,
+    current_score: &mut HardSoftScore,
+    late_scores: &[HardSoftScore],
+    step: u64,
+    rng: &mut R,
+    timer: &mut PhaseTimer,
+) -> bool {
+    let unassigned = solution.unassigned_visits();
+    if unassigned.is_empty() {
+        return false;
+    }
+
+    let mut group = solution.visit_group(unassigned[rng.gen_range(0..unassigned.len())]);
+    group.retain(|idx| unassigned.contains(idx));
+
+    let compatible: Vec<usize> = solution
+        .vehicles
+        .iter()
+        .enumerate()
+        .filter(|(_, v)| group.iter().all(|&idx| solution.visits[idx].is_compatible_with(v)))
+        .map(|(i, _)| i)
+        .collect();
+
+    if compatible.is_empty() {
+        return false;
+    }
+
+    let dst_vehicle = compatible[rng.gen_range(0..compatible.len())];
+
+    // Insert in group order (pickup before delivery)
+    let mut inserted_at = Vec::with_capacity(group.len());
+    let mut min_pos = 0;
+    for &idx in &group {
+        let pos = rng.gen_range(min_pos..=solution.vehicles[dst_vehicle].visits.len());
+        solution.vehicles[dst_vehicle].visits.insert(pos, idx);
+        inserted_at.push(pos);
+        min_pos = pos + 1;
+    }
+
+    timer.record_move();
+
+    // Evaluate
+    let new_score = calculate_score(solution);
+    let late_idx = (step as usize) % late_scores.len();
+    let late_score = late_scores[late_idx];
+
+    if new_score >= *current_score || new_score >= late_score {
+        // Accept
+        timer.record_accepted(&new_score.to_string());
+        *current_score = new_score;
+        true
+    } else {
+        // Reject - remove in reverse insertion order
+        for &pos in inserted_at.iter().rev() {
+            solution.vehicles[dst_vehicle].visits.remove(pos);
+        }
+        false
+    }
+}
+
+/// Updates job with the best solution so far and progress.
+fn update_job(
+    job: &Arc<RwLock<SolveJob>>,
+    solution: &VehicleRoutePlan,
+    score: HardSoftScore,
+    stats: SolveStats,
+    history: &[BestScorePoint],
+    saver: &mut JobSaver,
+) {
     let mut job_guard = job.write();
     job_guard.plan = solution.clone();
     job_guard.plan.score = Some(score);
+    record_stats(&mut job_guard, stats, history);
+    // Readers may proceed while the solution is written
+    saver.save(&RwLockWriteGuard::downgrade(job_guard), false);
 }
 
-/// Finishes job and sets status.
-fn finish_job(job: &Arc<RwLock<SolveJob>>, solution: &VehicleRoutePlan, score: HardSoftScore) {
+/// Finishes job with the best solution found and sets status.
+fn finish_job(
+    job: &Arc<RwLock<SolveJob>>,
+    solution: &VehicleRoutePlan,
+    score: HardSoftScore,
+    stats: SolveStats,
+    history: &[BestScorePoint],
+    saver: &mut JobSaver,
+) {
     let mut job_guard = job.write();
     job_guard.plan = solution.clone();
     job_guard.plan.score = Some(score);
+    record_stats(&mut job_guard, stats, history);
+    metrics::global().record_solve_completed();
     job_guard.status = SolverStatus::NotSolving;
+    job_guard.stop_signal = None;
+    job_guard.finished_at = Some(Instant::now());
+    saver.save(&RwLockWriteGuard::downgrade(job_guard), true);
+}
+
+/// Stores a job's progress and best scores found since the last update,
+/// and adds the increase to the global counters.
+fn record_stats(job: &mut SolveJob, stats: SolveStats, history: &[BestScorePoint]) {
+    metrics::global().record_progress(
+        stats.moves_evaluated.saturating_sub(job.stats.moves_evaluated),
+        stats.steps_accepted.saturating_sub(job.stats.steps_accepted),
+    );
+    job.stats = stats;
+    let recorded = job.history.len().min(history.len());
+    job.history.extend_from_slice(&history[recorded..]);
+}
+
+/// Saves a job's state (and solution, if given) unless it was removed.
+fn save(repository: &dyn JobRepository, job: &SolveJob, solution: Option<&VehicleRoutePlan>) {
+    if job.removed {
+        return;
+    }
+    if let Err(e) = repository.update(&job.record(), solution) {
+        warn!(job_id = %job.id, "Failed to save job: {}", e);
+    }
+}
+
+/// Saves the solutions of a running job, at most once per [`SAVE_INTERVAL`].
+struct JobSaver {
+    repository: Arc<dyn JobRepository>,
+    last_saved: Option<Instant>,
+}
+
+impl JobSaver {
+    fn new(repository: Arc<dyn JobRepository>) -> Self {
+        Self {
+            repository,
+            last_saved: None,
+        }
+    }
+
+    /// Saves the job's current plan; `force` ignores the interval.
+    fn save(&mut self, job: &SolveJob, force: bool) {
+        if !force && self.last_saved.is_some_and(|t| t.elapsed() < SAVE_INTERVAL) {
+            return;
+        }
+        save(self.repository.as_ref(), job, Some(&job.plan));
+        self.last_saved = Some(Instant::now());
+    }
 }
 
 #[cfg(test)]
@@ -622,4 +1432,208 @@ mod tests {
         assert_eq!(total_visits, 49); // Philadelphia has 49 visits
         assert!(score.hard() <= 0); // May have some violations
     }
+
+    #[test]
+    fn test_construction_keeps_pairs_together() {
+        use crate::domain::{Location, Vehicle, Visit};
+
+        let depot = Location::new(0, 39.95, -75.16);
+        let loc = Location::new(1, 39.96, -75.17);
+        let visits = vec![
+            Visit::new(0, "Delivery", loc.clone()).with_delivery_for(2),
+            Visit::new(1, "Other", loc.clone()),
+            Visit::new(2, "Pickup", loc).with_pickup_for(0),
+        ];
+        let vehicles = vec![
+            Vehicle::new(0, "A", 10, depot.clone()),
+            Vehicle::new(1, "B", 10, depot.clone()),
+        ];
+        let mut plan = VehicleRoutePlan::new("pairs", vec![depot], visits, vehicles);
+        plan.finalize();
+
+        let mut timer = PhaseTimer::start("ConstructionHeuristic", 0);
+        construction_heuristic(&mut plan, &mut timer);
+
+        assert_eq!(plan.vehicles[0].visits, vec![2, 0]);
+        assert_eq!(plan.vehicles[1].visits, vec![1]);
+        assert!(crate::constraints::calculate_pair_violations(&plan).is_empty());
+    }
+
+    #[test]
+    fn test_construction_drops_optional_visits_over_capacity() {
+        use crate::domain::{Location, Vehicle, Visit};
+
+        let depot = Location::new(0, 39.95, -75.16);
+        let loc = Location::new(1, 39.96, -75.17);
+        let visits = vec![
+            Visit::new(0, "Mandatory", loc.clone()).with_demand(8),
+            Visit::new(1, "Optional", loc.clone()).with_demand(5).with_optional(10),
+            Visit::new(2, "Small", loc).with_demand(2).with_optional(10),
+        ];
+        let vehicles = vec![Vehicle::new(0, "A", 10, depot.clone())];
+        let mut plan = VehicleRoutePlan::new("optional", vec![depot], visits, vehicles);
+        plan.finalize();
+
+        let mut timer = PhaseTimer::start("ConstructionHeuristic", 0);
+        let score = construction_heuristic(&mut plan, &mut timer);
+
+        assert_eq!(plan.vehicles[0].visits, vec![0, 2]);
+        assert_eq!(plan.unassigned_visits(), vec![1]);
+        assert!(score.is_feasible());
+    }
+
+    #[test]
+    fn test_construction_inserts_reloads() {
+        use crate::domain::{Location, Vehicle, Visit};
+
+        let depot = Location::new(0, 39.95, -75.16);
+        let loc = Location::new(1, 39.96, -75.17);
+        let visits = vec![
+            Visit::new(0, "A", loc.clone()).with_demand(20),
+            Visit::new(1, "B", loc.clone()).with_demand(20),
+            Visit::new(2, "C", loc).with_demand(20),
+        ];
+        let vehicles = vec![Vehicle::new(0, "Van", 30, depot.clone()).with_reloads(2, 600)];
+        let mut plan = VehicleRoutePlan::new("reloads", vec![depot], visits, vehicles);
+        plan.add_reload_stops();
+        plan.finalize();
+
+        let mut timer = PhaseTimer::start("ConstructionHeuristic", 0);
+        let score = construction_heuristic(&mut plan, &mut timer);
+
+        assert_eq!(plan.vehicles[0].visits, vec![0, 3, 1, 4, 2]);
+        assert_eq!(plan.reload_count(&plan.vehicles[0]), 2);
+        assert!(score.is_feasible());
+    }
+
+    fn limited_service(job_ttl: Duration) -> SolverService {
+        SolverService::new().with_limits(SolverLimits {
+            max_concurrent: 1,
+            max_wall_time: Duration::from_secs(60),
+            job_ttl,
+        })
+    }
+
+    async fn wait_for(condition: impl Fn() -> bool) {
+        for _ in 0..500 {
+            if condition() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("condition not met in time");
+    }
+
+    #[test]
+    fn test_wall_time_caps_time_limit() {
+        let limits = SolverLimits {
+            max_wall_time: Duration::from_secs(60),
+            ..SolverLimits::default()
+        };
+        let capped = |time_limit| limits.cap(SolverConfig { time_limit, ..Default::default() }).time_limit;
+        assert_eq!(capped(None), Some(Duration::from_secs(60)));
+        assert_eq!(capped(Some(Duration::from_secs(3600))), Some(Duration::from_secs(60)));
+        assert_eq!(capped(Some(Duration::from_secs(5))), Some(Duration::from_secs(5)));
+    }
+
+    #[tokio::test]
+    async fn test_queue_limits_concurrent_jobs() {
+        let service = limited_service(Duration::from_secs(3600));
+        let config = SolverConfig {
+            time_limit: Some(Duration::from_secs(30)),
+            ..Default::default()
+        };
+        let first = service.create_job_with_config("first".into(), generate_philadelphia(), config.clone());
+        let second = service.create_job_with_config("second".into(), generate_philadelphia(), config);
+        service.start_solving(first.clone()).unwrap();
+        service.start_solving(second.clone()).unwrap();
+
+        assert_eq!(first.read().status, SolverStatus::Solving);
+        assert_eq!(second.read().status, SolverStatus::SolvingScheduled);
+        assert_eq!(service.queue_position("second"), Some(1));
+
+        // Stopping the running job frees the slot for the queued one
+        assert!(service.stop_solving("first"));
+        wait_for(|| second.read().status == SolverStatus::Solving).await;
+        assert_eq!(service.queue_position("second"), None);
+        assert!(service.stop_solving("second"));
+    }
+
+    #[tokio::test]
+    async fn test_active_job_is_not_queued_twice() {
+        let service = limited_service(Duration::from_secs(3600));
+        let config = SolverConfig {
+            time_limit: Some(Duration::from_secs(30)),
+            ..Default::default()
+        };
+        let first = service.create_job_with_config("first".into(), generate_philadelphia(), config.clone());
+        let second = service.create_job_with_config("second".into(), generate_philadelphia(), config);
+        service.start_solving(first.clone()).unwrap();
+        service.start_solving(second.clone()).unwrap();
+
+        assert_eq!(service.start_solving(first), Err(SolverStatus::Solving));
+        assert_eq!(service.start_solving(second), Err(SolverStatus::SolvingScheduled));
+        assert_eq!(service.queue_position("second"), Some(1));
+
+        // The original stop signals still work
+        assert!(service.stop_solving("second"));
+        assert!(service.stop_solving("first"));
+    }
+
+    #[tokio::test]
+    async fn test_queued_job_can_be_stopped() {
+        let service = limited_service(Duration::from_secs(3600));
+        let config = SolverConfig {
+            time_limit: Some(Duration::from_secs(30)),
+            ..Default::default()
+        };
+        let first = service.create_job_with_config("first".into(), generate_philadelphia(), config.clone());
+        let second = service.create_job_with_config("second".into(), generate_philadelphia(), config);
+        service.start_solving(first).unwrap();
+        service.start_solving(second.clone()).unwrap();
+
+        assert!(service.stop_solving("second"));
+        assert_eq!(second.read().status, SolverStatus::NotSolving);
+        assert_eq!(service.queue_position("second"), None);
+        assert!(service.stop_solving("first"));
+    }
+
+    #[tokio::test]
+    async fn test_history_records_improvements() {
+        let service = SolverService::new();
+        let config = SolverConfig {
+            step_limit: Some(2000),
+            ..Default::default()
+        };
+        let job = service.create_job_with_config("history".into(), generate_philadelphia(), config);
+        service.start_solving(job.clone()).unwrap();
+        wait_for(|| job.read().status == SolverStatus::NotSolving).await;
+
+        let guard = job.read();
+        let history = &guard.history;
+        assert_eq!(history[0].step, 0);
+        assert!(history.windows(2).all(|w| w[1].score > w[0].score && w[1].elapsed >= w[0].elapsed));
+        assert!(history.iter().all(|p| p.step <= 2000));
+        assert_eq!(history.last().map(|p| p.score), guard.stats.best_score);
+
+        // The stored plan is the best one, not the last accepted one
+        assert_eq!(guard.plan.score, guard.stats.best_score);
+        assert_eq!(Some(calculate_score(&guard.plan)), guard.stats.best_score);
+    }
+
+    #[tokio::test]
+    async fn test_finished_jobs_expire() {
+        let service = limited_service(Duration::ZERO);
+        let config = SolverConfig {
+            step_limit: Some(10),
+            ..Default::default()
+        };
+        let job = service.create_job_with_config("done".into(), generate_philadelphia(), config);
+        service.start_solving(job.clone()).unwrap();
+        assert_eq!(service.list_jobs(), vec!["done".to_string()]);
+
+        wait_for(|| job.read().status == SolverStatus::NotSolving).await;
+        assert!(service.list_jobs().is_empty());
+        assert!(service.get_job("done").is_none());
+    }
 }
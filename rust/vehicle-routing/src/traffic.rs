@@ -0,0 +1,238 @@
+//! Time-of-day speed profiles.
+//!
+//! A [`SpeedProfile`] splits the day into hour buckets, each scaling driving
+//! speeds per highway class (e.g. primary roads at 60% during the morning
+//! rush). Providers turn it into one travel time matrix per bucket
+//! ([`TimeDependentTimes`]), and the plan picks the matrix by departure time.
+//! Without a profile the static matrix is used for every departure.
+
+use crate::routing::{SpeedFactors, ROUTABLE_HIGHWAYS};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+/// Seconds in a day.
+pub const SECONDS_PER_DAY: i64 = 86_400;
+
+fn default_factor() -> f64 {
+    1.0
+}
+
+/// Speed multipliers for the part of the day starting at `start_hour`.
+///
+/// A bucket lasts until the next bucket starts; the last one wraps around
+/// midnight to the first.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SpeedBucket {
+    /// First hour of the bucket (0-23).
+    pub start_hour: u32,
+    /// Speed multiplier per highway class (e.g. `{"primary": 0.6}`).
+    /// 1.0 is free flow, 0.5 doubles travel times.
+    #[serde(default)]
+    pub factors: HashMap<String, f64>,
+    /// Multiplier for classes not listed, and for providers that don't know
+    /// road classes (straight-line, matrix files, table services).
+    #[serde(default = "default_factor")]
+    pub default_factor: f64,
+}
+
+impl SpeedBucket {
+    /// A bucket scaling every road class by `factor`.
+    pub fn uniform(start_hour: u32, factor: f64) -> Self {
+        Self {
+            start_hour,
+            factors: HashMap::new(),
+            default_factor: factor,
+        }
+    }
+
+    /// Sets the multiplier for one highway class.
+    pub fn with_factor(mut self, highway: impl Into<String>, factor: f64) -> Self {
+        self.factors.insert(highway.into(), factor);
+        self
+    }
+
+    /// Speed multiplier for a highway class.
+    pub fn factor(&self, highway: &str) -> f64 {
+        self.factors.get(highway).copied().unwrap_or(self.default_factor)
+    }
+}
+
+/// Hour-of-day speed buckets.
+///
+/// # Examples
+///
+/// ```
+/// use vehicle_routing::traffic::{SpeedBucket, SpeedProfile};
+///
+/// let profile = SpeedProfile::new(vec![
+///     SpeedBucket::uniform(0, 1.0),
+///     SpeedBucket::uniform(7, 0.8).with_factor("primary", 0.5),
+///     SpeedBucket::uniform(10, 1.0),
+/// ]);
+/// assert!(profile.validate().is_ok());
+///
+/// // 7:30 falls in the rush hour bucket
+/// assert_eq!(profile.bucket_at(7 * 3600 + 1800), 1);
+/// assert_eq!(profile.buckets[1].factor("primary"), 0.5);
+/// assert_eq!(profile.buckets[1].factor("residential"), 0.8);
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SpeedProfile {
+    /// Buckets sorted by start hour (deserialized profiles should be
+    /// passed through [`SpeedProfile::new`]).
+    pub buckets: Vec<SpeedBucket>,
+}
+
+impl SpeedProfile {
+    /// Creates a profile, sorting the buckets by start hour.
+    pub fn new(mut buckets: Vec<SpeedBucket>) -> Self {
+        buckets.sort_by_key(|b| b.start_hour);
+        Self { buckets }
+    }
+
+    /// Checks that the profile has buckets with distinct start hours below
+    /// 24 and positive factors.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.buckets.is_empty() {
+            return Err("speed profile has no buckets".into());
+        }
+        let mut hours: Vec<u32> = self.buckets.iter().map(|b| b.start_hour).collect();
+        hours.sort_unstable();
+        if hours.windows(2).any(|w| w[0] == w[1]) {
+            return Err("speed profile has duplicate start hours".into());
+        }
+        if hours.iter().any(|&h| h >= 24) {
+            return Err("speed profile start hours must be below 24".into());
+        }
+        let mut factors = self
+            .buckets
+            .iter()
+            .flat_map(|b| b.factors.values().chain(std::iter::once(&b.default_factor)));
+        if factors.any(|f| !f.is_finite() || *f <= 0.0) {
+            return Err("speed profile factors must be positive".into());
+        }
+        Ok(())
+    }
+
+    /// Start of each bucket in seconds after midnight, in bucket order.
+    pub fn bucket_starts(&self) -> Vec<i64> {
+        self.buckets.iter().map(|b| b.start_hour as i64 * 3600).collect()
+    }
+
+    /// Index of the bucket covering `second_of_day`.
+    pub fn bucket_at(&self, second_of_day: i64) -> usize {
+        bucket_index(&self.bucket_starts(), second_of_day)
+    }
+
+    /// Speed multipliers of bucket `idx` per routable highway class, in
+    /// [`ROUTABLE_HIGHWAYS`] order, with the bucket's default for the rest.
+    pub fn class_factors(&self, idx: usize) -> SpeedFactors {
+        let bucket = &self.buckets[idx];
+        SpeedFactors {
+            by_class: ROUTABLE_HIGHWAYS.iter().map(|h| bucket.factor(h)).collect(),
+            default: bucket.default_factor,
+        }
+    }
+}
+
+/// Bucket covering `second_of_day`, given ascending bucket starts. Times
+/// before the first start belong to the last bucket (wrapping midnight).
+fn bucket_index(starts: &[i64], second_of_day: i64) -> usize {
+    let second = second_of_day.rem_euclid(SECONDS_PER_DAY);
+    match starts.partition_point(|&s| s <= second) {
+        0 => starts.len().saturating_sub(1),
+        n => n - 1,
+    }
+}
+
+/// Travel times per time-of-day bucket.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct TimeDependentTimes {
+    /// Start of each bucket in seconds after midnight, ascending.
+    #[serde(rename = "bucketStarts", alias = "bucket_starts")]
+    pub bucket_starts: Vec<i64>,
+    /// `times[bucket][from][to]` in seconds.
+    pub times: Vec<Vec<Vec<i64>>>,
+}
+
+impl TimeDependentTimes {
+    /// Scales a static matrix by each bucket's default factor.
+    ///
+    /// Used for providers that only know a single free-flow matrix.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use vehicle_routing::traffic::{SpeedBucket, SpeedProfile, TimeDependentTimes};
+    ///
+    /// let profile = SpeedProfile::new(vec![SpeedBucket::uniform(0, 1.0), SpeedBucket::uniform(8, 0.5)]);
+    /// let times = TimeDependentTimes::scaled(&[vec![0, 600], vec![600, 0]], &profile);
+    /// assert_eq!(times.travel_time(0, 1, 9 * 3600), Some(1200));
+    /// assert_eq!(times.travel_time(0, 1, 3 * 3600), Some(600));
+    /// ```
+    pub fn scaled(times: &[Vec<i64>], profile: &SpeedProfile) -> Self {
+        Self {
+            bucket_starts: profile.bucket_starts(),
+            times: profile
+                .buckets
+                .iter()
+                .map(|bucket| {
+                    times
+                        .iter()
+                        .map(|row| {
+                            row.iter()
+                                .map(|&t| (t as f64 / bucket.default_factor).round() as i64)
+                                .collect()
+                        })
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
+    /// Travel time from `from` to `to` when departing at `second_of_day`.
+    pub fn travel_time(&self, from: usize, to: usize, second_of_day: i64) -> Option<i64> {
+        let bucket = bucket_index(&self.bucket_starts, second_of_day);
+        self.times.get(bucket)?.get(from)?.get(to).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_wraps_midnight() {
+        let profile = SpeedProfile::new(vec![SpeedBucket::uniform(22, 1.2), SpeedBucket::uniform(6, 1.0)]);
+        assert_eq!(profile.buckets[0].start_hour, 6);
+        assert_eq!(profile.bucket_at(3 * 3600), 1);
+        assert_eq!(profile.bucket_at(6 * 3600), 0);
+        assert_eq!(profile.bucket_at(23 * 3600), 1);
+        // Next day and negative offsets wrap too
+        assert_eq!(profile.bucket_at(SECONDS_PER_DAY + 7 * 3600), 0);
+        assert_eq!(profile.bucket_at(-3600), 1);
+    }
+
+    #[test]
+    fn test_validate() {
+        let dup = SpeedProfile::new(vec![SpeedBucket::uniform(7, 1.0), SpeedBucket::uniform(7, 0.5)]);
+        assert!(dup.validate().is_err());
+        assert!(SpeedProfile::new(vec![]).validate().is_err());
+        assert!(SpeedProfile::new(vec![SpeedBucket::uniform(24, 1.0)]).validate().is_err());
+        let zero = SpeedProfile::new(vec![SpeedBucket::uniform(0, 1.0).with_factor("primary", 0.0)]);
+        assert!(zero.validate().is_err());
+    }
+
+    #[test]
+    fn test_class_factors() {
+        let profile = SpeedProfile::new(vec![SpeedBucket::uniform(0, 0.9).with_factor("motorway", 0.5)]);
+        let factors = profile.class_factors(0);
+        assert_eq!(factors.by_class.len(), ROUTABLE_HIGHWAYS.len());
+        assert_eq!(factors.by_class[0], 0.5); // motorway
+        assert_eq!(factors.by_class[5], 0.9); // residential
+        assert_eq!(factors.default, 0.9);
+    }
+}
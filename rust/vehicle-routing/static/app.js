@@ -7,6 +7,7 @@ let loadedRoutePlan = null;
 let newVisit = null;
 let visitMarker = null;
 let routeGeometries = null;  // Cache for encoded polyline geometries
+const COARSE_GEOMETRY_TOLERANCE_M = 25;  // Route simplification while solving
 let useRealRoads = true;     // Routing mode toggle state (default: real roads)
 const solveButton = $("#solveButton");
 const stopSolvingButton = $("#stopSolvingButton");
@@ -60,13 +61,14 @@ function decodePolyline(encoded) {
 
 /**
  * Fetch route geometries for the current schedule from the backend.
+ * @param {number} tolerance - Simplification tolerance in meters (0 = full detail)
  * @returns {Promise<Object|null>} The geometries object or null if unavailable
  */
-async function fetchRouteGeometries() {
+async function fetchRouteGeometries(tolerance = 0) {
   if (!scheduleId) return null;
 
   try {
-    const response = await fetch(`/route-plans/${scheduleId}/geometry`);
+    const response = await fetch(`/route-plans/${scheduleId}/geometry?tolerance=${tolerance}`);
     if (response.ok) {
       const data = await response.json();
       // Transform segments array into map: { vehicleId: [polyline] }
@@ -771,9 +773,10 @@ async function renderRouteLines(highlightedId = null) {
 
   if (!loadedRoutePlan) return;
 
-  // Fetch geometries during solving (routes change)
+  // Fetch geometries during solving (routes change); coarse shapes while
+  // routes are still changing, full detail once solving stops
   if (scheduleId) {
-    routeGeometries = await fetchRouteGeometries();
+    routeGeometries = await fetchRouteGeometries(optimizing ? COARSE_GEOMETRY_TOLERANCE_M : 0);
   }
 
   const visitByIdMap = new Map(loadedRoutePlan.visits.map(visit => [visit.id, visit]));